@@ -1,30 +1,46 @@
 use ail::{
-    widget::{Button, Widget},
-    Application, ApplicationEvent, Window,
+    layout::{Flex, Padding},
+    program::{Program, View},
+    widget::{Button, Label, Panel},
+    Window,
 };
-use aom::Object;
+use aom::ID;
 
 #[derive(Clone, Copy, Debug)]
 pub enum Message {
-    ButtonHovered,
+    Increment,
+    Decrement,
+}
+
+fn update(count: &mut i32, message: Message) {
+    match message {
+        Message::Increment => *count += 1,
+        Message::Decrement => *count -= 1,
+    }
+}
+
+fn view(count: &i32) -> View<Message> {
+    let mut label = Label::new("count");
+    label.set_text(count.to_string());
+    let mut increment = Button::new("increment");
+    increment.set_text("+1");
+    let mut decrement = Button::new("decrement");
+    decrement.set_text("-1");
+    let mut panel = Panel::new("panel");
+    panel.set_layout(Flex::column().padding(Padding::all(10)));
+    panel.push(label);
+    panel.push(increment);
+    panel.push(decrement);
+    View::new(panel)
+        .on_click(ID::from("increment"), Message::Increment)
+        .on_click(ID::from("decrement"), Message::Decrement)
 }
 
 fn main() {
-    let window = Window::new();
-    let mut button = Button::new("my_button");
-    let button_id = button.id();
-    button.set_text(button.id());
-    let app = Application::new(window, button);
-    app.run(|event, registry| match event {
-        ApplicationEvent::OnEvent(mes, id) => match mes {
-            ail::WidgetEvent::OnClick => {
-                if id == button_id {
-                    let button: &mut Box<Button> = registry.get_mut(id).unwrap();
-                    button.set_text("Hovered");
-                }
-            }
-            ail::WidgetEvent::OnHover => {}
-        },
-        _ => {}
-    });
+    let window = Window::builder()
+        .title("Counter")
+        .size(320, 160)
+        .build()
+        .expect("failed to open the window");
+    Program::new(window, 0, update, view).run();
 }
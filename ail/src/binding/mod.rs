@@ -0,0 +1,251 @@
+//! Observable values that widget properties follow
+//!
+//! A [`Signal`] holds a value shared by everyone holding a clone of it. [`Bindings`] tie a
+//! signal to a property of a widget: after every event, bindings whose signal was set in the
+//! meantime pass the new value to the widget and the window is redrawn.
+//!
+//! ```
+//! use ail::{binding::Signal, management::Handle, testing::Driver, widget::Button};
+//!
+//! let text = Signal::new(String::from("Save"));
+//! let button = Button::new("save");
+//! let handle = Handle::of(&button);
+//!
+//! let mut driver = Driver::new(button, 300, 80);
+//! driver.bindings().bind(&text, handle, |button, text| button.set_text(text));
+//! driver.step();
+//! assert_eq!(handle.get(&driver.registry()).unwrap().text(), "Save");
+//!
+//! text.set(String::from("Saved"));
+//! assert!(driver.step());
+//! assert_eq!(handle.get(&driver.registry()).unwrap().text(), "Saved");
+//! ```
+
+use std::{
+    cell::{Cell, RefCell},
+    fmt,
+    rc::Rc,
+};
+
+use crate::{
+    management::{Handle, WidgetRegistry},
+    widget::Widget,
+};
+
+/// A value that bindings follow
+///
+/// Clones share the same value, so a signal can be handed to the callbacks that change it
+/// and to the bindings that show it.
+pub struct Signal<T> {
+    inner: Rc<Inner<T>>,
+}
+
+struct Inner<T> {
+    value: RefCell<T>,
+    /// Incremented on every change
+    version: Cell<u64>,
+}
+
+impl<T> Signal<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            inner: Rc::new(Inner {
+                value: RefCell::new(value),
+                version: Cell::new(0),
+            }),
+        }
+    }
+
+    /// Obtains a copy of the value
+    pub fn get(&self) -> T
+    where
+        T: Clone,
+    {
+        self.inner.value.borrow().clone()
+    }
+
+    /// Calls `f` with the value without copying it
+    pub fn with<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        f(&self.inner.value.borrow())
+    }
+
+    pub fn set(&self, value: T) {
+        self.update(|old| *old = value);
+    }
+
+    /// Changes the value in place
+    pub fn update(&self, f: impl FnOnce(&mut T)) {
+        f(&mut self.inner.value.borrow_mut());
+        self.inner.version.set(self.inner.version.get() + 1);
+    }
+
+    fn version(&self) -> u64 {
+        self.inner.version.get()
+    }
+}
+
+impl<T> Clone for Signal<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T> Default for Signal<T>
+where
+    T: Default,
+{
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T> fmt::Debug for Signal<T>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Signal")
+            .field(&self.inner.value.borrow())
+            .finish()
+    }
+}
+
+struct Binding {
+    /// Version of the signal last passed to the widget, `None` before the first time
+    seen: Option<u64>,
+    version: Box<dyn Fn() -> u64>,
+    apply: Box<dyn FnMut(&mut WidgetRegistry) -> bool>,
+}
+
+/// The bindings of a window
+#[derive(Default)]
+pub struct Bindings {
+    bindings: Vec<Binding>,
+}
+
+impl Bindings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Passes the value of `signal` to `apply` together with the widget, now and whenever
+    /// the signal changes
+    ///
+    /// Nothing happens while the widget is not registered.
+    pub fn bind<T, W, F>(&mut self, signal: &Signal<T>, widget: Handle<W>, mut apply: F)
+    where
+        T: 'static,
+        W: Widget,
+        F: FnMut(&mut W, &T) + 'static,
+    {
+        let version = signal.clone();
+        let value = signal.clone();
+        self.bindings.push(Binding {
+            seen: None,
+            version: Box::new(move || version.version()),
+            apply: Box::new(move |registry| match widget.get_mut(registry) {
+                Some(widget) => {
+                    value.with(|value| apply(widget, value));
+                    true
+                }
+                None => false,
+            }),
+        });
+    }
+
+    /// Passes the signals that changed since the last call to their widgets
+    ///
+    /// Updated widgets are invalidated, so the window is redrawn once for all of them.
+    pub(crate) fn apply(&mut self, registry: &mut WidgetRegistry) {
+        for binding in &mut self.bindings {
+            let version = (binding.version)();
+            if binding.seen != Some(version) && (binding.apply)(registry) {
+                binding.seen = Some(version);
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Bindings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Bindings")
+            .field("bindings", &self.bindings.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        layout::Flex,
+        testing::Driver,
+        widget::{Button, Layout, Panel},
+        ApplicationEvent, ColorPair, MouseButton, Theme, WidgetEvent,
+    };
+
+    #[test]
+    fn callbacks_drive_bound_widgets() {
+        let count = Signal::new(0);
+        let enabled = Signal::new(true);
+        let colors = Signal::new(None::<ColorPair>);
+
+        let counter = Button::new("counter");
+        let target = Button::new("target");
+        let (counter_handle, target_handle) = (Handle::of(&counter), Handle::of(&target));
+        let mut panel = Panel::new("panel");
+        panel.set_layout(Flex::column());
+        panel.push(counter);
+        panel.push(target);
+
+        let mut driver = Driver::new(panel, 300, 120);
+        let bindings = driver.bindings();
+        bindings.bind(&count, target_handle, |button, count| {
+            button.set_text(format!("{} clicks", count))
+        });
+        bindings.bind(&enabled, target_handle, |button, enabled| {
+            button.set_enabled(*enabled)
+        });
+        bindings.bind(&colors, target_handle, |button, colors| {
+            button.set_colors(*colors)
+        });
+        driver.callback({
+            let (count, enabled, colors) = (count.clone(), enabled.clone(), colors.clone());
+            move |event, _| {
+                if let ApplicationEvent::OnEvent(WidgetEvent::OnClick, id) = event {
+                    if id == counter_handle.id() {
+                        count.update(|count| *count += 1);
+                        enabled.set(count.get() < 2);
+                        colors.set(Some(Theme::DARK.normal));
+                    }
+                }
+            }
+        });
+        driver.step();
+        let text = |driver: &Driver| {
+            let registry = driver.registry();
+            target_handle.get(&registry).unwrap().text().to_string()
+        };
+        assert_eq!(text(&driver), "0 clicks");
+
+        let before = driver.image().clone();
+        let area = counter_handle.get(&driver.registry()).unwrap().area()[0];
+        driver.click(area.x() as i32 + 5, area.y() as i32 + 5);
+        assert_eq!(text(&driver), "1 clicks");
+        assert!(driver.step());
+        assert_ne!(&before, driver.image());
+
+        // Once disabled, the target no longer reports clicks.
+        driver.click(area.x() as i32 + 5, area.y() as i32 + 5);
+        let target = target_handle.get(&driver.registry()).unwrap().area()[0];
+        driver.take_events();
+        driver.pointer_move(target.x() as i32 + 5, target.y() as i32 + 5);
+        driver.press(MouseButton::Left).release(MouseButton::Left);
+        assert!(!driver.take_events().iter().any(|event| matches!(
+            event,
+            ApplicationEvent::OnEvent(WidgetEvent::OnClick, id) if *id == target_handle.id()
+        )));
+    }
+}
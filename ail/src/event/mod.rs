@@ -2,7 +2,15 @@ use std::fmt::Debug;
 
 use aom::ID;
 
-use crate::{widget::Widget, ApplicationEvent};
+use winit::{
+    event::{ElementState, MouseScrollDelta, WindowEvent},
+    keyboard::NamedKey,
+};
+
+use crate::{
+    management::WidgetRegistry, widget::Widget, ApplicationEvent, Key, KeyCode, Modifiers,
+    MouseButton, WidgetEvent,
+};
 
 #[derive(Clone, Copy, Debug)]
 pub enum EventKind {
@@ -31,3 +39,439 @@ impl EventRequest {
         self.kind
     }
 }
+
+/// Pixels scrolled by touchpads that count as one line of a mouse wheel
+const PIXELS_PER_LINE: f64 = 20.0;
+
+/// Input delivered to the widgets of a window
+///
+/// Coordinates are in physical pixels relative to the upper left corner of the window.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Input {
+    CursorMoved {
+        x: i32,
+        y: i32,
+    },
+    CursorLeft,
+    MouseInput {
+        button: MouseButton,
+        pressed: bool,
+    },
+    /// The mouse wheel was turned by the given number of lines
+    Wheel {
+        dx: f32,
+        dy: f32,
+    },
+    Key {
+        key: Key,
+        code: KeyCode,
+        pressed: bool,
+        /// Whether the key is held and the press was generated by auto-repeat
+        repeat: bool,
+        /// Text produced by the press, if any
+        text: Option<String>,
+    },
+    /// The set of held modifier keys changed
+    Modifiers(Modifiers),
+    /// An input method composed or committed text
+    Ime(Ime),
+}
+
+/// Text from an input method, for languages typed with more keys than characters
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Ime {
+    /// The text composed so far, with the byte range the input method highlights
+    ///
+    /// An empty text ends the composition.
+    Preedit(String, Option<(usize, usize)>),
+    /// The composition was accepted and the text should be inserted
+    Commit(String),
+}
+
+impl Input {
+    /// Converts a winit window event, returning `None` for events that are not input
+    pub(crate) fn from_window_event(event: &WindowEvent) -> Option<Self> {
+        match event {
+            WindowEvent::CursorMoved { position, .. } => Some(Input::CursorMoved {
+                x: position.x as i32,
+                y: position.y as i32,
+            }),
+            WindowEvent::CursorLeft { .. } => Some(Input::CursorLeft),
+            WindowEvent::MouseInput { state, button, .. } => Some(Input::MouseInput {
+                button: *button,
+                pressed: *state == ElementState::Pressed,
+            }),
+            WindowEvent::MouseWheel { delta, .. } => {
+                let (dx, dy) = match delta {
+                    MouseScrollDelta::LineDelta(dx, dy) => (*dx, *dy),
+                    MouseScrollDelta::PixelDelta(position) => (
+                        (position.x / PIXELS_PER_LINE) as f32,
+                        (position.y / PIXELS_PER_LINE) as f32,
+                    ),
+                };
+                Some(Input::Wheel { dx, dy })
+            }
+            WindowEvent::KeyboardInput { event, .. } => Some(Input::Key {
+                key: event.logical_key.clone(),
+                code: match KeyCode::from(event.physical_key) {
+                    KeyCode::Unidentified => KeyCode::from_logical(&event.logical_key),
+                    code => code,
+                },
+                pressed: event.state == ElementState::Pressed,
+                repeat: event.repeat,
+                text: event.text.as_ref().map(|text| text.to_string()),
+            }),
+            WindowEvent::Ime(ime) => match ime {
+                winit::event::Ime::Preedit(text, cursor) => {
+                    Some(Input::Ime(Ime::Preedit(text.clone(), *cursor)))
+                }
+                winit::event::Ime::Commit(text) => Some(Input::Ime(Ime::Commit(text.clone()))),
+                winit::event::Ime::Disabled => Some(Input::Ime(Ime::Preedit(String::new(), None))),
+                winit::event::Ime::Enabled => None,
+            },
+            WindowEvent::ModifiersChanged(modifiers) => {
+                Some(Input::Modifiers(modifiers.state().into()))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A key press or release delivered to the focused widget
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyEvent {
+    /// The key with the keyboard layout and modifiers applied
+    pub key: Key,
+    /// The key's position on the keyboard, regardless of layout
+    pub code: KeyCode,
+    /// Modifier keys held when the key was pressed
+    pub modifiers: Modifiers,
+    /// Whether the press was generated by holding the key down
+    pub repeat: bool,
+}
+
+impl KeyEvent {
+    /// Creates the event of a first press of `key`, guessing its position from a US layout
+    pub fn new(key: Key, modifiers: Modifiers) -> Self {
+        Self {
+            code: KeyCode::from_logical(&key),
+            key,
+            modifiers,
+            repeat: false,
+        }
+    }
+}
+
+/// Turns [`Input`] into widget callbacks and [`WidgetEvent`]s
+///
+/// The dispatcher remembers where the cursor is and which widget is hovered or pressed,
+/// so a widget is only notified when its state actually changes.
+/// A click is reported when the left button is released over the widget it was pressed on.
+///
+/// Pressing the left button over a [focusable] widget gives it keyboard focus,
+/// and keys are delivered to the focused widget: every press and release as a [`KeyEvent`],
+/// followed by the printable text of the press, unless a shortcut modifier is held.
+/// Tab and Shift+Tab move focus through the focusable widgets in [Tab order], unless the
+/// focused widget [captures] them.
+/// The mouse wheel scrolls the hovered widget.
+///
+/// [focusable]: crate::widget::EventListener::focusable
+/// [Tab order]: crate::widget::EventListener::tab_index
+/// [captures]: crate::widget::EventListener::captures_tab
+#[derive(Debug, Default)]
+pub struct Dispatcher {
+    cursor: Option<(i32, i32)>,
+    hovered: Option<ID>,
+    pressed: Option<ID>,
+    focused: Option<ID>,
+    /// Whether focus was last moved from the keyboard, so it should be shown
+    focus_visible: bool,
+    modifiers: Modifiers,
+}
+
+impl Dispatcher {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Delivers `input` to the widget trees rooted at `ids`
+    ///
+    /// Widgets drawn later are on top and therefore win hit-testing.
+    pub fn dispatch(
+        &mut self,
+        input: Input,
+        registry: &mut WidgetRegistry,
+        ids: &[ID],
+    ) -> Vec<(ID, WidgetEvent)> {
+        self.retain(registry);
+        let mut events = vec![];
+        match input {
+            Input::CursorMoved { x, y } => {
+                self.cursor = Some((x, y));
+                if let Some(id) = self.pressed {
+                    registry.search_mut(id).on_drag(x, y);
+                    registry.invalidate(id);
+                }
+                let target = hit_test(registry, ids, x, y);
+                if target != self.hovered {
+                    if let Some(id) = self.hovered {
+                        registry.search_mut(id).unfocus();
+                        registry.repaint(id);
+                    }
+                    if let Some(id) = target {
+                        let widget = registry.search_mut(id);
+                        if self.pressed == Some(id) {
+                            widget.on_click();
+                        } else {
+                            widget.on_hover();
+                        }
+                        registry.repaint(id);
+                        events.push((id, WidgetEvent::OnHover));
+                    }
+                    self.hovered = target;
+                }
+            }
+            Input::CursorLeft => {
+                self.cursor = None;
+                if let Some(id) = self.hovered.take() {
+                    registry.search_mut(id).unfocus();
+                    registry.repaint(id);
+                }
+            }
+            Input::MouseInput {
+                button: MouseButton::Left,
+                pressed: true,
+            } => {
+                let focus = self.hovered.filter(|id| registry.search(id).focusable());
+                events.extend(self.focus(focus, registry));
+                self.focus_visible = false;
+                if let Some(id) = self.hovered {
+                    let widget = registry.search_mut(id);
+                    widget.on_click();
+                    if let Some((x, y)) = self.cursor {
+                        widget.on_press(x, y);
+                    }
+                    self.pressed = Some(id);
+                    registry.repaint(id);
+                }
+            }
+            Input::MouseInput {
+                button: MouseButton::Left,
+                pressed: false,
+            } => {
+                if let Some(id) = self.pressed.take() {
+                    let widget = registry.search_mut(id);
+                    if self.hovered == Some(id) {
+                        widget.on_hover();
+                        events.push((id, WidgetEvent::OnClick));
+                    } else {
+                        widget.unfocus();
+                    }
+                    registry.repaint(id);
+                }
+            }
+            Input::MouseInput { .. } => {}
+            Input::Wheel { dx, dy } => {
+                if let Some(id) = self.hovered {
+                    registry.search_mut(id).on_scroll(dx, dy);
+                    registry.invalidate(id);
+                }
+            }
+            Input::Key {
+                key: Key::Named(NamedKey::Tab),
+                pressed: true,
+                ..
+            } if self.tab_moves_focus(registry) => {
+                let order = focus_order(registry, ids);
+                let current = self
+                    .focused
+                    .and_then(|id| order.iter().position(|o| *o == id));
+                let len = order.len();
+                if len > 0 {
+                    let next = match (current, self.modifiers.shift_key()) {
+                        (Some(i), false) => i + 1,
+                        (Some(i), true) => i + len - 1,
+                        (None, false) => 0,
+                        (None, true) => len - 1,
+                    };
+                    events.extend(self.focus(Some(order[next % len]), registry));
+                }
+            }
+            Input::Key {
+                key,
+                code,
+                pressed,
+                repeat,
+                text,
+            } => {
+                if let Some(id) = self.focused {
+                    let widget = registry.search_mut(id);
+                    let event = KeyEvent {
+                        key,
+                        code,
+                        modifiers: self.modifiers,
+                        repeat,
+                    };
+                    if pressed {
+                        widget.on_key_down(&event);
+                        if widget.activates(&event) {
+                            events.push((id, WidgetEvent::OnClick));
+                        }
+                        if let Some(text) = text.filter(|_| !is_shortcut(self.modifiers)) {
+                            let text: String = text.chars().filter(|c| !c.is_control()).collect();
+                            if !text.is_empty() {
+                                widget.on_char(&text);
+                            }
+                        }
+                    } else {
+                        widget.on_key_up(&event);
+                    }
+                    // Widgets taking text place it when they are laid out, so keys may move it.
+                    match widget.text_input_area() {
+                        Some(_) => registry.invalidate(id),
+                        None => registry.repaint(id),
+                    }
+                }
+            }
+            Input::Modifiers(modifiers) => self.modifiers = modifiers,
+            Input::Ime(ime) => {
+                if let Some(id) = self.focused {
+                    let widget = registry.search_mut(id);
+                    match ime {
+                        Ime::Preedit(text, cursor) => widget.on_preedit(&text, cursor),
+                        Ime::Commit(text) => {
+                            widget.on_preedit("", None);
+                            widget.on_char(&text);
+                        }
+                    }
+                    registry.invalidate(id);
+                }
+            }
+        }
+        if let Some(id) = self.focused {
+            if registry.search_mut(id).take_change() {
+                events.push((id, WidgetEvent::OnChange));
+            }
+        }
+        events
+    }
+
+    /// The widget that receives key presses
+    pub fn focused(&self) -> Option<ID> {
+        self.focused
+    }
+
+    /// The modifier keys held down
+    pub fn modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
+
+    /// Whether the focus should be shown with a focus ring
+    pub fn focus_visible(&self) -> bool {
+        self.focused.is_some() && self.focus_visible
+    }
+
+    /// Moves keyboard focus to `focus`, returning the resulting focus events
+    ///
+    /// The focus is shown as if it had been moved from the keyboard.
+    /// Nothing happens if `focus` is not registered or cannot take focus.
+    pub fn focus(
+        &mut self,
+        focus: Option<ID>,
+        registry: &mut WidgetRegistry,
+    ) -> Vec<(ID, WidgetEvent)> {
+        self.retain(registry);
+        if focus.is_some_and(|id| !registry.contains(id) || !registry.search(&id).focusable()) {
+            return vec![];
+        }
+        // The focus ring may appear, move or go away.
+        self.focus_visible = true;
+        for id in [self.focused, focus].into_iter().flatten() {
+            registry.repaint(id);
+        }
+        if focus == self.focused {
+            return vec![];
+        }
+        let mut events = vec![];
+        if let Some(id) = self.focused {
+            registry.search_mut(id).on_blur();
+            events.push((id, WidgetEvent::OnBlur));
+        }
+        if let Some(id) = focus {
+            registry.search_mut(id).on_focus();
+            events.push((id, WidgetEvent::OnFocus));
+        }
+        self.focused = focus;
+        events
+    }
+
+    fn tab_moves_focus(&self, registry: &WidgetRegistry) -> bool {
+        match self.focused {
+            Some(id) => !registry.search(&id).captures_tab() || self.modifiers.control_key(),
+            None => true,
+        }
+    }
+
+    /// Stops tracking widgets that are no longer registered
+    ///
+    /// Callbacks may have removed widgets since the last input.
+    fn retain(&mut self, registry: &WidgetRegistry) {
+        for state in [&mut self.hovered, &mut self.pressed, &mut self.focused] {
+            if state.is_some_and(|id| !registry.contains(id)) {
+                *state = None;
+            }
+        }
+    }
+
+    /// Stops tracking widgets that are about to be removed
+    pub(crate) fn forget(&mut self, ids: &[ID]) {
+        for state in [&mut self.hovered, &mut self.pressed, &mut self.focused] {
+            if state.is_some_and(|id| ids.contains(&id)) {
+                *state = None;
+            }
+        }
+    }
+}
+
+/// Whether the modifiers turn a key press into a shortcut rather than text input
+///
+/// Control and Alt together are how Windows reports AltGr, which does produce text.
+fn is_shortcut(modifiers: Modifiers) -> bool {
+    modifiers.control_key() != modifiers.alt_key() || modifiers.super_key()
+}
+
+/// Lists the focusable widgets in the order Tab visits them
+fn focus_order(registry: &WidgetRegistry, ids: &[ID]) -> Vec<ID> {
+    let mut order: Vec<ID> = ids
+        .iter()
+        .flat_map(|id| registry.descendants(*id))
+        .filter(|id| registry.search(id).focusable())
+        .collect();
+    // The sort is stable, so widgets without an index keep their tree order.
+    order.sort_by_key(|id| match registry.search(id).tab_index() {
+        Some(index) => (0, index),
+        None => (1, 0),
+    });
+    order
+}
+
+/// Finds the topmost widget containing the point
+fn hit_test(registry: &WidgetRegistry, ids: &[ID], x: i32, y: i32) -> Option<ID> {
+    let drawn: Vec<ID> = ids
+        .iter()
+        .flat_map(|id| registry.descendants(*id))
+        .collect();
+    drawn
+        .into_iter()
+        .rev()
+        .find(|id| {
+            x >= 0
+                && y >= 0
+                && registry
+                    .search(id)
+                    .area()
+                    .iter()
+                    .any(|area| area.contains(x as u32, y as u32))
+        })
+        .filter(|id| registry.search(id).enabled())
+}
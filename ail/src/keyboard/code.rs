@@ -0,0 +1,526 @@
+use std::ops::{BitOr, BitOrAssign};
+
+use winit::keyboard::{Key, ModifiersState, NamedKey, PhysicalKey};
+
+/// A key identified by its position on the keyboard, independently of the platform
+///
+/// Keys are named after what they produce on a US keyboard, so [`KeyCode::Z`] is the key
+/// left of X even on layouts where it types Y. Use the logical key of a
+/// [`KeyEvent`](crate::event::KeyEvent) for what the key means in the active layout.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum KeyCode {
+    // Letters, named after their position on a US keyboard
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+    I,
+    J,
+    K,
+    L,
+    M,
+    N,
+    O,
+    P,
+    Q,
+    R,
+    S,
+    T,
+    U,
+    V,
+    W,
+    X,
+    Y,
+    Z,
+    // Digits above the letters
+    Digit0,
+    Digit1,
+    Digit2,
+    Digit3,
+    Digit4,
+    Digit5,
+    Digit6,
+    Digit7,
+    Digit8,
+    Digit9,
+    // Function keys
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    F13,
+    F14,
+    F15,
+    F16,
+    F17,
+    F18,
+    F19,
+    F20,
+    F21,
+    F22,
+    F23,
+    F24,
+    // Arrows
+    ArrowUp,
+    ArrowDown,
+    ArrowLeft,
+    ArrowRight,
+    // Navigation and editing
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Insert,
+    Delete,
+    Backspace,
+    Tab,
+    Enter,
+    Escape,
+    Space,
+    // Numeric keypad
+    Numpad0,
+    Numpad1,
+    Numpad2,
+    Numpad3,
+    Numpad4,
+    Numpad5,
+    Numpad6,
+    Numpad7,
+    Numpad8,
+    Numpad9,
+    NumpadAdd,
+    NumpadSubtract,
+    NumpadMultiply,
+    NumpadDivide,
+    NumpadDecimal,
+    NumpadComma,
+    NumpadEqual,
+    NumpadEnter,
+    // Punctuation
+    Backquote,
+    Minus,
+    Equal,
+    BracketLeft,
+    BracketRight,
+    Backslash,
+    Semicolon,
+    Quote,
+    Comma,
+    Period,
+    Slash,
+    IntlBackslash,
+    // Modifiers
+    ShiftLeft,
+    ShiftRight,
+    ControlLeft,
+    ControlRight,
+    AltLeft,
+    AltRight,
+    SuperLeft,
+    SuperRight,
+    // Locks and system keys
+    CapsLock,
+    NumLock,
+    ScrollLock,
+    PrintScreen,
+    Pause,
+    ContextMenu,
+    /// A key that has no code on this platform
+    Unidentified,
+}
+
+impl KeyCode {
+    /// Guesses the key from the character or named key it produced
+    ///
+    /// Used when the platform does not report the physical key. Characters are looked up
+    /// on a US layout.
+    pub fn from_logical(key: &Key) -> Self {
+        match key {
+            Key::Named(named) => Self::from_named(*named),
+            Key::Character(text) => {
+                let mut chars = text.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => Self::from_char(c),
+                    _ => KeyCode::Unidentified,
+                }
+            }
+            _ => KeyCode::Unidentified,
+        }
+    }
+
+    fn from_named(key: NamedKey) -> Self {
+        match key {
+            NamedKey::ArrowUp => KeyCode::ArrowUp,
+            NamedKey::ArrowDown => KeyCode::ArrowDown,
+            NamedKey::ArrowLeft => KeyCode::ArrowLeft,
+            NamedKey::ArrowRight => KeyCode::ArrowRight,
+            NamedKey::Home => KeyCode::Home,
+            NamedKey::End => KeyCode::End,
+            NamedKey::PageUp => KeyCode::PageUp,
+            NamedKey::PageDown => KeyCode::PageDown,
+            NamedKey::Insert => KeyCode::Insert,
+            NamedKey::Delete => KeyCode::Delete,
+            NamedKey::Backspace => KeyCode::Backspace,
+            NamedKey::Tab => KeyCode::Tab,
+            NamedKey::Enter => KeyCode::Enter,
+            NamedKey::Escape => KeyCode::Escape,
+            NamedKey::Space => KeyCode::Space,
+            NamedKey::Shift => KeyCode::ShiftLeft,
+            NamedKey::Control => KeyCode::ControlLeft,
+            NamedKey::Alt => KeyCode::AltLeft,
+            NamedKey::AltGraph => KeyCode::AltRight,
+            NamedKey::Super => KeyCode::SuperLeft,
+            NamedKey::Meta => KeyCode::SuperLeft,
+            NamedKey::CapsLock => KeyCode::CapsLock,
+            NamedKey::NumLock => KeyCode::NumLock,
+            NamedKey::ScrollLock => KeyCode::ScrollLock,
+            NamedKey::PrintScreen => KeyCode::PrintScreen,
+            NamedKey::Pause => KeyCode::Pause,
+            NamedKey::ContextMenu => KeyCode::ContextMenu,
+            NamedKey::F1 => KeyCode::F1,
+            NamedKey::F2 => KeyCode::F2,
+            NamedKey::F3 => KeyCode::F3,
+            NamedKey::F4 => KeyCode::F4,
+            NamedKey::F5 => KeyCode::F5,
+            NamedKey::F6 => KeyCode::F6,
+            NamedKey::F7 => KeyCode::F7,
+            NamedKey::F8 => KeyCode::F8,
+            NamedKey::F9 => KeyCode::F9,
+            NamedKey::F10 => KeyCode::F10,
+            NamedKey::F11 => KeyCode::F11,
+            NamedKey::F12 => KeyCode::F12,
+            NamedKey::F13 => KeyCode::F13,
+            NamedKey::F14 => KeyCode::F14,
+            NamedKey::F15 => KeyCode::F15,
+            NamedKey::F16 => KeyCode::F16,
+            NamedKey::F17 => KeyCode::F17,
+            NamedKey::F18 => KeyCode::F18,
+            NamedKey::F19 => KeyCode::F19,
+            NamedKey::F20 => KeyCode::F20,
+            NamedKey::F21 => KeyCode::F21,
+            NamedKey::F22 => KeyCode::F22,
+            NamedKey::F23 => KeyCode::F23,
+            NamedKey::F24 => KeyCode::F24,
+            _ => KeyCode::Unidentified,
+        }
+    }
+
+    fn from_char(c: char) -> Self {
+        match c.to_ascii_uppercase() {
+            'A' => KeyCode::A,
+            'B' => KeyCode::B,
+            'C' => KeyCode::C,
+            'D' => KeyCode::D,
+            'E' => KeyCode::E,
+            'F' => KeyCode::F,
+            'G' => KeyCode::G,
+            'H' => KeyCode::H,
+            'I' => KeyCode::I,
+            'J' => KeyCode::J,
+            'K' => KeyCode::K,
+            'L' => KeyCode::L,
+            'M' => KeyCode::M,
+            'N' => KeyCode::N,
+            'O' => KeyCode::O,
+            'P' => KeyCode::P,
+            'Q' => KeyCode::Q,
+            'R' => KeyCode::R,
+            'S' => KeyCode::S,
+            'T' => KeyCode::T,
+            'U' => KeyCode::U,
+            'V' => KeyCode::V,
+            'W' => KeyCode::W,
+            'X' => KeyCode::X,
+            'Y' => KeyCode::Y,
+            'Z' => KeyCode::Z,
+            '0' => KeyCode::Digit0,
+            '1' => KeyCode::Digit1,
+            '2' => KeyCode::Digit2,
+            '3' => KeyCode::Digit3,
+            '4' => KeyCode::Digit4,
+            '5' => KeyCode::Digit5,
+            '6' => KeyCode::Digit6,
+            '7' => KeyCode::Digit7,
+            '8' => KeyCode::Digit8,
+            '9' => KeyCode::Digit9,
+            '`' => KeyCode::Backquote,
+            '-' => KeyCode::Minus,
+            '=' => KeyCode::Equal,
+            '[' => KeyCode::BracketLeft,
+            ']' => KeyCode::BracketRight,
+            '\\' => KeyCode::Backslash,
+            ';' => KeyCode::Semicolon,
+            '\'' => KeyCode::Quote,
+            ',' => KeyCode::Comma,
+            '.' => KeyCode::Period,
+            '/' => KeyCode::Slash,
+            ' ' => KeyCode::Space,
+            _ => KeyCode::Unidentified,
+        }
+    }
+}
+
+impl From<PhysicalKey> for KeyCode {
+    fn from(key: PhysicalKey) -> Self {
+        use winit::keyboard::KeyCode as Code;
+
+        let PhysicalKey::Code(code) = key else {
+            return KeyCode::Unidentified;
+        };
+        match code {
+            Code::KeyA => KeyCode::A,
+            Code::KeyB => KeyCode::B,
+            Code::KeyC => KeyCode::C,
+            Code::KeyD => KeyCode::D,
+            Code::KeyE => KeyCode::E,
+            Code::KeyF => KeyCode::F,
+            Code::KeyG => KeyCode::G,
+            Code::KeyH => KeyCode::H,
+            Code::KeyI => KeyCode::I,
+            Code::KeyJ => KeyCode::J,
+            Code::KeyK => KeyCode::K,
+            Code::KeyL => KeyCode::L,
+            Code::KeyM => KeyCode::M,
+            Code::KeyN => KeyCode::N,
+            Code::KeyO => KeyCode::O,
+            Code::KeyP => KeyCode::P,
+            Code::KeyQ => KeyCode::Q,
+            Code::KeyR => KeyCode::R,
+            Code::KeyS => KeyCode::S,
+            Code::KeyT => KeyCode::T,
+            Code::KeyU => KeyCode::U,
+            Code::KeyV => KeyCode::V,
+            Code::KeyW => KeyCode::W,
+            Code::KeyX => KeyCode::X,
+            Code::KeyY => KeyCode::Y,
+            Code::KeyZ => KeyCode::Z,
+            Code::Digit0 => KeyCode::Digit0,
+            Code::Digit1 => KeyCode::Digit1,
+            Code::Digit2 => KeyCode::Digit2,
+            Code::Digit3 => KeyCode::Digit3,
+            Code::Digit4 => KeyCode::Digit4,
+            Code::Digit5 => KeyCode::Digit5,
+            Code::Digit6 => KeyCode::Digit6,
+            Code::Digit7 => KeyCode::Digit7,
+            Code::Digit8 => KeyCode::Digit8,
+            Code::Digit9 => KeyCode::Digit9,
+            Code::F1 => KeyCode::F1,
+            Code::F2 => KeyCode::F2,
+            Code::F3 => KeyCode::F3,
+            Code::F4 => KeyCode::F4,
+            Code::F5 => KeyCode::F5,
+            Code::F6 => KeyCode::F6,
+            Code::F7 => KeyCode::F7,
+            Code::F8 => KeyCode::F8,
+            Code::F9 => KeyCode::F9,
+            Code::F10 => KeyCode::F10,
+            Code::F11 => KeyCode::F11,
+            Code::F12 => KeyCode::F12,
+            Code::F13 => KeyCode::F13,
+            Code::F14 => KeyCode::F14,
+            Code::F15 => KeyCode::F15,
+            Code::F16 => KeyCode::F16,
+            Code::F17 => KeyCode::F17,
+            Code::F18 => KeyCode::F18,
+            Code::F19 => KeyCode::F19,
+            Code::F20 => KeyCode::F20,
+            Code::F21 => KeyCode::F21,
+            Code::F22 => KeyCode::F22,
+            Code::F23 => KeyCode::F23,
+            Code::F24 => KeyCode::F24,
+            Code::ArrowUp => KeyCode::ArrowUp,
+            Code::ArrowDown => KeyCode::ArrowDown,
+            Code::ArrowLeft => KeyCode::ArrowLeft,
+            Code::ArrowRight => KeyCode::ArrowRight,
+            Code::Home => KeyCode::Home,
+            Code::End => KeyCode::End,
+            Code::PageUp => KeyCode::PageUp,
+            Code::PageDown => KeyCode::PageDown,
+            Code::Insert => KeyCode::Insert,
+            Code::Delete => KeyCode::Delete,
+            Code::Backspace => KeyCode::Backspace,
+            Code::Tab => KeyCode::Tab,
+            Code::Enter => KeyCode::Enter,
+            Code::Escape => KeyCode::Escape,
+            Code::Space => KeyCode::Space,
+            Code::Numpad0 => KeyCode::Numpad0,
+            Code::Numpad1 => KeyCode::Numpad1,
+            Code::Numpad2 => KeyCode::Numpad2,
+            Code::Numpad3 => KeyCode::Numpad3,
+            Code::Numpad4 => KeyCode::Numpad4,
+            Code::Numpad5 => KeyCode::Numpad5,
+            Code::Numpad6 => KeyCode::Numpad6,
+            Code::Numpad7 => KeyCode::Numpad7,
+            Code::Numpad8 => KeyCode::Numpad8,
+            Code::Numpad9 => KeyCode::Numpad9,
+            Code::NumpadAdd => KeyCode::NumpadAdd,
+            Code::NumpadSubtract => KeyCode::NumpadSubtract,
+            Code::NumpadMultiply => KeyCode::NumpadMultiply,
+            Code::NumpadDivide => KeyCode::NumpadDivide,
+            Code::NumpadDecimal => KeyCode::NumpadDecimal,
+            Code::NumpadComma => KeyCode::NumpadComma,
+            Code::NumpadEqual => KeyCode::NumpadEqual,
+            Code::NumpadEnter => KeyCode::NumpadEnter,
+            Code::Backquote => KeyCode::Backquote,
+            Code::Minus => KeyCode::Minus,
+            Code::Equal => KeyCode::Equal,
+            Code::BracketLeft => KeyCode::BracketLeft,
+            Code::BracketRight => KeyCode::BracketRight,
+            Code::Backslash => KeyCode::Backslash,
+            Code::Semicolon => KeyCode::Semicolon,
+            Code::Quote => KeyCode::Quote,
+            Code::Comma => KeyCode::Comma,
+            Code::Period => KeyCode::Period,
+            Code::Slash => KeyCode::Slash,
+            Code::IntlBackslash => KeyCode::IntlBackslash,
+            Code::ShiftLeft => KeyCode::ShiftLeft,
+            Code::ShiftRight => KeyCode::ShiftRight,
+            Code::ControlLeft => KeyCode::ControlLeft,
+            Code::ControlRight => KeyCode::ControlRight,
+            Code::AltLeft => KeyCode::AltLeft,
+            Code::AltRight => KeyCode::AltRight,
+            Code::SuperLeft => KeyCode::SuperLeft,
+            Code::SuperRight => KeyCode::SuperRight,
+            Code::CapsLock => KeyCode::CapsLock,
+            Code::NumLock => KeyCode::NumLock,
+            Code::ScrollLock => KeyCode::ScrollLock,
+            Code::PrintScreen => KeyCode::PrintScreen,
+            Code::Pause => KeyCode::Pause,
+            Code::ContextMenu => KeyCode::ContextMenu,
+            _ => KeyCode::Unidentified,
+        }
+    }
+}
+
+/// The set of modifier keys held down
+///
+/// Left and right keys are not told apart; see [`KeyCode`] for that.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Modifiers(u8);
+
+impl Modifiers {
+    pub const SHIFT: Modifiers = Modifiers(1);
+    pub const CONTROL: Modifiers = Modifiers(1 << 1);
+    pub const ALT: Modifiers = Modifiers(1 << 2);
+    /// The Windows key, or Command on macOS
+    pub const SUPER: Modifiers = Modifiers(1 << 3);
+
+    pub const fn empty() -> Self {
+        Modifiers(0)
+    }
+
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Whether every modifier of `other` is held
+    pub const fn contains(self, other: Modifiers) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub const fn shift_key(self) -> bool {
+        self.contains(Self::SHIFT)
+    }
+
+    pub const fn control_key(self) -> bool {
+        self.contains(Self::CONTROL)
+    }
+
+    pub const fn alt_key(self) -> bool {
+        self.contains(Self::ALT)
+    }
+
+    pub const fn super_key(self) -> bool {
+        self.contains(Self::SUPER)
+    }
+}
+
+impl BitOr for Modifiers {
+    type Output = Modifiers;
+
+    fn bitor(self, rhs: Modifiers) -> Modifiers {
+        Modifiers(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for Modifiers {
+    fn bitor_assign(&mut self, rhs: Modifiers) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl From<ModifiersState> for Modifiers {
+    fn from(state: ModifiersState) -> Self {
+        let mut modifiers = Modifiers::empty();
+        for (held, modifier) in [
+            (state.shift_key(), Modifiers::SHIFT),
+            (state.control_key(), Modifiers::CONTROL),
+            (state.alt_key(), Modifiers::ALT),
+            (state.super_key(), Modifiers::SUPER),
+        ] {
+            if held {
+                modifiers |= modifier;
+            }
+        }
+        modifiers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::keyboard::{KeyCode as Code, NativeKeyCode};
+
+    use super::*;
+
+    #[test]
+    fn physical_and_logical_keys() {
+        assert_eq!(KeyCode::from(PhysicalKey::Code(Code::KeyQ)), KeyCode::Q);
+        assert_eq!(
+            KeyCode::from(PhysicalKey::Code(Code::NumpadEnter)),
+            KeyCode::NumpadEnter
+        );
+        assert_eq!(
+            KeyCode::from(PhysicalKey::Unidentified(NativeKeyCode::Unidentified)),
+            KeyCode::Unidentified
+        );
+
+        assert_eq!(
+            KeyCode::from_logical(&Key::Character("q".into())),
+            KeyCode::Q
+        );
+        assert_eq!(
+            KeyCode::from_logical(&Key::Character(";".into())),
+            KeyCode::Semicolon
+        );
+        assert_eq!(
+            KeyCode::from_logical(&Key::Character("é".into())),
+            KeyCode::Unidentified
+        );
+        assert_eq!(
+            KeyCode::from_logical(&Key::Named(NamedKey::F12)),
+            KeyCode::F12
+        );
+    }
+
+    #[test]
+    fn modifiers() {
+        let state = ModifiersState::CONTROL | ModifiersState::SHIFT;
+        let modifiers = Modifiers::from(state);
+        assert_eq!(modifiers, Modifiers::CONTROL | Modifiers::SHIFT);
+        assert!(modifiers.contains(Modifiers::SHIFT));
+        assert!(!modifiers.alt_key());
+        assert!(Modifiers::default().is_empty());
+    }
+}
@@ -2,8 +2,8 @@ use std::ptr::null;
 use x11::{
     keysym::*,
     xlib::{
-        AnyModifier, Button1, ButtonPressMask, False, XCloseDisplay, XDefaultRootWindow,
-        XGrabButton, XNextEvent, XOpenDisplay, XQueryKeymap,
+        Button1Mask, Display, KeySym, XCloseDisplay, XDefaultRootWindow, XKeysymToKeycode,
+        XOpenDisplay, XQueryKeymap, XQueryPointer,
     },
 };
 
@@ -50,49 +50,79 @@ fn conver_upper_case(id: KeyId) -> KeyId {
     id - 32
 }
 
-pub fn _get_key_state(id: KeyId) -> bool {
+pub fn _get_key_state(id: KeyId) -> Option<bool> {
+    let connection = _Connection::open()?;
     if id == _VK_LBUTTON {
         unsafe {
-            let display = XOpenDisplay(null());
+            let display = connection.display;
             let root = XDefaultRootWindow(display);
-            let mut ev = std::mem::MaybeUninit::uninit().assume_init();
-            XGrabButton(
+            let (mut root_return, mut child_return) = (0, 0);
+            let (mut root_x, mut root_y, mut win_x, mut win_y) = (0, 0, 0, 0);
+            let mut mask = 0;
+            XQueryPointer(
                 display,
-                Button1,
-                AnyModifier,
                 root,
-                False,
-                ButtonPressMask as i32,
-                GrabModeAsync,
-                GrabModeAsync,
-                0,
-                0,
+                &mut root_return,
+                &mut child_return,
+                &mut root_x,
+                &mut root_y,
+                &mut win_x,
+                &mut win_y,
+                &mut mask,
             );
-            XNextEvent(display, &mut ev);
-            XCloseDisplay(display);
-            return ev.button.button == Button1;
+            Some(mask & Button1Mask != 0)
         }
     } else {
+        Some(
+            connection
+                .keyboard_state()
+                .get(connection.key_index(id))
+                .is_some_and(|state| state & 0x80 != 0),
+        )
+    }
+}
+
+/// A connection to the X server, shared by the queries of a keyboard snapshot
+pub struct _Connection {
+    display: *mut Display,
+}
+
+impl _Connection {
+    /// Connects to the default display, failing on headless or Wayland-only systems
+    pub fn open() -> Option<Self> {
         let display = unsafe { XOpenDisplay(null()) };
-        if display.is_null() {
-            panic!("Can't open display.");
-        }
-        let mut state = vec![0; 256];
+        (!display.is_null()).then_some(Self { display })
+    }
+
+    /// Index of a key in the state returned by [`_Connection::keyboard_state`]
+    ///
+    /// Key ids are X keysyms, while the state is indexed by the keycodes of the keyboard.
+    pub fn key_index(&self, id: KeyId) -> usize {
+        unsafe { XKeysymToKeycode(self.display, id as KeySym) as usize }
+    }
+
+    /// Obtains one byte per keycode, with the high bit set for pressed keys as on Windows
+    pub fn keyboard_state(&self) -> Vec<u8> {
+        // XQueryKeymap reports one bit per keycode.
+        let mut keymap = [0; 32];
         unsafe {
-            XQueryKeymap(display, state.as_mut_ptr());
+            XQueryKeymap(self.display, keymap.as_mut_ptr());
         }
-        state[id as usize] != 0
+        (0..256)
+            .map(
+                |keycode| match keymap[keycode / 8] as u8 & (1 << (keycode % 8)) {
+                    0 => 0,
+                    _ => 0x80,
+                },
+            )
+            .collect()
     }
 }
 
-pub fn _get_keyboard_state() -> Vec<u8> {
-    let display = unsafe { XOpenDisplay(null()) };
-    if display.is_null() {
-        panic!("Can't open display.");
-    }
-    let mut state: Vec<u8> = vec![0; 256];
-    unsafe {
-        XQueryKeymap(display, state.as_mut_ptr() as *mut i8);
+impl Drop for _Connection {
+    fn drop(&mut self) {
+        unsafe {
+            XCloseDisplay(self.display);
+        }
     }
-    state
 }
@@ -1,3 +1,4 @@
+mod code;
 #[cfg(target_os = "linux")]
 pub mod linux;
 #[cfg(target_os = "windows")]
@@ -8,6 +9,11 @@ pub(crate) use self::linux::*;
 #[cfg(target_os = "windows")]
 pub(crate) use self::windows::*;
 
+pub use code::*;
+
+/// Platform specific key id: a virtual-key code on Windows and a keysym on Linux
+///
+/// Key events identify keys with the portable [`KeyCode`] instead.
 pub type KeyId = u32;
 
 pub const VK_NULL: KeyId = 0x0;
@@ -50,26 +56,37 @@ const VK_ARRAY: [KeyId; 26] = [
     VK_Q, VK_R, VK_S, VK_T, VK_U, VK_V, VK_W, VK_X, VK_Y, VK_Z,
 ];
 
+/// The keys held down at one moment
+///
+/// The display connection used to take the snapshot is kept to look keys up in it.
 pub struct Keyboard {
     inner: Vec<u8>,
+    connection: _Connection,
 }
 
 impl Keyboard {
-    pub fn get() -> Self {
-        Self {
-            inner: _get_keyboard_state(),
-        }
+    /// Takes a snapshot of the keyboard
+    ///
+    /// Returns `None` if the keyboard cannot be queried, such as on Linux without an X display.
+    pub fn get() -> Option<Self> {
+        let connection = _Connection::open()?;
+        Some(Self {
+            inner: connection.keyboard_state(),
+            connection,
+        })
     }
 
     pub fn get_key_state(&self, id: KeyId) -> bool {
-        self.inner[id as usize] != 0
+        self.inner
+            .get(self.connection.key_index(id))
+            .is_some_and(|state| state & 0x80 != 0)
     }
 
     pub fn extract(&self) -> KeyId {
         let mut code = VK_NULL;
 
         for i in VK_ARRAY {
-            if self.inner[i as usize] & 0x80 == 128 {
+            if self.get_key_state(i) {
                 code += i;
             }
         }
@@ -78,6 +95,7 @@ impl Keyboard {
     }
 }
 
-pub fn get_key_state(id: KeyId) -> bool {
+/// Returns whether the key is held down, or `None` if the keyboard cannot be queried
+pub fn get_key_state(id: KeyId) -> Option<bool> {
     _get_key_state(id)
 }
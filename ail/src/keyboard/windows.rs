@@ -37,14 +37,28 @@ pub const _VK_BACKSPACE: KeyId = VK_BACK as KeyId;
 pub const _VK_TAB: KeyId = VK_TAB as KeyId;
 pub const _VK_SHIFT: KeyId = VK_SHIFT as KeyId;
 
-pub fn _get_key_state(id: KeyId) -> bool {
-    unsafe { GetAsyncKeyState(id as i32) != 0 }
+pub fn _get_key_state(id: KeyId) -> Option<bool> {
+    Some(unsafe { GetAsyncKeyState(id as i32) != 0 })
 }
 
-pub fn _get_keyboard_state() -> Vec<u8> {
-    let mut state = vec![0; 256];
-    unsafe {
-        GetKeyboardState(state.as_mut_ptr());
+/// Stands in for the display connection needed on Linux
+pub struct _Connection;
+
+impl _Connection {
+    pub fn open() -> Option<Self> {
+        Some(Self)
+    }
+
+    /// Index of a key in the state returned by [`_Connection::keyboard_state`]
+    pub fn key_index(&self, id: KeyId) -> usize {
+        id as usize
+    }
+
+    pub fn keyboard_state(&self) -> Vec<u8> {
+        let mut state = vec![0; 256];
+        unsafe {
+            GetKeyboardState(state.as_mut_ptr());
+        }
+        state
     }
-    state
 }
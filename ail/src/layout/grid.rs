@@ -0,0 +1,346 @@
+use crate::Rect;
+
+use super::{Align, Padding, Size};
+
+/// Size of a row or column of a [`Grid`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Track {
+    /// Exactly this many pixels
+    Fixed(u32),
+    /// Just large enough for the children in the track
+    Auto,
+    /// A share of the space left over by the other tracks
+    ///
+    /// The leftover space is split in proportion to the fractions, like `fr` in CSS.
+    Fraction(f32),
+}
+
+/// Where a single child is placed in a [`Grid`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GridItem {
+    pub row: usize,
+    pub column: usize,
+    pub row_span: usize,
+    pub column_span: usize,
+    /// Overrides [`Grid::align_x`] for this child
+    pub align_x: Option<Align>,
+    /// Overrides [`Grid::align_y`] for this child
+    pub align_y: Option<Align>,
+}
+
+impl GridItem {
+    /// Places the child in a single cell
+    pub fn new(row: usize, column: usize) -> Self {
+        Self {
+            row,
+            column,
+            row_span: 1,
+            column_span: 1,
+            align_x: None,
+            align_y: None,
+        }
+    }
+
+    /// Sets how many rows and columns the child covers
+    pub fn span(mut self, rows: usize, columns: usize) -> Self {
+        self.row_span = rows.max(1);
+        self.column_span = columns.max(1);
+        self
+    }
+
+    pub fn align_x(mut self, align: Align) -> Self {
+        self.align_x = Some(align);
+        self
+    }
+
+    pub fn align_y(mut self, align: Align) -> Self {
+        self.align_y = Some(align);
+        self
+    }
+}
+
+/// A layout that places children in the cells of rows and columns
+///
+/// Children may span several tracks. Rows and columns referenced by a child but not
+/// declared are added as [`Track::Auto`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grid {
+    pub columns: Vec<Track>,
+    pub rows: Vec<Track>,
+    pub column_gap: u32,
+    pub row_gap: u32,
+    pub padding: Padding,
+    /// Horizontal placement of children inside their cells
+    pub align_x: Align,
+    /// Vertical placement of children inside their cells
+    pub align_y: Align,
+}
+
+impl Grid {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn columns<T>(mut self, columns: T) -> Self
+    where
+        T: Into<Vec<Track>>,
+    {
+        self.columns = columns.into();
+        self
+    }
+
+    pub fn rows<T>(mut self, rows: T) -> Self
+    where
+        T: Into<Vec<Track>>,
+    {
+        self.rows = rows.into();
+        self
+    }
+
+    /// Sets the space between two rows and between two columns
+    pub fn gap(mut self, column_gap: u32, row_gap: u32) -> Self {
+        self.column_gap = column_gap;
+        self.row_gap = row_gap;
+        self
+    }
+
+    pub fn padding(mut self, padding: Padding) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    pub fn align_x(mut self, align: Align) -> Self {
+        self.align_x = align;
+        self
+    }
+
+    pub fn align_y(mut self, align: Align) -> Self {
+        self.align_y = align;
+        self
+    }
+
+    /// Computes the size needed to show every child at its preferred size
+    ///
+    /// Fractional tracks are measured like [`Track::Auto`] ones.
+    pub fn measure(&self, children: &[(Size, GridItem)]) -> Size {
+        let columns = self.column_sizes(children, None);
+        let rows = self.row_sizes(children, None);
+        Size::new(
+            span_length(&columns, 0, columns.len(), self.column_gap) + self.padding.horizontal(),
+            span_length(&rows, 0, rows.len(), self.row_gap) + self.padding.vertical(),
+        )
+    }
+
+    /// Splits `area` between the children
+    ///
+    /// Returns one rectangle per child, in the same order as `children`.
+    pub fn arrange(&self, area: Rect, children: &[(Size, GridItem)]) -> Vec<Rect> {
+        let inner = self.padding.apply(area);
+        let columns = self.column_sizes(children, Some(inner.width()));
+        let rows = self.row_sizes(children, Some(inner.height()));
+
+        children
+            .iter()
+            .map(|(size, item)| {
+                let (x, width) = place(
+                    offset(&columns, item.column, self.column_gap),
+                    span_length(&columns, item.column, item.column_span, self.column_gap),
+                    size.width,
+                    item.align_x.unwrap_or(self.align_x),
+                );
+                let (y, height) = place(
+                    offset(&rows, item.row, self.row_gap),
+                    span_length(&rows, item.row, item.row_span, self.row_gap),
+                    size.height,
+                    item.align_y.unwrap_or(self.align_y),
+                );
+                Rect::from_coordinate(inner.x() + x, inner.y() + y, width, height)
+            })
+            .collect()
+    }
+
+    fn column_sizes(&self, children: &[(Size, GridItem)], available: Option<u32>) -> Vec<u32> {
+        let spans: Vec<(usize, usize, u32)> = children
+            .iter()
+            .map(|(size, item)| (item.column, item.column_span.max(1), size.width))
+            .collect();
+        resolve(&self.columns, &spans, self.column_gap, available)
+    }
+
+    fn row_sizes(&self, children: &[(Size, GridItem)], available: Option<u32>) -> Vec<u32> {
+        let spans: Vec<(usize, usize, u32)> = children
+            .iter()
+            .map(|(size, item)| (item.row, item.row_span.max(1), size.height))
+            .collect();
+        resolve(&self.rows, &spans, self.row_gap, available)
+    }
+}
+
+impl Default for Grid {
+    fn default() -> Self {
+        Self {
+            columns: vec![],
+            rows: vec![],
+            column_gap: 0,
+            row_gap: 0,
+            padding: Padding::default(),
+            align_x: Align::Stretch,
+            align_y: Align::Stretch,
+        }
+    }
+}
+
+/// Resolves the sizes of the tracks along one axis
+///
+/// `spans` holds the first track, the number of tracks and the preferred length of every child.
+/// Without `available` space, fractional tracks are sized to their content.
+fn resolve(
+    defined: &[Track],
+    spans: &[(usize, usize, u32)],
+    gap: u32,
+    available: Option<u32>,
+) -> Vec<u32> {
+    let count = spans
+        .iter()
+        .map(|(start, span, _)| start + span)
+        .max()
+        .unwrap_or(0)
+        .max(defined.len());
+    let tracks: Vec<Track> = (0..count)
+        .map(|i| defined.get(i).copied().unwrap_or(Track::Auto))
+        .collect();
+    let sized_by_content = |track: &Track| match track {
+        Track::Fixed(_) => false,
+        Track::Auto => true,
+        Track::Fraction(_) => available.is_none(),
+    };
+
+    let mut sizes: Vec<u32> = tracks
+        .iter()
+        .map(|track| match track {
+            Track::Fixed(size) => *size,
+            _ => 0,
+        })
+        .collect();
+
+    for (start, _, length) in spans.iter().filter(|(_, span, _)| *span == 1) {
+        if sized_by_content(&tracks[*start]) {
+            sizes[*start] = sizes[*start].max(*length);
+        }
+    }
+
+    // Children spanning several tracks grow the content sized tracks they cover evenly.
+    for (start, span, length) in spans.iter().filter(|(_, span, _)| *span > 1) {
+        let current = span_length(&sizes, *start, *span, gap);
+        let flexible: Vec<usize> = (*start..start + span)
+            .filter(|i| sized_by_content(&tracks[*i]))
+            .collect();
+        if *length <= current || flexible.is_empty() {
+            continue;
+        }
+        let missing = length - current;
+        let n = flexible.len() as u32;
+        for (k, i) in flexible.into_iter().enumerate() {
+            sizes[i] += missing / n + u32::from((k as u32) < missing % n);
+        }
+    }
+
+    if let Some(available) = available {
+        let fractions: f32 = tracks
+            .iter()
+            .map(|track| match track {
+                Track::Fraction(fraction) => fraction.max(0.0),
+                _ => 0.0,
+            })
+            .sum();
+        if fractions > 0.0 {
+            let used = span_length(&sizes, 0, count, gap);
+            let free = available.saturating_sub(used) as f32;
+            let mut acc = 0.0f32;
+            for (i, track) in tracks.iter().enumerate() {
+                if let Track::Fraction(fraction) = track {
+                    let before = acc.round() as u32;
+                    acc += free * fraction.max(0.0) / fractions;
+                    sizes[i] = acc.round() as u32 - before;
+                }
+            }
+        }
+    }
+
+    sizes
+}
+
+/// Length covered by `span` tracks starting at `start`, including the gaps between them
+fn span_length(sizes: &[u32], start: usize, span: usize, gap: u32) -> u32 {
+    let end = (start + span).min(sizes.len());
+    if start >= end {
+        return 0;
+    }
+    sizes[start..end].iter().sum::<u32>() + gap * (end - start - 1) as u32
+}
+
+fn offset(sizes: &[u32], index: usize, gap: u32) -> u32 {
+    let index = index.min(sizes.len());
+    sizes[..index].iter().sum::<u32>() + gap * index as u32
+}
+
+/// Places a child of the given preferred length inside a cell
+fn place(start: u32, cell: u32, preferred: u32, align: Align) -> (u32, u32) {
+    let length = match align {
+        Align::Stretch => cell,
+        _ => preferred.min(cell),
+    };
+    let start = match align {
+        Align::Start | Align::Stretch => start,
+        Align::Center => start + (cell - length) / 2,
+        Align::End => start + cell - length,
+    };
+    (start, length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn track_sizes() {
+        let grid = Grid::new()
+            .columns([Track::Fixed(50), Track::Auto, Track::Fraction(1.0)])
+            .gap(10, 5)
+            .padding(Padding::all(5));
+        let children = [
+            (Size::new(20, 20), GridItem::new(0, 0)),
+            (Size::new(80, 30), GridItem::new(0, 1)),
+            (Size::new(40, 10), GridItem::new(0, 2)),
+            (Size::new(300, 20), GridItem::new(1, 0).span(1, 2)),
+        ];
+        // The spanning child needs 300 - 50 - 10 = 240 pixels from the auto column.
+        assert_eq!(
+            grid.measure(&children),
+            Size::new(5 + 50 + 10 + 240 + 10 + 40 + 5, 65)
+        );
+
+        let rects = grid.arrange(Rect::from_coordinate(0, 0, 400, 100), &children);
+        assert_eq!(rects[0], Rect::from_coordinate(5, 5, 50, 30));
+        assert_eq!(rects[1], Rect::from_coordinate(65, 5, 240, 30));
+        assert_eq!(rects[2], Rect::from_coordinate(315, 5, 80, 30));
+        assert_eq!(rects[3], Rect::from_coordinate(5, 40, 300, 20));
+    }
+
+    #[test]
+    fn fractions_and_alignment() {
+        let grid = Grid::new()
+            .columns([Track::Fraction(1.0), Track::Fraction(2.0)])
+            .rows([Track::Fixed(40)]);
+        let children = [
+            (
+                Size::new(20, 10),
+                GridItem::new(0, 0).align_x(Align::Center),
+            ),
+            (Size::new(20, 10), GridItem::new(0, 1).align_y(Align::End)),
+        ];
+        let rects = grid.arrange(Rect::from_coordinate(0, 0, 300, 40), &children);
+        assert_eq!(rects[0], Rect::from_coordinate(40, 0, 20, 40));
+        assert_eq!(rects[1], Rect::from_coordinate(100, 30, 200, 10));
+    }
+}
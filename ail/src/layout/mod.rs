@@ -0,0 +1,511 @@
+//! Layout of widgets inside their containers
+//!
+//! Layout happens in two passes. The measure pass asks every widget for its preferred
+//! [`Size`], bottom up. The arrange pass then hands every widget the [`Rect`] it occupies,
+//! top down, with each container splitting its own area between its children.
+//! [`WidgetRegistry::layout`] runs both passes over a widget tree.
+//!
+//! [`WidgetRegistry::layout`]: crate::management::WidgetRegistry::layout
+
+mod grid;
+
+pub use grid::*;
+
+use crate::Rect;
+
+/// Measures text the way the renderer draws it
+///
+/// Every render target provides metrics for its font, so widgets showing text can
+/// report a preferred size that matches what ends up on screen.
+pub trait TextMetrics {
+    /// Width of `text` drawn on a single line
+    fn text_width(&self, text: &str) -> u32;
+
+    /// Distance between two lines of text
+    fn line_height(&self) -> u32;
+}
+
+/// Metrics of a font whose glyphs all have the same size
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Monospace {
+    pub char_width: u32,
+    pub line_height: u32,
+}
+
+impl Monospace {
+    pub const fn new(char_width: u32, line_height: u32) -> Self {
+        Self {
+            char_width,
+            line_height,
+        }
+    }
+}
+
+impl TextMetrics for Monospace {
+    fn text_width(&self, text: &str) -> u32 {
+        text.chars().count() as u32 * self.char_width
+    }
+
+    fn line_height(&self) -> u32 {
+        self.line_height
+    }
+}
+
+/// Width and height in pixels
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Size {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Size {
+    pub const ZERO: Size = Size::new(0, 0);
+    pub const MAX: Size = Size::new(u32::MAX, u32::MAX);
+
+    pub const fn new(width: u32, height: u32) -> Self {
+        Self { width, height }
+    }
+
+    fn main(&self, direction: Direction) -> u32 {
+        match direction {
+            Direction::Row => self.width,
+            Direction::Column => self.height,
+        }
+    }
+
+    fn cross(&self, direction: Direction) -> u32 {
+        match direction {
+            Direction::Row => self.height,
+            Direction::Column => self.width,
+        }
+    }
+}
+
+/// Space left empty inside the edges of a container
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Padding {
+    pub left: u32,
+    pub top: u32,
+    pub right: u32,
+    pub bottom: u32,
+}
+
+impl Padding {
+    pub const fn new(left: u32, top: u32, right: u32, bottom: u32) -> Self {
+        Self {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    /// The same padding on every edge
+    pub const fn all(value: u32) -> Self {
+        Self::new(value, value, value, value)
+    }
+
+    pub(crate) fn horizontal(&self) -> u32 {
+        self.left + self.right
+    }
+
+    pub(crate) fn vertical(&self) -> u32 {
+        self.top + self.bottom
+    }
+
+    /// Shrinks `area` by the padding
+    pub(crate) fn apply(&self, area: Rect) -> Rect {
+        let width = area.width().saturating_sub(self.horizontal());
+        let height = area.height().saturating_sub(self.vertical());
+        Rect::from_coordinate(area.x() + self.left, area.y() + self.top, width, height)
+    }
+}
+
+/// The axis children are placed along
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Direction {
+    /// Left to right
+    Row,
+    /// Top to bottom
+    #[default]
+    Column,
+}
+
+/// Placement of a child on the cross axis
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Align {
+    #[default]
+    Start,
+    Center,
+    End,
+    /// Fill the whole cross axis
+    Stretch,
+}
+
+/// Distribution of the space left over on the main axis
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Justify {
+    #[default]
+    Start,
+    Center,
+    End,
+    /// Equal space between children, none at the edges
+    SpaceBetween,
+    /// Equal space around every child, so the edges get half as much
+    SpaceAround,
+    /// Equal space between children and at the edges
+    SpaceEvenly,
+}
+
+/// How a single child takes part in a [`Flex`] layout
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FlexItem {
+    pub grow: f32,
+    pub shrink: f32,
+    pub min: Size,
+    pub max: Size,
+    /// Overrides [`Flex::align`] for this child
+    pub align: Option<Align>,
+}
+
+impl FlexItem {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Share of the free space the child takes when the container is larger than needed
+    pub fn grow(mut self, grow: f32) -> Self {
+        self.grow = grow;
+        self
+    }
+
+    /// Share of the missing space the child gives up when the container is too small
+    pub fn shrink(mut self, shrink: f32) -> Self {
+        self.shrink = shrink;
+        self
+    }
+
+    pub fn min(mut self, min: Size) -> Self {
+        self.min = min;
+        self
+    }
+
+    pub fn max(mut self, max: Size) -> Self {
+        self.max = max;
+        self
+    }
+
+    pub fn align(mut self, align: Align) -> Self {
+        self.align = Some(align);
+        self
+    }
+
+    fn clamp(&self, size: Size) -> Size {
+        Size::new(
+            size.width
+                .max(self.min.width)
+                .min(self.max.width.max(self.min.width)),
+            size.height
+                .max(self.min.height)
+                .min(self.max.height.max(self.min.height)),
+        )
+    }
+}
+
+impl Default for FlexItem {
+    fn default() -> Self {
+        Self {
+            grow: 0.0,
+            shrink: 1.0,
+            min: Size::ZERO,
+            max: Size::MAX,
+            align: None,
+        }
+    }
+}
+
+/// A row or column layout in the spirit of CSS flexbox
+///
+/// Children are laid out in a single line along [`Flex::direction`].
+/// Every child starts at its preferred size, then free space is handed out according
+/// to [`FlexItem::grow`] or taken away according to [`FlexItem::shrink`],
+/// always respecting the minimum and maximum sizes of the child.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Flex {
+    pub direction: Direction,
+    pub gap: u32,
+    pub padding: Padding,
+    pub align: Align,
+    pub justify: Justify,
+}
+
+impl Flex {
+    pub fn row() -> Self {
+        Self {
+            direction: Direction::Row,
+            ..Default::default()
+        }
+    }
+
+    pub fn column() -> Self {
+        Self {
+            direction: Direction::Column,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the space between two children
+    pub fn gap(mut self, gap: u32) -> Self {
+        self.gap = gap;
+        self
+    }
+
+    pub fn padding(mut self, padding: Padding) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    pub fn align(mut self, align: Align) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn justify(mut self, justify: Justify) -> Self {
+        self.justify = justify;
+        self
+    }
+
+    /// Computes the size needed to show every child at its preferred size
+    pub fn measure(&self, children: &[(Size, FlexItem)]) -> Size {
+        let mut main = 0;
+        let mut cross = 0;
+        for (size, item) in children {
+            let size = item.clamp(*size);
+            main += size.main(self.direction);
+            cross = cross.max(size.cross(self.direction));
+        }
+        main += self.gap * children.len().saturating_sub(1) as u32;
+
+        match self.direction {
+            Direction::Row => Size::new(
+                main + self.padding.horizontal(),
+                cross + self.padding.vertical(),
+            ),
+            Direction::Column => Size::new(
+                cross + self.padding.horizontal(),
+                main + self.padding.vertical(),
+            ),
+        }
+    }
+
+    /// Splits `area` between the children
+    ///
+    /// Returns one rectangle per child, in the same order as `children`.
+    pub fn arrange(&self, area: Rect, children: &[(Size, FlexItem)]) -> Vec<Rect> {
+        if children.is_empty() {
+            return vec![];
+        }
+
+        let inner = self.padding.apply(area);
+        let (inner_main, inner_cross) = match self.direction {
+            Direction::Row => (inner.width(), inner.height()),
+            Direction::Column => (inner.height(), inner.width()),
+        };
+
+        let gaps = (self.gap * (children.len() - 1) as u32) as f32;
+        let mains = self.resolve_main(inner_main as f32 - gaps, children);
+        let used: f32 = mains.iter().sum::<f32>() + gaps;
+        let free = (inner_main as f32 - used).max(0.0);
+
+        let n = children.len() as f32;
+        let (mut position, between) = match self.justify {
+            Justify::Start => (0.0, 0.0),
+            Justify::Center => (free / 2.0, 0.0),
+            Justify::End => (free, 0.0),
+            Justify::SpaceBetween if children.len() > 1 => (0.0, free / (n - 1.0)),
+            Justify::SpaceBetween => (0.0, 0.0),
+            Justify::SpaceAround => (free / n / 2.0, free / n),
+            Justify::SpaceEvenly => (free / (n + 1.0), free / (n + 1.0)),
+        };
+
+        let mut rects = Vec::with_capacity(children.len());
+        for ((size, item), main) in children.iter().zip(mains) {
+            let preferred = item.clamp(*size);
+            let (min_cross, max_cross) = match self.direction {
+                Direction::Row => (item.min.height, item.max.height),
+                Direction::Column => (item.min.width, item.max.width),
+            };
+            let align = item.align.unwrap_or(self.align);
+            let cross = match align {
+                Align::Stretch => inner_cross.max(min_cross).min(max_cross.max(min_cross)),
+                _ => preferred.cross(self.direction),
+            };
+            let cross_offset = match align {
+                Align::Start | Align::Stretch => 0,
+                Align::Center => inner_cross.saturating_sub(cross) / 2,
+                Align::End => inner_cross.saturating_sub(cross),
+            };
+
+            let start = position.round() as u32;
+            let end = (position + main).round() as u32;
+            let length = end - start;
+            rects.push(match self.direction {
+                Direction::Row => Rect::from_coordinate(
+                    inner.x() + start,
+                    inner.y() + cross_offset,
+                    length,
+                    cross,
+                ),
+                Direction::Column => Rect::from_coordinate(
+                    inner.x() + cross_offset,
+                    inner.y() + start,
+                    cross,
+                    length,
+                ),
+            });
+            position += main + self.gap as f32 + between;
+        }
+        rects
+    }
+
+    /// Resolves the main size of every child so that they fit in `available`
+    ///
+    /// Children that hit their minimum or maximum are frozen and the remaining
+    /// space is distributed again among the others.
+    fn resolve_main(&self, available: f32, children: &[(Size, FlexItem)]) -> Vec<f32> {
+        let limits: Vec<(f32, f32)> = children
+            .iter()
+            .map(|(_, item)| {
+                let min = item.min.main(self.direction) as f32;
+                let max = (item.max.main(self.direction) as f32).max(min);
+                (min, max)
+            })
+            .collect();
+        let bases: Vec<f32> = children
+            .iter()
+            .map(|(size, item)| item.clamp(*size).main(self.direction) as f32)
+            .collect();
+
+        let mut sizes = bases.clone();
+        let mut frozen = vec![false; children.len()];
+        let growing = bases.iter().sum::<f32>() < available;
+
+        loop {
+            let used: f32 = sizes.iter().sum();
+            let free = available - used;
+            if free.abs() < 0.5 {
+                break;
+            }
+
+            let weights: Vec<f32> = children
+                .iter()
+                .enumerate()
+                .map(|(i, (_, item))| match frozen[i] {
+                    true => 0.0,
+                    false if growing => item.grow,
+                    false => item.shrink * bases[i],
+                })
+                .collect();
+            let total: f32 = weights.iter().sum();
+            if total <= 0.0 {
+                break;
+            }
+
+            let mut clamped = false;
+            for i in 0..children.len() {
+                if weights[i] == 0.0 {
+                    continue;
+                }
+                let target = sizes[i] + free * weights[i] / total;
+                let (min, max) = limits[i];
+                sizes[i] = target.max(min).min(max);
+                if sizes[i] != target {
+                    frozen[i] = true;
+                    clamped = true;
+                }
+            }
+            if !clamped {
+                break;
+            }
+        }
+
+        sizes.into_iter().map(|s| s.max(0.0)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: u32, y: u32, width: u32, height: u32) -> (u32, u32, u32, u32) {
+        (x, y, width, height)
+    }
+
+    fn coords(rects: Vec<Rect>) -> Vec<(u32, u32, u32, u32)> {
+        rects
+            .into_iter()
+            .map(|r| (r.x(), r.y(), r.width(), r.height()))
+            .collect()
+    }
+
+    #[test]
+    fn row_with_gap_and_padding() {
+        let flex = Flex::row().gap(10).padding(Padding::all(5));
+        let children = [
+            (Size::new(50, 20), FlexItem::new()),
+            (Size::new(30, 40), FlexItem::new()),
+        ];
+        assert_eq!(flex.measure(&children), Size::new(100, 50));
+
+        let rects = flex.arrange(Rect::from_coordinate(0, 0, 200, 100), &children);
+        assert_eq!(coords(rects), vec![rect(5, 5, 50, 20), rect(65, 5, 30, 40)]);
+    }
+
+    #[test]
+    fn grow_respects_max() {
+        let flex = Flex::row();
+        let children = [
+            (
+                Size::new(10, 10),
+                FlexItem::new().grow(1.0).max(Size::new(30, 10)),
+            ),
+            (Size::new(10, 10), FlexItem::new().grow(1.0)),
+        ];
+        let rects = flex.arrange(Rect::from_coordinate(0, 0, 100, 10), &children);
+        assert_eq!(coords(rects), vec![rect(0, 0, 30, 10), rect(30, 0, 70, 10)]);
+    }
+
+    #[test]
+    fn shrink_respects_min() {
+        let flex = Flex::column();
+        let children = [
+            (Size::new(10, 60), FlexItem::new().min(Size::new(0, 50))),
+            (Size::new(10, 60), FlexItem::new()),
+        ];
+        let rects = flex.arrange(Rect::from_coordinate(0, 0, 10, 80), &children);
+        assert_eq!(coords(rects), vec![rect(0, 0, 10, 50), rect(0, 50, 10, 30)]);
+    }
+
+    #[test]
+    fn justify_and_align() {
+        let flex = Flex::row()
+            .justify(Justify::SpaceBetween)
+            .align(Align::Center);
+        let children = [
+            (Size::new(20, 10), FlexItem::new()),
+            (Size::new(20, 10), FlexItem::new().align(Align::Stretch)),
+        ];
+        let rects = flex.arrange(Rect::from_coordinate(0, 0, 100, 30), &children);
+        assert_eq!(
+            coords(rects),
+            vec![rect(0, 10, 20, 10), rect(80, 0, 20, 30)]
+        );
+
+        let flex = Flex::row().justify(Justify::SpaceEvenly);
+        let rects = flex.arrange(Rect::from_coordinate(0, 0, 100, 30), &children);
+        assert_eq!(
+            coords(rects),
+            vec![rect(20, 0, 20, 10), rect(60, 0, 20, 30)]
+        );
+    }
+}
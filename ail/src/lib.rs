@@ -1,22 +1,39 @@
-use std::{fmt::Debug, sync::Mutex, time::Duration};
-
-use aom::{Object, ID};
-use cursor::Cursor;
-use keyboard::{get_key_state, VK_LBUTTON};
-use management::{RenderManager, WidgetRegistry};
-use widget::{Button, Widget};
-use winit::event_loop::EventLoop;
-
+use std::{collections::HashMap, fmt::Debug};
+
+use aom::ID;
+use binding::Bindings;
+use event::{Dispatcher, Input};
+use management::{RegistryError, RenderManager, WidgetRegistry};
+use render::DisplayList;
+use shortcut::{Outcome, Shortcuts, Stroke};
+use widget::{Choice, Widget};
+use window::Request;
+use winit::{
+    dpi::{PhysicalPosition, PhysicalSize},
+    event_loop::ControlFlow,
+};
+
+pub mod binding;
 pub mod event;
 pub mod keyboard;
+pub mod layout;
 pub mod management;
+pub mod program;
 pub mod render;
+pub mod shortcut;
+pub mod snapshot;
+pub mod testing;
 pub mod widget;
-pub(crate) mod cursor;
+pub mod window;
 
 pub type CursorIcon = winit::window::CursorIcon;
 pub type WindowTheme = winit::window::Theme;
 pub type WindowLevel = winit::window::WindowLevel;
+pub type MouseButton = winit::event::MouseButton;
+pub type Key = winit::keyboard::Key;
+
+pub use keyboard::{KeyCode, Modifiers};
+pub use window::{Icon, Window, WindowBuilder, WindowError, Windows};
 
 /// Represents an area on the screen
 ///
@@ -26,7 +43,7 @@ pub type WindowLevel = winit::window::WindowLevel;
 /// * `top` - Y coordinate of upper left corner
 /// * `right` - X coordinate of lower right corner
 /// * `bottom` - Y coordinate of lower right corner
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Rect {
     left: u32,
     top: u32,
@@ -63,6 +80,39 @@ impl Rect {
             bottom: y + height,
         }
     }
+
+    /// Returns whether the two rectangles share at least one pixel
+    pub fn intersects(&self, other: &Rect) -> bool {
+        self.left < other.right
+            && other.left < self.right
+            && self.top < other.bottom
+            && other.top < self.bottom
+    }
+
+    /// Obtains the area the two rectangles share, if any
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        self.intersects(other).then(|| Rect {
+            left: self.left.max(other.left),
+            top: self.top.max(other.top),
+            right: self.right.min(other.right),
+            bottom: self.bottom.min(other.bottom),
+        })
+    }
+
+    /// Returns whether the pixel at the given coordinate lies in the rectangle
+    pub fn contains(&self, x: u32, y: u32) -> bool {
+        (self.left..self.right).contains(&x) && (self.top..self.bottom).contains(&y)
+    }
+
+    /// Grows the rectangle by `by` on every side, stopping at zero
+    pub(crate) fn inflate(&self, by: u32) -> Self {
+        Self {
+            left: self.left.saturating_sub(by),
+            top: self.top.saturating_sub(by),
+            right: self.right + by,
+            bottom: self.bottom + by,
+        }
+    }
 }
 
 impl Default for Rect {
@@ -74,7 +124,7 @@ impl Default for Rect {
 /// Represents a color
 ///
 /// Initialization with ARGB allows you to create your own colors
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Color {
     Black,
     White,
@@ -83,6 +133,15 @@ pub enum Color {
 }
 
 impl Color {
+    /// Obtains the alpha, red, green and blue components
+    pub fn to_argb(&self) -> [u8; 4] {
+        match *self {
+            Color::Black => [255, 0, 0, 0],
+            Color::White => [255, 255, 255, 255],
+            Color::ARGB(a, r, g, b) => [a, r, g, b],
+        }
+    }
+
     pub fn inversion(&self) -> Color {
         match self {
             Color::Black => Color::White,
@@ -122,6 +181,8 @@ pub struct Theme {
     pub hover: ColorPair,
     pub click: ColorPair,
     pub normal: ColorPair,
+    /// Color of the ring drawn around the widget focused from the keyboard
+    pub focus: Color,
     window: WindowTheme,
     pub bgr: Color,
 }
@@ -143,6 +204,7 @@ impl Theme {
             bgr: Color::White,
             shadow: Color::ARGB(255, 128, 128, 128),
         },
+        focus: Color::ARGB(255, 0, 120, 215),
         window: WindowTheme::Light,
         bgr: Color::ARGB(255, 240, 240, 240),
     };
@@ -162,6 +224,7 @@ impl Theme {
             bgr: Color::ARGB(255, 72, 72, 72),
             shadow: Color::ARGB(255, 200, 200, 200),
         },
+        focus: Color::ARGB(255, 96, 205, 255),
         window: WindowTheme::Dark,
         bgr: Color::ARGB(255, 72, 72, 72),
     };
@@ -182,6 +245,7 @@ impl Theme {
             bgr: Color::White,
             shadow: Color::ARGB(255, 40, 40, 40),
         },
+        focus: Color::Black,
         window: WindowTheme::Light,
         bgr: Color::White,
     };
@@ -202,6 +266,7 @@ impl Theme {
             bgr: Color::Black,
             shadow: Color::ARGB(255, 0, 255, 224),
         },
+        focus: Color::ARGB(255, 255, 255, 0),
         window: WindowTheme::Dark,
         bgr: Color::Black,
     };
@@ -223,6 +288,12 @@ impl Default for Theme {
 pub enum WidgetEvent {
     OnHover,
     OnClick,
+    /// The widget received keyboard focus
+    OnFocus,
+    /// The widget lost keyboard focus
+    OnBlur,
+    /// The user changed the value of the widget, such as the text of an input
+    OnChange,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -230,24 +301,17 @@ pub enum ApplicationEvent {
     Close,
     RedrawRequested,
     OnEvent(WidgetEvent, ID),
-}
-
-pub struct Window {
-    event_loop: Option<EventLoop<()>>,
-    inner: winit::window::Window,
-}
-
-impl Window {
-    pub fn new() -> Self {
-        let event_loop = EventLoop::new().unwrap();
-        let inner = winit::window::WindowBuilder::new()
-            .build(&event_loop)
-            .unwrap();
-        Self {
-            event_loop: Some(event_loop),
-            inner,
-        }
-    }
+    /// A shortcut bound to this message was pressed
+    Shortcut(ID),
+    /// The window whose root widget has this ID was closed
+    WindowClosed(ID),
+    /// The window whose root widget has this ID could not be opened
+    WindowFailed(ID),
+    /// The dialog with this ID was answered and closed
+    DialogClosed(ID, Choice),
+    /// A widget pushed to a container after it was shown, or the new view of a
+    /// [`Program`](program::Program), was dropped, see the error
+    WidgetRejected(RegistryError),
 }
 
 pub struct Application<W>
@@ -257,6 +321,10 @@ where
     window: Window,
     widget: W,
     theme: Theme,
+    focus: Option<ID>,
+    shortcuts: Shortcuts,
+    bindings: Bindings,
+    windows: Windows,
     render_manager: RenderManager,
 }
 
@@ -264,16 +332,63 @@ impl<W> Application<W>
 where
     W: Widget + 'static,
 {
-    pub fn new(window: Window, widget: W) -> Self {
-        let render_manager = RenderManager::new(&window.inner);
+    /// Creates an application showing `widget` in `window`
+    ///
+    /// The widget is laid out to fill the whole window, so a bare [`widget::Button`] is
+    /// stretched to the size of the window. Push it to a [`widget::Panel`] to give it its
+    /// preferred size instead.
+    pub fn new(mut window: Window, widget: W) -> Self {
+        let renderer = window
+            .renderer
+            .take()
+            .expect("the window is used only once");
+        let render_manager = RenderManager::with_renderer(renderer);
         Self {
             window,
             widget,
             theme: Theme::LIGHT,
+            focus: None,
+            shortcuts: Shortcuts::new(),
+            bindings: Bindings::new(),
+            windows: Windows::new(),
             render_manager,
         }
     }
 
+    /// The keyboard shortcuts of the window
+    pub fn shortcuts(&mut self) -> &mut Shortcuts {
+        &mut self.shortcuts
+    }
+
+    /// The bindings of the widgets of the window to signals
+    ///
+    /// These only reach the widgets of this window, further windows are given their own
+    /// with [`Windows::open_with_bindings`].
+    pub fn bindings(&mut self) -> &mut Bindings {
+        &mut self.bindings
+    }
+
+    /// Sets the colors of the widgets, [`Theme::LIGHT`] by default
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    /// Gives keyboard focus to the widget `id` when the application starts
+    ///
+    /// Nothing happens if there is no such widget or it cannot take focus.
+    pub fn set_focus(&mut self, id: ID) {
+        self.focus = Some(id);
+    }
+
+    /// A handle for opening and closing further windows while the application runs
+    pub fn windows(&self) -> Windows {
+        self.windows.clone()
+    }
+
+    /// Runs the event loop until the window the application was created with is closed
+    ///
+    /// `callback` receives the events of every window together with the widgets of the
+    /// window they happened in.
     pub fn run<F>(mut self, mut callback: F)
     where
         F: FnMut(ApplicationEvent, &mut WidgetRegistry),
@@ -281,94 +396,425 @@ where
         let id = self.widget.id();
         self.widget.theme(self.theme);
         self.render_manager.register(self.widget);
-        let mut ids = vec![id];
-
-        let mut cursor_entered = true;
-        let mut clicked = false;
-        let mut click_sended = false;
-
-        self.window
-            .event_loop
-            .unwrap()
+        let mut main = AppWindow::new(self.window.inner, self.render_manager, id, self.theme);
+        main.stage.shortcuts = self.shortcuts;
+        main.stage.bindings = self.bindings;
+        main.stage.apply_bindings();
+        if let Some(id) = self.focus {
+            main.stage.focus(id, &mut callback);
+        }
+        let main_id = main.window.id();
+        let mut windows = HashMap::from([(main_id, main)]);
+        let theme = self.theme;
+        let requests = self.windows;
+
+        let event_loop = self.window.event_loop.unwrap();
+        // Nothing happens between events: widgets invalidate themselves when they change.
+        event_loop.set_control_flow(ControlFlow::Wait);
+        event_loop
             .run(|e, elwt| {
-                if cursor_entered {
-                    let mut events = vec![];
-                    for i in &ids {
-                        let mut registry = self.render_manager.registry.lock().unwrap();
-                        let widget = registry.search_mut(id);
-                        let position = Cursor::get(&self.window.inner);
-                        
-                        for area in widget.area() {
-                            let x = position.x();
-                            let y = position.y();
-                            let cx = (area.left) as i32;
-                            let cy = (area.top) as i32;
-                            let width = (area.right - area.left) as i32;
-                            let height = (area.bottom - area.top) as i32;
-                            if x >= cx && x <= cx + width {
-                                if y >= cy && y <= cy + height {
-                                    clicked = get_key_state(VK_LBUTTON);
-                                    if clicked {
-                                        widget.on_click();
-                                        
-                                        
-                                        if !click_sended {
-                                            events.push((*i, WidgetEvent::OnClick));
-                                            click_sended = true;
-                                        }
-                                        self.window.inner.request_redraw();
-                                    } else {
-                                        widget.on_hover();
-                                        events.push((*i, WidgetEvent::OnHover));
-                                        click_sended = false;
-                                    }
-                                } else {
-                                    widget.unfocus();
-                                }
-                            } else {
-                                widget.unfocus();
-                            }
+                if let winit::event::Event::AboutToWait = e {
+                    // Everything changed while handling the last batch of events is drawn
+                    // in a single frame.
+                    for window in windows.values_mut() {
+                        if window.stage.needs_redraw() {
+                            window.requested = true;
+                            window.window.request_redraw();
                         }
                     }
-
-                    let mut registry = self.render_manager.registry.lock().unwrap();
-
-                    for (id, mes) in events {
-                        callback(ApplicationEvent::OnEvent(mes, id), &mut registry);
-                    }
+                    return;
                 }
-                match e {
-                    winit::event::Event::NewEvents(e) => {}
-                    winit::event::Event::WindowEvent { window_id, event } => match event {
+                if let winit::event::Event::WindowEvent { window_id, event } = e {
+                    let Some(window) = windows.get_mut(&window_id) else {
+                        return;
+                    };
+                    if let Some(input) = Input::from_window_event(&event) {
+                        window.stage.input(input, &mut callback);
+                    }
+
+                    match event {
                         winit::event::WindowEvent::RedrawRequested => {
-                            self.render_manager.render(&[id]);
+                            window.render();
                         }
 
                         winit::event::WindowEvent::Resized(size) => {
-                            self.render_manager.resize(size.width, size.height);
+                            window.stage.resize(size.width, size.height);
                         }
 
                         winit::event::WindowEvent::CloseRequested => {
-                            elwt.exit();
-                        }
-
-                        winit::event::WindowEvent::CursorEntered { device_id } => {
-                            cursor_entered = true;
+                            requests.close(window.id);
                         }
 
                         _ => {}
-                    },
-                    winit::event::Event::Resumed => {}
+                    }
+                }
 
-                    winit::event::Event::MemoryWarning => {}
+                // Callbacks run while handling requests may queue further ones.
+                while let Some(request) = requests.pop() {
+                    match request {
+                        Request::Open(builder, mut widget, bindings) => {
+                            let id = widget.id();
+                            if windows.values().any(|window| window.id == id) {
+                                continue;
+                            }
+                            let opened = builder.open(elwt).ok().and_then(|(window, renderer)| {
+                                let mut render_manager = RenderManager::with_renderer(renderer);
+                                widget.theme(theme);
+                                render_manager.register_boxed(widget).ok()?;
+                                Some((window, render_manager))
+                            });
+                            let Some((window, render_manager)) = opened else {
+                                // The main window stays open as long as the application runs.
+                                let main = windows.get_mut(&main_id).unwrap();
+                                main.stage
+                                    .report(ApplicationEvent::WindowFailed(id), &mut callback);
+                                continue;
+                            };
+                            let mut window = AppWindow::new(window, render_manager, id, theme);
+                            window.stage.bindings = bindings;
+                            window.stage.apply_bindings();
+                            windows.insert(window.window.id(), window);
+                        }
+                        Request::Dialog(id, dialog) => {
+                            let Some(window) = windows.values_mut().find(|window| window.id == id)
+                            else {
+                                continue;
+                            };
+                            window.stage.show_dialog(dialog, &mut callback);
+                        }
+                        Request::Close(id) => {
+                            let Some(window_id) = windows
+                                .iter()
+                                .find(|(_, window)| window.id == id)
+                                .map(|(window_id, _)| *window_id)
+                            else {
+                                continue;
+                            };
+                            if window_id == main_id {
+                                elwt.exit();
+                                continue;
+                            }
+                            let mut window = windows.remove(&window_id).unwrap();
+                            window
+                                .stage
+                                .report(ApplicationEvent::WindowClosed(id), &mut callback);
+                        }
+                        Request::Frame(id, frame) => {
+                            if let Some(window) = windows.values().find(|window| window.id == id) {
+                                frame(window.stage.frame());
+                            }
+                        }
+                    }
+                }
 
-                    _ => {}
+                // Callbacks may have set signals bound to widgets of any window.
+                for window in windows.values_mut() {
+                    window.stage.apply_bindings();
+                    window.stage.report_rejected(&mut callback);
                 }
             })
             .unwrap();
     }
 }
 
+/// A window of a running [`Application`] with the widgets shown in it
+struct AppWindow {
+    window: winit::window::Window,
+    stage: Stage,
+    /// ID of the root widget
+    id: ID,
+    /// Where the input method was last placed
+    ime: Option<Rect>,
+    /// Whether the next redraw was requested for changed widgets rather than by the
+    /// window system
+    requested: bool,
+}
+
+impl AppWindow {
+    fn new(
+        window: winit::window::Window,
+        render_manager: RenderManager,
+        id: ID,
+        theme: Theme,
+    ) -> Self {
+        let size = window.inner_size();
+        let stage = Stage::new(render_manager, vec![id], theme, size.width, size.height);
+        // Input methods start disabled until a text widget has focus.
+        update_ime(&window, None);
+        Self {
+            window,
+            stage,
+            id,
+            ime: None,
+            requested: false,
+        }
+    }
+
+    fn render(&mut self) {
+        // The window system asks for a redraw when the window was uncovered or restored,
+        // whose content has to be drawn again even if no widget changed.
+        if !std::mem::take(&mut self.requested) {
+            self.stage.expose();
+        }
+        self.stage.render();
+        let area = self.stage.text_input_area();
+        if area != self.ime {
+            update_ime(&self.window, area);
+            self.ime = area;
+        }
+    }
+}
+
+/// Enables the input method while text is entered in `area`, placing its candidate window there
+fn update_ime(window: &winit::window::Window, area: Option<Rect>) {
+    window.set_ime_allowed(area.is_some());
+    if let Some(area) = area {
+        window.set_ime_cursor_area(
+            PhysicalPosition::new(area.x(), area.y()),
+            PhysicalSize::new(area.width(), area.height()),
+        );
+    }
+}
+
+/// The widgets of a window together with their renderer and input state
+///
+/// [`Application`] drives a stage from the winit event loop, while
+/// [`testing::Driver`] drives one directly without any window.
+/// Every root widget is laid out to fill the whole window.
+pub(crate) struct Stage {
+    render_manager: RenderManager,
+    dispatcher: Dispatcher,
+    pub(crate) shortcuts: Shortcuts,
+    pub(crate) bindings: Bindings,
+    ids: Vec<ID>,
+    /// Open dialogs from bottom to top, each with the widget to focus once it closes
+    dialogs: Vec<(ID, Option<ID>)>,
+    theme: Theme,
+    width: u32,
+    height: u32,
+    /// Whether the widgets were laid out since the size changed
+    laid_out: bool,
+}
+
+impl Stage {
+    pub(crate) fn new(
+        render_manager: RenderManager,
+        ids: Vec<ID>,
+        theme: Theme,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let mut stage = Self {
+            render_manager,
+            dispatcher: Dispatcher::new(),
+            shortcuts: Shortcuts::new(),
+            bindings: Bindings::new(),
+            ids,
+            dialogs: vec![],
+            theme,
+            width,
+            height,
+            laid_out: false,
+        };
+        stage.layout();
+        stage
+    }
+
+    /// Dispatches `input` and reports the resulting widget events to `callback`
+    ///
+    /// Widgets changed along the way are invalidated, see [`Stage::needs_redraw`].
+    pub(crate) fn input<F>(&mut self, input: Input, callback: &mut F)
+    where
+        F: FnMut(ApplicationEvent, &mut WidgetRegistry),
+    {
+        // Callbacks may have changed widgets since the last input, so hit-testing
+        // needs an up-to-date layout.
+        self.layout();
+        let mut registry = self.render_manager.registry.lock().unwrap();
+
+        // Shortcuts see key presses before the focused widget does, unless a dialog
+        // holds all input.
+        let key = match &input {
+            Input::Key {
+                key,
+                code,
+                pressed: true,
+                ..
+            } if self.dialogs.is_empty() => Some((key, *code)),
+            _ => None,
+        };
+        if let Some((key, code)) = key {
+            let stroke = Stroke::of_key(key, code, self.dispatcher.modifiers());
+            let focused = self.dispatcher.focused();
+            match self.shortcuts.handle(stroke, focused, &mut registry) {
+                Outcome::Ignored => {}
+                Outcome::Consumed => return,
+                Outcome::Message(id) => {
+                    callback(ApplicationEvent::Shortcut(id), &mut registry);
+                    return;
+                }
+            }
+        }
+
+        let roots = match self.dialogs.last() {
+            Some((dialog, _)) => vec![*dialog],
+            None => self.ids.clone(),
+        };
+        let events = self.dispatcher.dispatch(input, &mut registry, &roots);
+        for (id, mes) in events {
+            callback(ApplicationEvent::OnEvent(mes, id), &mut registry);
+        }
+
+        if let Some(&(dialog, restore)) = self.dialogs.last() {
+            let choice = registry
+                .search_mut(dialog)
+                .as_dialog_mut()
+                .and_then(|dialog| dialog.take_choice());
+            if let Some(choice) = choice {
+                self.dialogs.pop();
+                let events = self.dispatcher.focus(restore, &mut registry);
+                // Any widget of the dialog may be hovered, pressed or focused.
+                let removed = registry.descendants(dialog);
+                self.dispatcher.forget(&removed);
+                registry.remove(dialog);
+                for (id, mes) in events.into_iter().filter(|(id, _)| !removed.contains(id)) {
+                    callback(ApplicationEvent::OnEvent(mes, id), &mut registry);
+                }
+                callback(
+                    ApplicationEvent::DialogClosed(dialog, choice),
+                    &mut registry,
+                );
+            }
+        }
+    }
+
+    /// Shows `dialog` over the window, where it takes all input until it is answered
+    ///
+    /// Nothing happens if a widget with the ID of the dialog or one of its children is
+    /// already shown.
+    pub(crate) fn show_dialog<F>(&mut self, mut dialog: Box<dyn Widget>, callback: &mut F)
+    where
+        F: FnMut(ApplicationEvent, &mut WidgetRegistry),
+    {
+        let id = dialog.id();
+        dialog.theme(self.theme);
+        if self.render_manager.register_boxed(dialog).is_err() {
+            return;
+        }
+        self.dialogs.push((id, self.dispatcher.focused()));
+        self.layout();
+        let mut registry = self.render_manager.registry.lock().unwrap();
+        // The widgets below lose focus even to a dialog that cannot take it.
+        let focus = Some(id).filter(|id| registry.search(id).focusable());
+        let mut events = self.dispatcher.focus(None, &mut registry);
+        events.extend(self.dispatcher.focus(focus, &mut registry));
+        for (id, mes) in events {
+            callback(ApplicationEvent::OnEvent(mes, id), &mut registry);
+        }
+    }
+
+    /// Passes signals that changed to the widgets bound to them
+    pub(crate) fn apply_bindings(&mut self) {
+        let mut registry = self.render_manager.registry.lock().unwrap();
+        self.bindings.apply(&mut registry);
+    }
+
+    /// What the last frame shows, see [`RenderManager::frame`]
+    pub(crate) fn frame(&self) -> DisplayList {
+        self.render_manager.frame()
+    }
+
+    /// Draws every widget in the next frame
+    pub(crate) fn expose(&mut self) {
+        self.render_manager.expose();
+    }
+
+    /// Returns whether anything changed since the last frame
+    pub(crate) fn needs_redraw(&self) -> bool {
+        self.render_manager.needs_redraw()
+    }
+
+    /// Reports `event` to `callback` together with the widgets of the stage
+    pub(crate) fn report<F>(&mut self, event: ApplicationEvent, callback: &mut F)
+    where
+        F: FnMut(ApplicationEvent, &mut WidgetRegistry),
+    {
+        callback(event, &mut self.render_manager.registry.lock().unwrap());
+    }
+
+    /// Reports the widgets dropped during layout since the last call
+    pub(crate) fn report_rejected<F>(&mut self, callback: &mut F)
+    where
+        F: FnMut(ApplicationEvent, &mut WidgetRegistry),
+    {
+        let mut registry = self.render_manager.registry.lock().unwrap();
+        for error in registry.take_rejected() {
+            callback(ApplicationEvent::WidgetRejected(error), &mut registry);
+        }
+    }
+
+    /// Gives keyboard focus to the widget `id` and reports the focus events to `callback`
+    pub(crate) fn focus<F>(&mut self, id: ID, callback: &mut F)
+    where
+        F: FnMut(ApplicationEvent, &mut WidgetRegistry),
+    {
+        let mut registry = self.render_manager.registry.lock().unwrap();
+        for (id, mes) in self.dispatcher.focus(Some(id), &mut registry) {
+            callback(ApplicationEvent::OnEvent(mes, id), &mut registry);
+        }
+    }
+
+    /// Where the focused widget takes text, if it does
+    pub(crate) fn text_input_area(&self) -> Option<Rect> {
+        let registry = self.render_manager.registry.lock().unwrap();
+        let id = self
+            .dispatcher
+            .focused()
+            .filter(|id| registry.contains(*id))?;
+        registry.search(&id).text_input_area()
+    }
+
+    pub(crate) fn resize(&mut self, width: u32, height: u32) {
+        self.render_manager.resize(width, height);
+        self.width = width;
+        self.height = height;
+        self.laid_out = false;
+        self.layout();
+    }
+
+    /// Lays out and renders the widgets, after which nothing needs to be redrawn
+    ///
+    /// Widgets changed by callbacks since the last layout are placed anew first.
+    pub(crate) fn render(&mut self) {
+        self.layout();
+        let focus = match self.dispatcher.focus_visible() {
+            true => self.dispatcher.focused().map(|id| (id, self.theme.focus)),
+            false => None,
+        };
+        // Dialogs show their own selection instead of a focus ring.
+        let focus = focus.filter(|(id, _)| !self.dialogs.iter().any(|(dialog, _)| dialog == id));
+        self.render_manager.render(&self.roots(), focus);
+    }
+
+    /// Lays out the widgets unless neither the size nor their layout changed since the last one
+    fn layout(&mut self) {
+        if self.laid_out && !self.render_manager.registry.lock().unwrap().needs_layout() {
+            return;
+        }
+        let area = Rect::from_coordinate(0, 0, self.width, self.height);
+        self.render_manager.layout(&self.roots(), area);
+        // Registering children pushed to containers since is part of this layout.
+        self.render_manager.registry.lock().unwrap().laid_out();
+        self.laid_out = true;
+    }
+
+    /// The root widgets followed by the dialogs over them, in drawing order
+    fn roots(&self) -> Vec<ID> {
+        let dialogs = self.dialogs.iter().map(|(dialog, _)| *dialog);
+        self.ids.iter().copied().chain(dialogs).collect()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
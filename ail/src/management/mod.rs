@@ -1,26 +1,86 @@
-use std::{collections::HashMap, fmt::Debug, sync::Mutex};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    marker::PhantomData,
+    sync::Mutex,
+};
 
 use aom::ID;
 use winit::window::Window;
 
-use crate::{render::Renderer, widget::Widget};
+use crate::{
+    layout::{Size, TextMetrics},
+    render::{DisplayList, Image, Item, Renderer},
+    widget::Widget,
+    Color, Rect, WindowError,
+};
+
+/// Width of the ring drawn around the focused widget
+const FOCUS_RING: u32 = 2;
 
 pub struct RenderManager {
     pub(crate) registry: Mutex<WidgetRegistry>,
     renderer: Renderer,
+    /// Areas of the widgets as of the last frame
+    drawn: HashMap<ID, Vec<Rect>>,
+    /// What each widget drew when it was last repainted
+    lists: HashMap<ID, DisplayList>,
+    /// The widgets of the last frame in drawing order
+    order: Vec<ID>,
+    /// The focus ring of the last frame
+    ring: DisplayList,
+    /// Whether the whole surface needs to be drawn, such as after a resize
+    exposed: bool,
 }
 
 impl RenderManager {
-    pub fn new(window: &Window) -> Self {
-        let registry = Mutex::new(WidgetRegistry::new());
-        let renderer = Renderer::new(window);
-        Self { registry, renderer }
+    pub fn new(window: &Window) -> Result<Self, WindowError> {
+        Ok(Self::with_renderer(Renderer::new(window)?))
+    }
+
+    /// Creates a render manager backed by an offscreen renderer
+    ///
+    /// See [`Renderer::offscreen`].
+    pub fn offscreen(width: u32, height: u32) -> Self {
+        Self::with_renderer(Renderer::offscreen(width, height))
+    }
+
+    pub(crate) fn with_renderer(renderer: Renderer) -> Self {
+        Self {
+            registry: Mutex::new(WidgetRegistry::new()),
+            renderer,
+            drawn: HashMap::new(),
+            lists: HashMap::new(),
+            order: vec![],
+            ring: DisplayList::new(),
+            exposed: true,
+        }
+    }
+
+    /// Obtains the last rendered frame if this is an offscreen render manager
+    pub fn image(&self) -> Option<&Image> {
+        self.renderer.image()
     }
 
     pub fn resize(&mut self, width: u32, height: u32) {
         self.renderer.resize(width, height);
+        self.exposed = true;
+    }
+
+    /// Draws everything in the next frame, as the window system lost what the surface showed
+    pub fn expose(&mut self) {
+        self.exposed = true;
     }
 
+    /// Returns whether the next frame would draw anything
+    pub fn needs_redraw(&self) -> bool {
+        self.exposed || self.registry.lock().unwrap().is_dirty()
+    }
+
+    /// Registers a widget and, if it is a container, all of its descendants
+    ///
+    /// # Panics
+    /// Panics if two of the widgets share an ID, or one of them is registered already.
     pub fn register<W>(&mut self, widget: W)
     where
         W: Widget + 'static,
@@ -28,48 +88,479 @@ impl RenderManager {
         self.registry.lock().unwrap().register(widget);
     }
 
-    pub fn render(&mut self, id: &[ID]) {
-        self.renderer.begin();
+    /// Registers a widget tree, leaving the registry unchanged if an ID is taken
+    pub(crate) fn register_boxed(&mut self, widget: Box<dyn Widget>) -> Result<(), RegistryError> {
+        self.registry.lock().unwrap().insert(widget, None)
+    }
+
+    /// Lays out the widgets in `id` to fill `area`, measuring text with the renderer's font
+    pub fn layout(&mut self, id: &[ID], area: Rect) {
+        let mut registry = self.registry.lock().unwrap();
+        for root in id {
+            registry.layout(*root, area, self.renderer.metrics());
+        }
+    }
+
+    /// Renders the widgets in `id` together with all of their descendants
+    ///
+    /// With `focus`, a ring of the given color is drawn around that widget on top of the others.
+    ///
+    /// Only the areas of widgets invalidated, moved or removed since the last frame are
+    /// repainted, together with the widgets overlapping them. Everything is drawn after a
+    /// resize, or if the surface cannot clip.
+    pub fn render(&mut self, id: &[ID], focus: Option<(ID, Color)>) {
         let mut registry = self.registry.lock().unwrap();
-        for i in id {
-            let w = registry.search_mut(*i);
-            self.renderer.render(w);
+        let order: Vec<ID> = id
+            .iter()
+            .flat_map(|root| registry.descendants(*root))
+            .collect();
+        let areas: HashMap<ID, Vec<Rect>> = order
+            .iter()
+            .map(|id| (*id, registry.search(id).area()))
+            .collect();
+        let dirty = registry.take_dirty();
+        let drawn = std::mem::replace(&mut self.drawn, areas);
+        let areas = &self.drawn;
+        self.lists.retain(|id, _| areas.contains_key(id));
+        self.order = order.clone();
+
+        let partial = if std::mem::take(&mut self.exposed) {
+            None
+        } else {
+            let damage = damage(&drawn, &self.drawn, &dirty);
+            if damage.is_empty() {
+                return;
+            }
+            self.renderer.begin_damaged(&damage).then_some(damage)
+        };
+        if partial.is_none() {
+            self.renderer.begin();
         }
+
+        for i in order {
+            let areas = &self.drawn[&i];
+            // Widgets without an area may draw anywhere.
+            let affected = match &partial {
+                Some(damage) => {
+                    areas.is_empty()
+                        || areas
+                            .iter()
+                            .any(|area| damage.iter().any(|d| d.intersects(area)))
+                }
+                None => true,
+            };
+            // A widget never drawn has no list to stand in for it in the frame.
+            if affected || !self.lists.contains_key(&i) {
+                let list = self.renderer.render(registry.search_mut(i));
+                self.lists.insert(i, list);
+            }
+        }
+        self.ring = match focus.filter(|(id, _)| registry.contains(*id)) {
+            Some((focused, color)) => registry
+                .search(&focused)
+                .area()
+                .into_iter()
+                .flat_map(|area| focus_ring(area, color))
+                .collect(),
+            None => DisplayList::new(),
+        };
+        self.renderer.draw(&self.ring);
         self.renderer.end();
     }
+
+    /// Everything the last frame shows, in drawing order
+    ///
+    /// Widgets that were not repainted contribute what they drew before, so the list
+    /// describes the whole window even after a partial repaint. Its text form can be sent
+    /// along with a bug report and replayed elsewhere.
+    pub fn frame(&self) -> DisplayList {
+        self.order
+            .iter()
+            .filter_map(|id| self.lists.get(id))
+            .chain([&self.ring])
+            .flat_map(|list| list.items().iter().cloned())
+            .collect()
+    }
+}
+
+/// Collects the regions to repaint: the old and new areas of every widget that changed
+///
+/// Areas are grown by the width of the focus ring, which is drawn just outside of them.
+fn damage(
+    drawn: &HashMap<ID, Vec<Rect>>,
+    areas: &HashMap<ID, Vec<Rect>>,
+    dirty: &HashSet<ID>,
+) -> Vec<Rect> {
+    let mut damage: Vec<Rect> = vec![];
+    for (id, old) in drawn {
+        let new = areas.get(id);
+        if dirty.contains(id) || new != Some(old) {
+            damage.extend(old);
+        }
+    }
+    for (id, new) in areas {
+        if dirty.contains(id) || drawn.get(id) != Some(new) {
+            damage.extend(new);
+        }
+    }
+    damage.iter().map(|area| area.inflate(FOCUS_RING)).collect()
+}
+
+/// Outlines `area` with bars just outside of it
+fn focus_ring(area: Rect, color: Color) -> DisplayList {
+    let x = area.x().saturating_sub(FOCUS_RING);
+    let y = area.y().saturating_sub(FOCUS_RING);
+    let width = area.x() + area.width() + FOCUS_RING - x;
+    let height = area.y() + area.height() + FOCUS_RING - y;
+    let right = area.x() + area.width();
+    let bottom = area.y() + area.height();
+    [
+        (x, y, width, FOCUS_RING),
+        (x, bottom, width, FOCUS_RING),
+        (x, y, FOCUS_RING, height),
+        (right, y, FOCUS_RING, height),
+    ]
+    .into_iter()
+    .map(|(x, y, width, height)| Item::Rectangle(Rect::from_coordinate(x, y, width, height), color))
+    .collect()
 }
 
 pub struct WidgetRegistry {
     map: HashMap<ID, Box<dyn Widget>>,
+    parents: HashMap<ID, ID>,
+    /// Widgets changed since the last frame
+    dirty: HashSet<ID>,
+    /// Whether widgets were added, removed or changed in a way that may move or resize
+    /// them since the last layout
+    needs_layout: bool,
+    /// Why children pushed to containers after registration were dropped
+    rejected: Vec<RegistryError>,
 }
 
 impl WidgetRegistry {
     pub fn new() -> Self {
         Self {
             map: HashMap::new(),
+            parents: HashMap::new(),
+            dirty: HashSet::new(),
+            needs_layout: true,
+            rejected: vec![],
+        }
+    }
+
+    /// Obtains the widget with the given ID as `T`
+    ///
+    /// Returns `None` if there is no such widget or it is not a `T`.
+    pub fn get<T>(&self, id: ID) -> Option<&T>
+    where
+        T: Widget,
+    {
+        self.try_get(id).ok()
+    }
+
+    /// Mutable version of [`WidgetRegistry::get`]
+    pub fn get_mut<T>(&mut self, id: ID) -> Option<&mut T>
+    where
+        T: Widget,
+    {
+        self.try_get_mut(id).ok()
+    }
+
+    /// Obtains the widget with the given ID as `T`, telling why the lookup failed
+    pub fn try_get<T>(&self, id: ID) -> Result<&T, RegistryError>
+    where
+        T: Widget,
+    {
+        let widget = self.map.get(&id).ok_or(RegistryError::NotFound(id))?;
+        widget
+            .as_ref()
+            .as_any()
+            .downcast_ref()
+            .ok_or(RegistryError::TypeMismatch {
+                id,
+                expected: std::any::type_name::<T>(),
+            })
+    }
+
+    /// Mutable version of [`WidgetRegistry::try_get`]
+    pub fn try_get_mut<T>(&mut self, id: ID) -> Result<&mut T, RegistryError>
+    where
+        T: Widget,
+    {
+        let widget = self.map.get(&id).ok_or(RegistryError::NotFound(id))?;
+        if !widget.as_ref().as_any().is::<T>() {
+            return Err(RegistryError::TypeMismatch {
+                id,
+                expected: std::any::type_name::<T>(),
+            });
         }
+        // Only a widget that can be changed is marked as changed.
+        self.invalidate(id);
+        let widget = self.map.get_mut(&id).unwrap();
+        Ok(widget.as_mut().as_any_mut().downcast_mut().unwrap())
     }
 
-    pub fn get<T>(&self, id: ID) -> Option<&Box<T>> {
-        match self.map.get(&id) {
-            Some(s) => return Some(unsafe { std::mem::transmute(s) }),
-            None => return None,
+    /// Marks the widget as changed, so that the window is laid out and drawn again
+    ///
+    /// Widgets obtained through [`WidgetRegistry::get_mut`] and widgets receiving input are
+    /// marked already. Several changes before the next frame cause a single redraw.
+    pub fn invalidate(&mut self, id: ID) {
+        if self.map.contains_key(&id) {
+            self.dirty.insert(id);
+            self.needs_layout = true;
         }
     }
 
-    pub fn get_mut<T>(&mut self, id: ID) -> Option<&mut Box<T>> {
-        match self.map.get_mut(&id) {
-            Some(s) => return Some(unsafe { std::mem::transmute(s) }),
-            None => return None,
+    /// Marks the widget as changed in its looks only, so that it is drawn again in place
+    ///
+    /// Used for hover, presses and focus, which neither move nor resize widgets.
+    pub(crate) fn repaint(&mut self, id: ID) {
+        if self.map.contains_key(&id) {
+            self.dirty.insert(id);
         }
     }
 
+    /// Returns whether a widget changed since the last frame
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+
+    /// Returns whether a widget with the given ID is registered
+    pub fn contains(&self, id: ID) -> bool {
+        self.map.contains_key(&id)
+    }
+
+    /// Obtains the ID of the container holding the widget
+    pub fn parent(&self, id: ID) -> Option<ID> {
+        self.parents.get(&id).copied()
+    }
+
+    /// Obtains the IDs of the children of a container
+    ///
+    /// Widgets that are not containers have no children.
+    pub fn children(&self, id: ID) -> Vec<ID> {
+        self.map
+            .get(&id)
+            .and_then(|w| w.as_container())
+            .map(|c| c.children())
+            .unwrap_or_default()
+    }
+
+    /// Obtains the widget and everything below it in drawing order
+    pub fn descendants(&self, id: ID) -> Vec<ID> {
+        let mut ids = vec![];
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            if !self.map.contains_key(&id) {
+                continue;
+            }
+            ids.push(id);
+            stack.extend(self.children(id).into_iter().rev());
+        }
+        ids
+    }
+
+    /// Computes the preferred size of a widget
+    ///
+    /// Containers are measured from the preferred sizes of their children.
+    pub fn measure(&self, id: ID, metrics: &dyn TextMetrics) -> Size {
+        let widget = &self.map[&id];
+        match widget.as_container() {
+            Some(container) => {
+                let sizes: Vec<Size> = container
+                    .children()
+                    .into_iter()
+                    .filter(|child| self.map.contains_key(child))
+                    .map(|child| self.measure(child, metrics))
+                    .collect();
+                container.measure_children(&sizes)
+            }
+            None => widget.measure(metrics),
+        }
+    }
+
+    /// Places a widget in `area` and lays out everything below it
+    ///
+    /// Children pushed to a container after it was registered are registered first. Those
+    /// with the ID of a widget registered already are dropped, which is reported as
+    /// [`crate::ApplicationEvent::WidgetRejected`].
+    pub fn layout(&mut self, id: ID, area: Rect, metrics: &dyn TextMetrics) {
+        self.adopt(id);
+        let children: Vec<ID> = self
+            .children(id)
+            .into_iter()
+            .filter(|child| self.map.contains_key(child))
+            .collect();
+        let sizes: Vec<Size> = children
+            .iter()
+            .map(|child| self.measure(*child, metrics))
+            .collect();
+
+        let widget = self.search_mut(id);
+        widget.arrange(area, metrics);
+        let rects = match widget.as_container() {
+            Some(container) => container.arrange_children(area, &sizes),
+            None => return,
+        };
+        for (child, rect) in children.into_iter().zip(rects) {
+            self.layout(child, rect, metrics);
+        }
+    }
+
+    /// Forgets the widgets changed since the last frame, returning them
+    pub(crate) fn take_dirty(&mut self) -> HashSet<ID> {
+        std::mem::take(&mut self.dirty)
+    }
+
+    /// Whether the layout is stale, see [`WidgetRegistry::invalidate`]
+    pub(crate) fn needs_layout(&self) -> bool {
+        self.needs_layout
+    }
+
+    /// Records that the widgets were laid out
+    pub(crate) fn laid_out(&mut self) {
+        self.needs_layout = false;
+    }
+
+    /// Registers a widget and, if it is a container, all of its descendants
+    ///
+    /// # Panics
+    /// Panics if two of the widgets share an ID, or one of them is registered already.
     pub(crate) fn register<W>(&mut self, widget: W)
     where
         W: Widget + 'static,
     {
+        if let Err(e) = self.insert(Box::new(widget), None) {
+            panic!("failed to register widgets: {}", e);
+        }
+    }
+
+    /// Registers `widget` and its descendants below `parent`
+    ///
+    /// Nothing is registered if one of their IDs is taken already.
+    fn insert(&mut self, widget: Box<dyn Widget>, parent: Option<ID>) -> Result<(), RegistryError> {
+        let mut fresh = WidgetRegistry::new();
+        fresh.insert_tree(widget, parent)?;
+        if let Some(id) = fresh.map.keys().find(|id| self.map.contains_key(id)) {
+            return Err(RegistryError::Duplicate(*id));
+        }
+        self.parents.extend(fresh.parents);
+        for (id, widget) in fresh.map {
+            self.map.insert(id, widget);
+            self.invalidate(id);
+        }
+        Ok(())
+    }
+
+    /// Adds `widget` and its descendants without checking the widgets registered before
+    fn insert_tree(
+        &mut self,
+        mut widget: Box<dyn Widget>,
+        parent: Option<ID>,
+    ) -> Result<(), RegistryError> {
         let id = widget.id();
-        self.map.insert(id, Box::new(widget));
+        if self.map.contains_key(&id) {
+            return Err(RegistryError::Duplicate(id));
+        }
+        let children = widget
+            .as_container_mut()
+            .map(|c| c.take_children())
+            .unwrap_or_default();
+        self.map.insert(id, widget);
+        if let Some(parent) = parent {
+            self.parents.insert(id, parent);
+        }
+        for child in children {
+            self.insert_tree(child, Some(id))?;
+        }
+        Ok(())
+    }
+
+    /// Registers the children pushed to the container `id` since it was registered
+    ///
+    /// Children whose IDs are taken are dropped, see [`WidgetRegistry::take_rejected`].
+    fn adopt(&mut self, id: ID) {
+        let children = match self.map.get_mut(&id).and_then(|w| w.as_container_mut()) {
+            Some(container) => container.take_children(),
+            None => return,
+        };
+        for child in children {
+            let child_id = child.id();
+            if let Err(e) = self.insert(child, Some(id)) {
+                if let Some(container) = self.search_mut(id).as_container_mut() {
+                    container.reject_child(child_id);
+                }
+                self.rejected.push(e);
+            }
+        }
+    }
+
+    /// Takes the errors of children that were dropped instead of being registered
+    pub(crate) fn take_rejected(&mut self) -> Vec<RegistryError> {
+        std::mem::take(&mut self.rejected)
+    }
+
+    /// Replaces the widget `root` and its descendants with `widget` and its descendants
+    ///
+    /// Widgets whose ID and type are found in both trees are kept and take over the
+    /// properties of their replacement through [`Widget::reconcile`], so they keep their state.
+    ///
+    /// Nothing is replaced if the new tree uses an ID twice, or one registered outside of `root`.
+    pub(crate) fn reconcile(
+        &mut self,
+        root: ID,
+        widget: Box<dyn Widget>,
+    ) -> Result<(), RegistryError> {
+        let mut fresh = WidgetRegistry::new();
+        fresh.insert_tree(widget, self.parent(root))?;
+        let replaced: HashSet<ID> = self.descendants(root).into_iter().collect();
+        if let Some(id) = fresh
+            .map
+            .keys()
+            .find(|id| self.map.contains_key(id) && !replaced.contains(id))
+        {
+            return Err(RegistryError::Duplicate(*id));
+        }
+        for id in replaced {
+            if !fresh.map.contains_key(&id) {
+                self.map.remove(&id);
+                self.parents.remove(&id);
+                self.dirty.remove(&id);
+            }
+        }
+        for (id, mut widget) in fresh.map {
+            let kept = match self.map.get_mut(&id) {
+                Some(old) => {
+                    old.as_ref().as_any().type_id() == widget.as_ref().as_any().type_id()
+                        && old.reconcile(widget.as_mut())
+                }
+                None => false,
+            };
+            if !kept {
+                self.map.insert(id, widget);
+            }
+            self.invalidate(id);
+            match fresh.parents.get(&id) {
+                Some(parent) => self.parents.insert(id, *parent),
+                None => self.parents.remove(&id),
+            };
+        }
+        Ok(())
+    }
+
+    /// Removes a widget together with all of its descendants
+    ///
+    /// The container holding it, if any, is marked as changed.
+    pub(crate) fn remove(&mut self, id: ID) {
+        if let Some(parent) = self.parent(id) {
+            self.invalidate(parent);
+        }
+        for id in self.descendants(id) {
+            self.map.remove(&id);
+            self.parents.remove(&id);
+            self.dirty.remove(&id);
+        }
+        self.needs_layout = true;
     }
 
     pub(crate) fn search(&self, id: &ID) -> &Box<dyn Widget> {
@@ -80,3 +571,285 @@ impl WidgetRegistry {
         self.map.get_mut(&id).unwrap()
     }
 }
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RegistryError {
+    /// No widget is registered with the ID
+    NotFound(ID),
+    /// The widget is not of the requested type
+    TypeMismatch { id: ID, expected: &'static str },
+    /// Another widget is registered with the ID already
+    Duplicate(ID),
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryError::NotFound(id) => write!(f, "no widget is registered as `{}`", id),
+            RegistryError::TypeMismatch { id, expected } => {
+                write!(f, "widget `{}` is not a `{}`", id, expected)
+            }
+            RegistryError::Duplicate(id) => {
+                write!(f, "a widget is registered as `{}` already", id)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+/// A widget ID that remembers the type of the widget
+///
+/// Handles are obtained from the widget itself, so looking one up can only fail
+/// if the widget was never registered.
+///
+/// ```
+/// use ail::{management::Handle, widget::Button};
+///
+/// let button = Button::new("my_button");
+/// let handle: Handle<Button> = Handle::of(&button);
+/// ```
+pub struct Handle<T> {
+    id: ID,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Handle<T>
+where
+    T: Widget,
+{
+    pub fn of(widget: &T) -> Self {
+        Self {
+            id: widget.id(),
+            marker: PhantomData,
+        }
+    }
+
+    pub fn id(&self) -> ID {
+        self.id
+    }
+
+    pub fn get<'a>(&self, registry: &'a WidgetRegistry) -> Option<&'a T> {
+        registry.get(self.id)
+    }
+
+    pub fn get_mut<'a>(&self, registry: &'a mut WidgetRegistry) -> Option<&'a mut T> {
+        registry.get_mut(self.id)
+    }
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Handle").field(&self.id).finish()
+    }
+}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        layout::{Flex, Padding},
+        testing::Driver,
+        widget::{Button, Layout, Panel},
+    };
+
+    #[derive(Debug)]
+    struct Other;
+
+    impl Widget for Other {}
+    impl crate::widget::Drawable for Other {}
+    impl crate::widget::EventListener for Other {}
+
+    impl crate::widget::Layout for Other {
+        fn area(&self) -> Vec<crate::Rect> {
+            vec![]
+        }
+    }
+
+    impl aom::Object for Other {
+        fn id(&self) -> ID {
+            ID::from("other")
+        }
+    }
+
+    #[test]
+    fn typed_lookup() {
+        let mut registry = WidgetRegistry::new();
+        let button = Button::new("button");
+        let handle = Handle::of(&button);
+        registry.register(button);
+        registry.register(Other);
+
+        assert_eq!(
+            aom::Object::id(handle.get(&registry).unwrap()),
+            ID::from("button")
+        );
+        registry
+            .get_mut::<Button>(ID::from("button"))
+            .unwrap()
+            .set_text("changed");
+        assert_eq!(handle.get(&registry).unwrap().text(), "changed");
+        assert!(registry.get::<Other>(ID::from("button")).is_none());
+
+        // Failed lookups leave the widget untouched.
+        registry.take_dirty();
+        assert!(registry.get_mut::<Other>(ID::from("button")).is_none());
+        assert!(!registry.is_dirty());
+        assert_eq!(
+            registry.try_get::<Button>(ID::from("other")).unwrap_err(),
+            RegistryError::TypeMismatch {
+                id: ID::from("other"),
+                expected: std::any::type_name::<Button>(),
+            }
+        );
+        assert_eq!(
+            registry.try_get::<Button>(ID::from("missing")).unwrap_err(),
+            RegistryError::NotFound(ID::from("missing"))
+        );
+    }
+
+    #[test]
+    fn duplicate_ids_are_rejected() {
+        let mut registry = WidgetRegistry::new();
+        registry.register(Button::new("button"));
+
+        let mut panel = Panel::new("panel");
+        panel.push(Button::new("new"));
+        panel.push(Button::new("button"));
+        assert_eq!(
+            registry.insert(Box::new(panel), None),
+            Err(RegistryError::Duplicate(ID::from("button")))
+        );
+        assert!(!registry.contains(ID::from("panel")));
+        assert!(!registry.contains(ID::from("new")));
+
+        let mut panel = Panel::new("panel");
+        panel.push(Button::new("twice"));
+        panel.push(Button::new("twice"));
+        assert_eq!(
+            registry.insert(Box::new(panel), None),
+            Err(RegistryError::Duplicate(ID::from("twice")))
+        );
+    }
+
+    fn buttons() -> Panel {
+        let mut panel = Panel::new("panel");
+        panel.set_layout(Flex::column().padding(Padding::all(10)));
+        for id in ["first", "second", "third"] {
+            panel.push(Button::new(id));
+        }
+        panel
+    }
+
+    #[test]
+    fn partial_frames_match_full_frames() {
+        let mut driver = Driver::new(buttons(), 300, 200);
+        driver.step();
+        let second = driver
+            .registry()
+            .get::<Button>(ID::from("second"))
+            .unwrap()
+            .area()[0];
+        let (x, y) = (second.x() as i32 + 5, second.y() as i32 + 5);
+        driver.pointer_move(x, y).step();
+        driver
+            .pointer_move(x, y + second.height() as i32 + 12)
+            .step();
+
+        let mut full = Driver::new(buttons(), 300, 200);
+        full.pointer_move(x, y + second.height() as i32 + 12).step();
+        assert_eq!(driver.image(), full.image());
+        assert_eq!(driver.frame(), full.frame());
+    }
+
+    #[test]
+    fn frames_can_be_dumped_and_diffed() {
+        let mut driver = Driver::new(buttons(), 300, 200);
+        driver.step();
+        let before: DisplayList = driver.frame().to_string().parse().unwrap();
+        assert_eq!(before, driver.frame());
+
+        let second = driver
+            .registry()
+            .get::<Button>(ID::from("second"))
+            .unwrap()
+            .area()[0];
+        driver
+            .pointer_move(second.x() as i32 + 5, second.y() as i32 + 5)
+            .step();
+        let after = driver.frame();
+        let changes = before.diff(&after);
+        assert!(!changes.is_empty());
+        assert!(changes.len() < after.len());
+    }
+
+    #[test]
+    fn exposed_surfaces_are_drawn_in_full() {
+        let roots = [ID::from("panel")];
+        let mut manager = RenderManager::offscreen(300, 200);
+        manager.register(buttons());
+        manager.layout(&roots, Rect::from_coordinate(0, 0, 300, 200));
+        manager.render(&roots, None);
+
+        // Changing a widget without invalidating it leaves the next frame empty.
+        manager
+            .registry
+            .lock()
+            .unwrap()
+            .search_mut(ID::from("second"))
+            .as_mut()
+            .as_any_mut()
+            .downcast_mut::<Button>()
+            .unwrap()
+            .set_text("changed");
+        assert!(!manager.needs_redraw());
+        manager.render(&roots, None);
+        assert!(!manager.frame().to_string().contains("changed"));
+
+        manager.expose();
+        assert!(manager.needs_redraw());
+        manager.render(&roots, None);
+        assert!(manager.frame().to_string().contains("changed"));
+    }
+
+    #[test]
+    fn damage_covers_changed_widgets() {
+        let button = Rect::from_coordinate(10, 10, 100, 20);
+        let other = Rect::from_coordinate(10, 40, 100, 20);
+        let drawn = HashMap::from([
+            (ID::from("button"), vec![button]),
+            (ID::from("other"), vec![other]),
+        ]);
+
+        let dirty = HashSet::from([ID::from("button")]);
+        assert_eq!(
+            damage(&drawn, &drawn, &dirty),
+            vec![button.inflate(FOCUS_RING), button.inflate(FOCUS_RING)]
+        );
+        assert!(damage(&drawn, &drawn, &HashSet::new()).is_empty());
+
+        // Removed widgets leave their area behind.
+        let mut areas = drawn.clone();
+        areas.remove(&ID::from("other"));
+        assert_eq!(
+            damage(&drawn, &areas, &HashSet::new()),
+            vec![other.inflate(FOCUS_RING)]
+        );
+    }
+}
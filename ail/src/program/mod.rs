@@ -0,0 +1,523 @@
+//! Applications written as a state, the messages that change it and a view of it
+//!
+//! A [`Program`] owns the state of the application. `view` builds the widgets showing the
+//! state, and tells which messages their events send. Each message is handed to `update`
+//! together with the state, after which `view` is called again and the new widgets are
+//! compared with those on screen: widgets keeping their ID and type are updated in place,
+//! so hover, focus and the caret of text inputs survive.
+//!
+//! Events that do not belong to a widget, such as shortcuts and closed dialogs, are turned
+//! into messages by [`View::on_shortcut`] and [`View::on_application`].
+//!
+//! ```no_run
+//! use ail::{
+//!     program::{Program, View},
+//!     widget::{Button, Label, Panel},
+//!     Window,
+//! };
+//! use aom::ID;
+//!
+//! #[derive(Clone)]
+//! enum Message {
+//!     Increment,
+//! }
+//!
+//! fn update(count: &mut u32, message: Message) {
+//!     match message {
+//!         Message::Increment => *count += 1,
+//!     }
+//! }
+//!
+//! fn view(count: &u32) -> View<Message> {
+//!     let mut label = Label::new("count");
+//!     label.set_text(count.to_string());
+//!     let mut button = Button::new("increment");
+//!     button.set_text("+1");
+//!     let mut panel = Panel::new("panel");
+//!     panel.push(label);
+//!     panel.push(button);
+//!     View::new(panel).on_click(ID::from("increment"), Message::Increment)
+//! }
+//!
+//! Program::new(Window::new(), 0, update, view).run();
+//! ```
+
+use std::fmt;
+
+use aom::{Object, ID};
+
+use crate::{
+    binding::Bindings,
+    management::WidgetRegistry,
+    shortcut::Shortcuts,
+    widget::{Container, Drawable, EventListener, Layout, Widget},
+    Application, ApplicationEvent, Rect, Theme, WidgetEvent, Window, Windows,
+};
+
+/// ID of the widget holding the view, so that views may change their root widget
+const ROOT: &str = "ail::program";
+
+type Handler<M> = Box<dyn Fn(WidgetEvent, &WidgetRegistry) -> Option<M>>;
+
+type EventHandler<M> = Box<dyn Fn(ApplicationEvent, &WidgetRegistry) -> Option<M>>;
+
+/// A tree of widgets built from the state, with the messages its events send
+pub struct View<M> {
+    root: Box<dyn Widget>,
+    handlers: Vec<(ID, Handler<M>)>,
+    events: Vec<EventHandler<M>>,
+}
+
+impl<M> View<M>
+where
+    M: 'static,
+{
+    pub fn new<W>(root: W) -> Self
+    where
+        W: Widget + 'static,
+    {
+        Self {
+            root: Box::new(root),
+            handlers: vec![],
+            events: vec![],
+        }
+    }
+
+    /// Sends `message` when the widget `id` is clicked
+    pub fn on_click(self, id: ID, message: M) -> Self
+    where
+        M: Clone,
+    {
+        self.on(id, move |event, _| match event {
+            WidgetEvent::OnClick => Some(message.clone()),
+            _ => None,
+        })
+    }
+
+    /// Sends the message returned by `handler` when the user changes the value of the widget `id`
+    ///
+    /// The handler reads the new value from the widget itself.
+    ///
+    /// ```
+    /// use ail::{program::View, widget::TextInput};
+    /// use aom::ID;
+    ///
+    /// enum Message {
+    ///     NameChanged(String),
+    /// }
+    ///
+    /// let view = View::new(TextInput::new("name")).on_change(ID::from("name"), |registry| {
+    ///     let input = registry.get::<TextInput>(ID::from("name"))?;
+    ///     Some(Message::NameChanged(input.text().to_string()))
+    /// });
+    /// ```
+    pub fn on_change<F>(self, id: ID, handler: F) -> Self
+    where
+        F: Fn(&WidgetRegistry) -> Option<M> + 'static,
+    {
+        self.on(id, move |event, registry| match event {
+            WidgetEvent::OnChange => handler(registry),
+            _ => None,
+        })
+    }
+
+    /// Calls `handler` with every event of the widget `id`, sending the message it returns
+    pub fn on<F>(mut self, id: ID, handler: F) -> Self
+    where
+        F: Fn(WidgetEvent, &WidgetRegistry) -> Option<M> + 'static,
+    {
+        self.handlers.push((id, Box::new(handler)));
+        self
+    }
+
+    /// Sends `message` when a shortcut bound to the message `id` is pressed
+    ///
+    /// Shortcuts are bound with [`Program::shortcuts`].
+    pub fn on_shortcut(self, id: ID, message: M) -> Self
+    where
+        M: Clone,
+    {
+        self.on_application(move |event, _| match event {
+            ApplicationEvent::Shortcut(pressed) if pressed == id => Some(message.clone()),
+            _ => None,
+        })
+    }
+
+    /// Calls `handler` with every event that does not belong to a widget, sending the
+    /// message it returns
+    ///
+    /// These are shortcuts, answered dialogs, the windows that closed or failed to open and
+    /// the widgets that were rejected, such as a new view in which two widgets share an ID.
+    /// A rejected view is not shown, and the previous one stays on screen.
+    pub fn on_application<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(ApplicationEvent, &WidgetRegistry) -> Option<M> + 'static,
+    {
+        self.events.push(Box::new(handler));
+        self
+    }
+}
+
+impl<M> fmt::Debug for View<M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let handlers: Vec<ID> = self.handlers.iter().map(|(id, _)| *id).collect();
+        f.debug_struct("View")
+            .field("root", &self.root)
+            .field("handlers", &handlers)
+            .field("events", &self.events.len())
+            .finish()
+    }
+}
+
+/// An application driven by messages
+///
+/// See the [module documentation](self).
+pub struct Program<S, M> {
+    application: Application<Root>,
+    runtime: Runtime<S, M>,
+}
+
+impl<S, M> Program<S, M>
+where
+    S: 'static,
+    M: 'static,
+{
+    pub fn new(window: Window, state: S, update: fn(&mut S, M), view: fn(&S) -> View<M>) -> Self {
+        let mut runtime = Runtime {
+            state,
+            update,
+            view,
+            handlers: vec![],
+            events: vec![],
+            theme: Theme::LIGHT,
+        };
+        Self {
+            application: Application::new(window, runtime.build()),
+            runtime,
+        }
+    }
+
+    /// Sets the colors of the widgets, [`Theme::LIGHT`] by default
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.application.set_theme(theme);
+        self.runtime.theme = theme;
+    }
+
+    /// The keyboard shortcuts of the window, see [`View::on_shortcut`]
+    pub fn shortcuts(&mut self) -> &mut Shortcuts {
+        self.application.shortcuts()
+    }
+
+    /// The bindings of the widgets of the window to signals
+    pub fn bindings(&mut self) -> &mut Bindings {
+        self.application.bindings()
+    }
+
+    /// A handle for opening further windows and showing dialogs while the program runs
+    ///
+    /// Keep it in the state, see [`Program::state_mut`], to use it in `update`.
+    pub fn windows(&self) -> Windows {
+        self.application.windows()
+    }
+
+    /// The state the program starts with
+    pub fn state_mut(&mut self) -> &mut S {
+        &mut self.runtime.state
+    }
+
+    pub fn run(self) {
+        let mut runtime = self.runtime;
+        self.application
+            .run(move |event, registry| runtime.handle(event, registry));
+    }
+}
+
+/// The state of a running [`Program`] and the handlers of its current view
+struct Runtime<S, M> {
+    state: S,
+    update: fn(&mut S, M),
+    view: fn(&S) -> View<M>,
+    handlers: Vec<(ID, Handler<M>)>,
+    events: Vec<EventHandler<M>>,
+    theme: Theme,
+}
+
+impl<S, M> Runtime<S, M> {
+    /// Builds the view of the current state
+    fn build(&mut self) -> Root {
+        let view = (self.view)(&self.state);
+        self.handlers = view.handlers;
+        self.events = view.events;
+        Root::new(view.root)
+    }
+
+    /// Turns `event` into messages, updates the state with them and shows the new view
+    fn handle(&mut self, event: ApplicationEvent, registry: &mut WidgetRegistry) {
+        let messages = self.messages(event, registry);
+        if !messages.is_empty() {
+            self.apply(messages, registry, true);
+        }
+    }
+
+    /// The messages the handlers of the current view send for `event`
+    fn messages(&self, event: ApplicationEvent, registry: &WidgetRegistry) -> Vec<M> {
+        match event {
+            ApplicationEvent::OnEvent(event, id) => self
+                .handlers
+                .iter()
+                .filter(|(handled, _)| *handled == id)
+                .filter_map(|(_, handler)| handler(event, registry))
+                .collect(),
+            event => self
+                .events
+                .iter()
+                .filter_map(|handler| handler(event, registry))
+                .collect(),
+        }
+    }
+
+    /// Updates the state with `messages` and shows the new view
+    ///
+    /// A view whose widgets cannot be registered is dropped, keeping the previous one with
+    /// its handlers. These are told with [`ApplicationEvent::WidgetRejected`] if `report` is
+    /// set, and the view built after the messages they send is not reported again.
+    fn apply(&mut self, messages: Vec<M>, registry: &mut WidgetRegistry, report: bool) {
+        for message in messages {
+            (self.update)(&mut self.state, message);
+        }
+        let view = (self.view)(&self.state);
+        let mut root = Root::new(view.root);
+        root.theme(self.theme);
+        match registry.reconcile(ID::from(ROOT), Box::new(root)) {
+            Ok(()) => {
+                self.handlers = view.handlers;
+                self.events = view.events;
+            }
+            Err(e) if report => {
+                let messages = self.messages(ApplicationEvent::WidgetRejected(e), registry);
+                if !messages.is_empty() {
+                    self.apply(messages, registry, false);
+                }
+            }
+            Err(_) => {}
+        }
+    }
+}
+
+/// Holds the root widget of the view
+#[derive(Debug)]
+struct Root {
+    child: ID,
+    pending: Option<Box<dyn Widget>>,
+}
+
+impl Root {
+    fn new(child: Box<dyn Widget>) -> Self {
+        Self {
+            child: child.id(),
+            pending: Some(child),
+        }
+    }
+}
+
+impl Widget for Root {
+    fn as_container(&self) -> Option<&dyn Container> {
+        Some(self)
+    }
+
+    fn as_container_mut(&mut self) -> Option<&mut dyn Container> {
+        Some(self)
+    }
+
+    fn reconcile(&mut self, new: &mut dyn Widget) -> bool {
+        let Some(new) = new.as_any_mut().downcast_mut::<Self>() else {
+            return false;
+        };
+        self.child = new.child;
+        true
+    }
+}
+
+impl Container for Root {
+    fn children(&self) -> Vec<ID> {
+        vec![self.child]
+    }
+
+    fn take_children(&mut self) -> Vec<Box<dyn Widget>> {
+        self.pending.take().into_iter().collect()
+    }
+}
+
+impl Drawable for Root {
+    fn theme(&mut self, theme: Theme) {
+        if let Some(child) = &mut self.pending {
+            child.theme(theme);
+        }
+    }
+}
+
+impl Layout for Root {
+    fn area(&self) -> Vec<Rect> {
+        vec![]
+    }
+}
+
+impl EventListener for Root {}
+
+impl Object for Root {
+    fn id(&self) -> ID {
+        ID::from(ROOT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        shortcut::Scope,
+        testing::Driver,
+        widget::{Button, Label, Panel, TextInput},
+        Key, Modifiers,
+    };
+
+    #[derive(Default)]
+    struct State {
+        count: u32,
+        name: String,
+    }
+
+    #[derive(Clone)]
+    enum Message {
+        Increment,
+        Rename(String),
+        Rejected,
+    }
+
+    fn update(state: &mut State, message: Message) {
+        match message {
+            Message::Increment => state.count += 1,
+            Message::Rename(name) => state.name = name,
+            Message::Rejected => state.name.clear(),
+        }
+    }
+
+    fn view(state: &State) -> View<Message> {
+        let mut label = Label::new("count");
+        label.set_text(format!("{} {}", state.name, state.count));
+        let mut button = Button::new("increment");
+        button.set_text("+1");
+        let mut name = TextInput::new("name");
+        name.set_text(state.name.clone());
+        let mut panel = Panel::new("panel");
+        panel.push(label);
+        panel.push(button);
+        panel.push(name);
+        // Counts above two grow a second button.
+        if state.count > 2 {
+            panel.push(Button::new("reset"));
+        }
+        // This name asks for a label with the ID of the button.
+        if state.name == "dup" {
+            panel.push(Label::new("increment"));
+        }
+        View::new(panel)
+            .on_click(ID::from("increment"), Message::Increment)
+            .on_shortcut(ID::from("increment"), Message::Increment)
+            .on_change(ID::from("name"), |registry| {
+                let name = registry.get::<TextInput>(ID::from("name"))?;
+                Some(Message::Rename(name.text().to_string()))
+            })
+            .on_application(|event, _| match event {
+                ApplicationEvent::WidgetRejected(_) => Some(Message::Rejected),
+                _ => None,
+            })
+    }
+
+    fn driver() -> Driver {
+        let mut runtime = Runtime {
+            state: State::default(),
+            update,
+            view,
+            handlers: vec![],
+            events: vec![],
+            theme: Theme::LIGHT,
+        };
+        let mut driver = Driver::new(runtime.build(), 300, 200);
+        driver.callback(move |event, registry| runtime.handle(event, registry));
+        driver
+    }
+
+    fn label(driver: &Driver) -> String {
+        let registry = driver.registry();
+        registry
+            .get::<Label>(ID::from("count"))
+            .unwrap()
+            .text()
+            .to_string()
+    }
+
+    #[test]
+    fn messages_update_the_view() {
+        let mut driver = driver();
+        driver.step();
+        let button = driver
+            .registry()
+            .get::<Button>(ID::from("increment"))
+            .unwrap()
+            .area()[0];
+        let (x, y) = (button.x() as i32 + 5, button.y() as i32 + 5);
+        for _ in 0..3 {
+            driver.click(x, y);
+        }
+        assert_eq!(label(&driver), " 3");
+        assert!(driver.registry().contains(ID::from("reset")));
+    }
+
+    #[test]
+    fn state_survives_a_new_view() {
+        let mut driver = driver();
+        driver.focus(ID::from("name"));
+        driver.type_text("ab");
+        assert_eq!(label(&driver), "ab 0");
+        {
+            let registry = driver.registry();
+            let input = registry.get::<TextInput>(ID::from("name")).unwrap();
+            assert_eq!((input.text(), input.caret()), ("ab", 2));
+        }
+        // The input was kept rather than replaced, so it still has focus.
+        driver.type_text("c");
+        assert_eq!(label(&driver), "abc 0");
+    }
+
+    #[test]
+    fn shortcuts_send_messages() {
+        let mut driver = driver();
+        driver
+            .shortcuts()
+            .bind(
+                "Ctrl+I".parse().unwrap(),
+                Scope::Window,
+                ID::from("increment"),
+            )
+            .unwrap();
+        driver
+            .modifiers(Modifiers::CONTROL)
+            .key_press(Key::Character("i".into()));
+        assert_eq!(label(&driver), " 1");
+    }
+
+    #[test]
+    fn rejected_views_keep_the_previous_one() {
+        let mut driver = driver();
+        driver.focus(ID::from("name"));
+        driver.type_text("du");
+        assert_eq!(label(&driver), "du 0");
+        // The view of "dup" uses an ID twice, so the handlers of the previous view are told.
+        driver.type_text("p");
+        assert_eq!(label(&driver), " 0");
+        assert!(driver
+            .registry()
+            .get::<Button>(ID::from("increment"))
+            .is_some());
+    }
+}
@@ -0,0 +1,1050 @@
+//! Drawing recorded as a list of items
+//!
+//! Widgets describe what they draw with a [`DisplayList`] instead of drawing directly, so the
+//! same list can be replayed onto any [`Canvas`], written to a file and read back, or compared
+//! with the list of the previous frame.
+//!
+//! ```
+//! use ail::{
+//!     render::{DisplayList, Item},
+//!     Color, Rect,
+//! };
+//!
+//! let mut list = DisplayList::new();
+//! list.push(Item::Rectangle(Rect::from_coordinate(0, 0, 100, 20), Color::White));
+//! list.push(Item::Text(
+//!     Rect::from_coordinate(0, 0, 100, 20),
+//!     Color::Black,
+//!     String::from("Save"),
+//! ));
+//!
+//! let dump = list.to_string();
+//! assert_eq!(dump, "rect 0 0 100 20 white\ntext 0 0 100 20 black \"Save\"\n");
+//! assert_eq!(dump.parse::<DisplayList>().unwrap(), list);
+//! assert_eq!(DisplayList::from_bytes(&list.to_bytes()).unwrap(), list);
+//! ```
+
+use std::{fmt, str::FromStr};
+
+use crate::{Color, Rect};
+
+use super::Image;
+
+/// Leading bytes of the binary format, followed by its version
+const MAGIC: &[u8; 4] = b"AIDL";
+const VERSION: u8 = 1;
+
+/// A point in window coordinates, which may lie outside of the window
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Point {
+    pub fn new(x: i32, y: i32) -> Self {
+        Self { x, y }
+    }
+}
+
+/// Moves the items drawn while it is pushed
+///
+/// Transforms pushed inside of each other add up.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Transform {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform { x: 0, y: 0 };
+
+    pub fn translate(x: i32, y: i32) -> Self {
+        Self { x, y }
+    }
+
+    fn then(self, other: Transform) -> Self {
+        Self::translate(
+            self.x.saturating_add(other.x),
+            self.y.saturating_add(other.y),
+        )
+    }
+
+    fn point(self, point: Point) -> Point {
+        Point::new(
+            point.x.saturating_add(self.x),
+            point.y.saturating_add(self.y),
+        )
+    }
+
+    /// Moves `area`, cutting off what moves past the edges of the coordinate range
+    fn rect(self, area: Rect) -> Rect {
+        let shift =
+            |value: u32, by: i32| (value as i64 + by as i64).clamp(0, u32::MAX as i64) as u32;
+        let (left, top) = (shift(area.x(), self.x), shift(area.y(), self.y));
+        let right = shift(area.x() + area.width(), self.x);
+        let bottom = shift(area.y() + area.height(), self.y);
+        Rect::from_coordinate(left, top, right - left, bottom - top)
+    }
+}
+
+/// Something drawn by a [`DisplayList`]
+#[derive(Clone, Debug, PartialEq)]
+pub enum Item {
+    /// A filled rectangle
+    Rectangle(Rect, Color),
+    /// A filled rectangle whose corners are rounded with the radius
+    RoundedRectangle(Rect, f64, Color),
+    /// A single line of text centered in the area and clipped to it
+    Text(Rect, Color, String),
+    /// A straight line of the given width
+    Line(Point, Point, u32, Color),
+    /// A polygon through the points, filled
+    Path(Vec<Point>, Color),
+    /// An image scaled to the area
+    Image(Rect, Image),
+    /// Restricts the following items to the area, within any clip already pushed
+    PushClip(Rect),
+    PopClip,
+    PushTransform(Transform),
+    PopTransform,
+}
+
+/// A backend display lists are replayed onto
+///
+/// [`DisplayList::replay`] resolves transforms and nested clips, so a canvas receives
+/// window coordinates and a single clip at a time.
+pub trait Canvas {
+    fn fill_rectangle(&mut self, area: Rect, radius: f64, color: Color);
+
+    fn draw_text(&mut self, area: Rect, color: Color, text: &str);
+
+    fn draw_line(&mut self, from: Point, to: Point, width: u32, color: Color);
+
+    fn fill_path(&mut self, points: &[Point], color: Color);
+
+    fn draw_image(&mut self, area: Rect, image: &Image);
+
+    /// Restricts drawing to `clip`, or lifts the restriction
+    fn clip(&mut self, clip: Option<Rect>);
+
+    /// Whether the canvas draws `item`, which backends supporting only some items skip
+    fn supports(&self, _item: &Item) -> bool {
+        true
+    }
+}
+
+/// The items a widget or a frame draws, in drawing order
+///
+/// A list is written as text with `to_string` and read back with `parse`, one item per
+/// line, or in a compact binary form with [`DisplayList::to_bytes`].
+///
+/// To capture what a running window shows, for instance to attach it to a bug report,
+/// request its frame with [`crate::Windows::frame`] and write it to a file. Windows under
+/// test give theirs with [`crate::testing::Driver::frame`]. Two captures are compared
+/// with [`DisplayList::diff`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DisplayList {
+    items: Vec<Item>,
+}
+
+impl DisplayList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, item: Item) {
+        self.items.push(item);
+    }
+
+    pub fn items(&self) -> &[Item] {
+        &self.items
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Draws the items onto `canvas`
+    ///
+    /// Pops without a matching push are ignored, and clips still pushed at the end are lifted.
+    pub fn replay<C>(&self, canvas: &mut C)
+    where
+        C: Canvas + ?Sized,
+    {
+        let mut transforms = vec![Transform::IDENTITY];
+        let mut clips: Vec<Rect> = vec![];
+        for item in &self.items {
+            let transform = *transforms.last().unwrap();
+            match item {
+                Item::Rectangle(area, color) => {
+                    canvas.fill_rectangle(transform.rect(*area), 0.0, *color)
+                }
+                Item::RoundedRectangle(area, radius, color) => {
+                    canvas.fill_rectangle(transform.rect(*area), *radius, *color)
+                }
+                Item::Text(area, color, text) => {
+                    canvas.draw_text(transform.rect(*area), *color, text)
+                }
+                Item::Line(from, to, width, color) => {
+                    canvas.draw_line(transform.point(*from), transform.point(*to), *width, *color)
+                }
+                Item::Path(points, color) => {
+                    let points: Vec<Point> = points.iter().map(|p| transform.point(*p)).collect();
+                    canvas.fill_path(&points, *color);
+                }
+                Item::Image(area, image) => canvas.draw_image(transform.rect(*area), image),
+                Item::PushClip(area) => {
+                    let area = transform.rect(*area);
+                    let clip = match clips.last() {
+                        Some(outer) => outer
+                            .intersection(&area)
+                            .unwrap_or(Rect::from_coordinate(0, 0, 0, 0)),
+                        None => area,
+                    };
+                    clips.push(clip);
+                    canvas.clip(Some(clip));
+                }
+                Item::PopClip => {
+                    if clips.pop().is_some() {
+                        canvas.clip(clips.last().copied());
+                    }
+                }
+                Item::PushTransform(by) => transforms.push(transform.then(*by)),
+                Item::PopTransform => {
+                    if transforms.len() > 1 {
+                        transforms.pop();
+                    }
+                }
+            }
+        }
+        if !clips.is_empty() {
+            canvas.clip(None);
+        }
+    }
+
+    /// Lists the items to remove from this list and to add to it to obtain `new`
+    ///
+    /// Changes are ordered as they appear in the lists, with the indices of the items in
+    /// the list they come from. Memory use grows with the length of the lists rather than
+    /// with the product of their lengths, so whole frames can be compared.
+    pub fn diff<'a>(&'a self, new: &'a DisplayList) -> Vec<Change<'a>> {
+        let (old, new) = (&self.items[..], &new.items[..]);
+        let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+        let suffix = old[prefix..]
+            .iter()
+            .rev()
+            .zip(new[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let a = &old[prefix..old.len() - suffix];
+        let b = &new[prefix..new.len() - suffix];
+
+        let mut changes = vec![];
+        diff(a, b, (prefix, prefix), &mut changes);
+        changes
+    }
+
+    /// Encodes the list in the binary format read by [`DisplayList::from_bytes`]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        bytes.extend((self.items.len() as u32).to_le_bytes());
+        for item in &self.items {
+            encode(item, &mut bytes);
+        }
+        bytes
+    }
+
+    /// Decodes a list written by [`DisplayList::to_bytes`]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut reader = Reader { bytes, offset: 0 };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(reader.error("not a display list"));
+        }
+        if reader.u8()? != VERSION {
+            return Err(reader.error("unsupported version"));
+        }
+        let count = reader.u32()?;
+        let items = (0..count)
+            .map(|_| reader.item())
+            .collect::<Result<Vec<_>, _>>()?;
+        if reader.offset != bytes.len() {
+            return Err(reader.error("trailing bytes"));
+        }
+        Ok(Self { items })
+    }
+}
+
+impl From<Vec<Item>> for DisplayList {
+    fn from(items: Vec<Item>) -> Self {
+        Self { items }
+    }
+}
+
+impl FromIterator<Item> for DisplayList {
+    fn from_iter<T: IntoIterator<Item = Item>>(iter: T) -> Self {
+        Self {
+            items: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Extend<Item> for DisplayList {
+    fn extend<T: IntoIterator<Item = Item>>(&mut self, iter: T) {
+        self.items.extend(iter);
+    }
+}
+
+impl IntoIterator for DisplayList {
+    type Item = Item;
+    type IntoIter = std::vec::IntoIter<Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a DisplayList {
+    type Item = &'a Item;
+    type IntoIter = std::slice::Iter<'a, Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.iter()
+    }
+}
+
+/// A difference between two display lists, see [`DisplayList::diff`]
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change<'a> {
+    Removed(usize, &'a Item),
+    Added(usize, &'a Item),
+}
+
+impl fmt::Display for Change<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Removed(_, item) => write!(f, "- {}", item),
+            Change::Added(_, item) => write!(f, "+ {}", item),
+        }
+    }
+}
+
+/// Why a display list could not be read
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// A line of the text format is malformed
+    Text { line: usize, reason: &'static str },
+    /// The binary format is malformed at the byte offset
+    Binary { offset: usize, reason: &'static str },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Text { line, reason } => write!(f, "line {}: {}", line, reason),
+            DecodeError::Binary { offset, reason } => write!(f, "byte {}: {}", offset, reason),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Writes one item per line, which [`DisplayList::from_str`] reads back
+impl fmt::Display for DisplayList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for item in &self.items {
+            writeln!(f, "{}", item)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for DisplayList {
+    type Err = DecodeError;
+
+    /// Reads the text format, skipping blank lines and lines starting with `#`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+            .map(|(n, line)| {
+                line.parse().map_err(|reason| DecodeError::Text {
+                    line: n + 1,
+                    reason,
+                })
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rect = |area: &Rect| {
+            format!(
+                "{} {} {} {}",
+                area.x(),
+                area.y(),
+                area.width(),
+                area.height()
+            )
+        };
+        match self {
+            Item::Rectangle(area, color) => write!(f, "rect {} {}", rect(area), ColorText(*color)),
+            Item::RoundedRectangle(area, radius, color) => write!(
+                f,
+                "rounded-rect {} {} {}",
+                rect(area),
+                radius,
+                ColorText(*color)
+            ),
+            Item::Text(area, color, text) => {
+                write!(f, "text {} {} \"", rect(area), ColorText(*color))?;
+                for c in text.chars() {
+                    match c {
+                        '"' => f.write_str("\\\"")?,
+                        '\\' => f.write_str("\\\\")?,
+                        '\n' => f.write_str("\\n")?,
+                        c => write!(f, "{}", c)?,
+                    }
+                }
+                f.write_str("\"")
+            }
+            Item::Line(from, to, width, color) => write!(
+                f,
+                "line {} {} {} {} {} {}",
+                from.x,
+                from.y,
+                to.x,
+                to.y,
+                width,
+                ColorText(*color)
+            ),
+            Item::Path(points, color) => {
+                write!(f, "path {}", ColorText(*color))?;
+                for point in points {
+                    write!(f, " {},{}", point.x, point.y)?;
+                }
+                Ok(())
+            }
+            Item::Image(area, image) => {
+                write!(
+                    f,
+                    "image {} {} {} ",
+                    rect(area),
+                    image.width(),
+                    image.height()
+                )?;
+                for byte in image.as_bytes() {
+                    write!(f, "{:02x}", byte)?;
+                }
+                Ok(())
+            }
+            Item::PushClip(area) => write!(f, "push-clip {}", rect(area)),
+            Item::PopClip => f.write_str("pop-clip"),
+            Item::PushTransform(by) => write!(f, "push-transform {} {}", by.x, by.y),
+            Item::PopTransform => f.write_str("pop-transform"),
+        }
+    }
+}
+
+impl FromStr for Item {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = Fields(s.trim());
+        let item = match fields.word()? {
+            "rect" => Item::Rectangle(fields.rect()?, fields.color()?),
+            "rounded-rect" => {
+                Item::RoundedRectangle(fields.rect()?, fields.number()?, fields.color()?)
+            }
+            "text" => Item::Text(fields.rect()?, fields.color()?, fields.quoted()?),
+            "line" => Item::Line(
+                fields.point()?,
+                fields.point()?,
+                fields.number()?,
+                fields.color()?,
+            ),
+            "path" => {
+                let color = fields.color()?;
+                let mut points = vec![];
+                while !fields.0.is_empty() {
+                    let (x, y) = fields.word()?.split_once(',').ok_or("expected a point")?;
+                    let coordinate = |n: &str| n.parse().map_err(|_| "expected a number");
+                    points.push(Point::new(coordinate(x)?, coordinate(y)?));
+                }
+                Item::Path(points, color)
+            }
+            "image" => {
+                let area = fields.rect()?;
+                let (width, height) = (fields.number()?, fields.number()?);
+                let hex = fields.word()?;
+                let data = (0..hex.len())
+                    .step_by(2)
+                    .map(|i| {
+                        hex.get(i..i + 2)
+                            .and_then(|b| u8::from_str_radix(b, 16).ok())
+                    })
+                    .collect::<Option<Vec<u8>>>()
+                    .ok_or("expected hexadecimal pixels")?;
+                let image = Image::from_rgba(width, height, data)
+                    .ok_or("pixels do not match the size of the image")?;
+                Item::Image(area, image)
+            }
+            "push-clip" => Item::PushClip(fields.rect()?),
+            "pop-clip" => Item::PopClip,
+            "push-transform" => {
+                let by = fields.point()?;
+                Item::PushTransform(Transform::translate(by.x, by.y))
+            }
+            "pop-transform" => Item::PopTransform,
+            _ => return Err("unknown item"),
+        };
+        match fields.0.is_empty() {
+            true => Ok(item),
+            false => Err("unexpected text after the item"),
+        }
+    }
+}
+
+/// Writes `black`, `white` or `#aarrggbb`
+struct ColorText(Color);
+
+impl fmt::Display for ColorText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Color::Black => f.write_str("black"),
+            Color::White => f.write_str("white"),
+            Color::ARGB(a, r, g, b) => write!(f, "#{:02x}{:02x}{:02x}{:02x}", a, r, g, b),
+        }
+    }
+}
+
+/// The fields of a line of the text format not read yet
+struct Fields<'a>(&'a str);
+
+impl<'a> Fields<'a> {
+    fn word(&mut self) -> Result<&'a str, &'static str> {
+        let rest = self.0.trim_start();
+        if rest.is_empty() {
+            return Err("missing field");
+        }
+        let end = rest.find(' ').unwrap_or(rest.len());
+        self.0 = &rest[end..];
+        Ok(&rest[..end])
+    }
+
+    fn number<T>(&mut self) -> Result<T, &'static str>
+    where
+        T: FromStr,
+    {
+        self.word()?.parse().map_err(|_| "expected a number")
+    }
+
+    fn rect(&mut self) -> Result<Rect, &'static str> {
+        checked_rect(
+            self.number()?,
+            self.number()?,
+            self.number()?,
+            self.number()?,
+        )
+        .ok_or("rectangle out of range")
+    }
+
+    fn point(&mut self) -> Result<Point, &'static str> {
+        Ok(Point::new(self.number()?, self.number()?))
+    }
+
+    fn color(&mut self) -> Result<Color, &'static str> {
+        match self.word()? {
+            "black" => Ok(Color::Black),
+            "white" => Ok(Color::White),
+            hex => {
+                let argb = hex
+                    .strip_prefix('#')
+                    .filter(|hex| hex.len() == 8)
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .ok_or("expected a color")?;
+                let [a, r, g, b] = argb.to_be_bytes();
+                Ok(Color::ARGB(a, r, g, b))
+            }
+        }
+    }
+
+    /// Reads the rest of the line as a string in double quotes
+    fn quoted(&mut self) -> Result<String, &'static str> {
+        let rest = self.0.trim_start();
+        let mut chars = rest.strip_prefix('"').ok_or("expected a string")?.chars();
+        let mut text = String::new();
+        loop {
+            match chars.next().ok_or("unterminated string")? {
+                '"' => break,
+                '\\' => match chars.next() {
+                    Some('n') => text.push('\n'),
+                    Some(c @ ('"' | '\\')) => text.push(c),
+                    _ => return Err("unknown escape"),
+                },
+                c => text.push(c),
+            }
+        }
+        self.0 = chars.as_str();
+        Ok(text)
+    }
+}
+
+/// Appends the changes turning `a` into `b`, whose first items have the indices `at`
+///
+/// This is Hirschberg's algorithm: `a` is split in half and `b` where the longest common
+/// subsequences of the halves add up to the longest one, which keeps only a row of
+/// lengths at a time.
+fn diff<'a>(a: &'a [Item], b: &'a [Item], at: (usize, usize), changes: &mut Vec<Change<'a>>) {
+    let (i, j) = at;
+    let removed =
+        |items: &'a [Item], i: usize| (i..).zip(items).map(|(i, item)| Change::Removed(i, item));
+    let added =
+        |items: &'a [Item], j: usize| (j..).zip(items).map(|(j, item)| Change::Added(j, item));
+    match a {
+        [] => changes.extend(added(b, j)),
+        _ if b.is_empty() => changes.extend(removed(a, i)),
+        [item] => match b.iter().position(|other| other == item) {
+            Some(k) => {
+                changes.extend(added(&b[..k], j));
+                changes.extend(added(&b[k + 1..], j + k + 1));
+            }
+            None => {
+                changes.push(Change::Removed(i, item));
+                changes.extend(added(b, j));
+            }
+        },
+        _ => {
+            let mid = a.len() / 2;
+            let left = lcs_lengths(a[..mid].iter(), b.iter());
+            let right = lcs_lengths(a[mid..].iter().rev(), b.iter().rev());
+            let split = (0..=b.len())
+                .rev()
+                .max_by_key(|&k| left[k] + right[b.len() - k])
+                .unwrap();
+            diff(&a[..mid], &b[..split], (i, j), changes);
+            diff(&a[mid..], &b[split..], (i + mid, j + split), changes);
+        }
+    }
+}
+
+/// Lengths of the longest common subsequences of `a` and every prefix of `b`
+fn lcs_lengths<'a, A, B>(a: A, b: B) -> Vec<usize>
+where
+    A: Iterator<Item = &'a Item>,
+    B: Iterator<Item = &'a Item> + Clone,
+{
+    let mut row = vec![0; b.clone().count() + 1];
+    for x in a {
+        // The length for the previous prefix of `b` in the row above.
+        let mut diagonal = 0;
+        for (k, y) in b.clone().enumerate() {
+            let above = row[k + 1];
+            row[k + 1] = match x == y {
+                true => diagonal + 1,
+                false => above.max(row[k]),
+            };
+            diagonal = above;
+        }
+    }
+    row
+}
+
+/// Builds a decoded rectangle unless its right or bottom edge lies past the coordinate range
+fn checked_rect(x: u32, y: u32, width: u32, height: u32) -> Option<Rect> {
+    x.checked_add(width)?;
+    y.checked_add(height)?;
+    Some(Rect::from_coordinate(x, y, width, height))
+}
+
+fn encode(item: &Item, bytes: &mut Vec<u8>) {
+    let rect = |area: &Rect, bytes: &mut Vec<u8>| {
+        for value in [area.x(), area.y(), area.width(), area.height()] {
+            bytes.extend(value.to_le_bytes());
+        }
+    };
+    let color = |color: &Color, bytes: &mut Vec<u8>| match *color {
+        Color::Black => bytes.push(0),
+        Color::White => bytes.push(1),
+        Color::ARGB(a, r, g, b) => bytes.extend([2, a, r, g, b]),
+    };
+    let point = |point: &Point, bytes: &mut Vec<u8>| {
+        bytes.extend(point.x.to_le_bytes());
+        bytes.extend(point.y.to_le_bytes());
+    };
+    match item {
+        Item::Rectangle(area, c) => {
+            bytes.push(0);
+            rect(area, bytes);
+            color(c, bytes);
+        }
+        Item::RoundedRectangle(area, radius, c) => {
+            bytes.push(1);
+            rect(area, bytes);
+            bytes.extend(radius.to_le_bytes());
+            color(c, bytes);
+        }
+        Item::Text(area, c, text) => {
+            bytes.push(2);
+            rect(area, bytes);
+            color(c, bytes);
+            bytes.extend((text.len() as u32).to_le_bytes());
+            bytes.extend(text.as_bytes());
+        }
+        Item::Line(from, to, width, c) => {
+            bytes.push(3);
+            point(from, bytes);
+            point(to, bytes);
+            bytes.extend(width.to_le_bytes());
+            color(c, bytes);
+        }
+        Item::Path(points, c) => {
+            bytes.push(4);
+            color(c, bytes);
+            bytes.extend((points.len() as u32).to_le_bytes());
+            for p in points {
+                point(p, bytes);
+            }
+        }
+        Item::Image(area, image) => {
+            bytes.push(5);
+            rect(area, bytes);
+            bytes.extend(image.width().to_le_bytes());
+            bytes.extend(image.height().to_le_bytes());
+            bytes.extend(image.as_bytes());
+        }
+        Item::PushClip(area) => {
+            bytes.push(6);
+            rect(area, bytes);
+        }
+        Item::PopClip => bytes.push(7),
+        Item::PushTransform(by) => {
+            bytes.push(8);
+            point(&Point::new(by.x, by.y), bytes);
+        }
+        Item::PopTransform => bytes.push(9),
+    }
+}
+
+/// Reads the binary format, remembering the offset for errors
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn error(&self, reason: &'static str) -> DecodeError {
+        DecodeError::Binary {
+            offset: self.offset,
+            reason,
+        }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        let bytes = self
+            .bytes
+            .get(self.offset..self.offset.saturating_add(len))
+            .ok_or(self.error("unexpected end"))?;
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, DecodeError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn i32(&mut self) -> Result<i32, DecodeError> {
+        Ok(i32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn rect(&mut self) -> Result<Rect, DecodeError> {
+        checked_rect(self.u32()?, self.u32()?, self.u32()?, self.u32()?)
+            .ok_or(self.error("rectangle out of range"))
+    }
+
+    fn point(&mut self) -> Result<Point, DecodeError> {
+        Ok(Point::new(self.i32()?, self.i32()?))
+    }
+
+    fn color(&mut self) -> Result<Color, DecodeError> {
+        match self.u8()? {
+            0 => Ok(Color::Black),
+            1 => Ok(Color::White),
+            2 => {
+                let [a, r, g, b] = self.take(4)?.try_into().unwrap();
+                Ok(Color::ARGB(a, r, g, b))
+            }
+            _ => Err(self.error("unknown color")),
+        }
+    }
+
+    fn item(&mut self) -> Result<Item, DecodeError> {
+        Ok(match self.u8()? {
+            0 => Item::Rectangle(self.rect()?, self.color()?),
+            1 => {
+                let area = self.rect()?;
+                let radius = f64::from_le_bytes(self.take(8)?.try_into().unwrap());
+                Item::RoundedRectangle(area, radius, self.color()?)
+            }
+            2 => {
+                let (area, color) = (self.rect()?, self.color()?);
+                let len = self.u32()? as usize;
+                let text = std::str::from_utf8(self.take(len)?)
+                    .map_err(|_| self.error("text is not UTF-8"))?;
+                Item::Text(area, color, text.to_string())
+            }
+            3 => Item::Line(self.point()?, self.point()?, self.u32()?, self.color()?),
+            4 => {
+                let color = self.color()?;
+                let count = self.u32()?;
+                let points = (0..count)
+                    .map(|_| self.point())
+                    .collect::<Result<Vec<_>, _>>()?;
+                Item::Path(points, color)
+            }
+            5 => {
+                let area = self.rect()?;
+                let (width, height) = (self.u32()?, self.u32()?);
+                let len = (width as usize)
+                    .checked_mul(height as usize)
+                    .and_then(|pixels| pixels.checked_mul(4))
+                    .ok_or(self.error("image too large"))?;
+                let data = self.take(len)?.to_vec();
+                Item::Image(area, Image::from_rgba(width, height, data).unwrap())
+            }
+            6 => Item::PushClip(self.rect()?),
+            7 => Item::PopClip,
+            8 => {
+                let by = self.point()?;
+                Item::PushTransform(Transform::translate(by.x, by.y))
+            }
+            9 => Item::PopTransform,
+            _ => return Err(self.error("unknown item")),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::offscreen::OffscreenSurface;
+
+    fn every_item() -> DisplayList {
+        let area = Rect::from_coordinate(4, 6, 30, 12);
+        let mut image = Image::new(2, 1);
+        image.set_pixel(1, 0, [255, 0, 0, 255]);
+        DisplayList::from(vec![
+            Item::Rectangle(area, Color::ARGB(255, 1, 2, 3)),
+            Item::RoundedRectangle(area, 4.2, Color::White),
+            Item::Text(area, Color::Black, String::from("say \"hi\"\\\n日本")),
+            Item::Line(Point::new(-3, 0), Point::new(10, 20), 2, Color::Black),
+            Item::Path(
+                vec![Point::new(0, 0), Point::new(8, 0), Point::new(4, -6)],
+                Color::ARGB(128, 0, 0, 255),
+            ),
+            Item::Image(area, image),
+            Item::PushClip(area),
+            Item::PushTransform(Transform::translate(-2, 5)),
+            Item::PopTransform,
+            Item::PopClip,
+        ])
+    }
+
+    #[test]
+    fn round_trips() {
+        let list = every_item();
+        assert_eq!(list.to_string().parse::<DisplayList>(), Ok(list.clone()));
+        assert_eq!(DisplayList::from_bytes(&list.to_bytes()), Ok(list.clone()));
+
+        let bytes = list.to_bytes();
+        assert!(matches!(
+            DisplayList::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(DecodeError::Binary { .. })
+        ));
+        assert_eq!(
+            "rect 0 0 1 1 white\n\nrect 0 0 1 red".parse::<DisplayList>(),
+            Err(DecodeError::Text {
+                line: 3,
+                reason: "expected a number"
+            })
+        );
+    }
+
+    #[test]
+    fn malformed_input_is_rejected() {
+        assert_eq!(
+            "rect 4294967295 0 1 1 white".parse::<DisplayList>(),
+            Err(DecodeError::Text {
+                line: 1,
+                reason: "rectangle out of range"
+            })
+        );
+        let mut bytes =
+            DisplayList::from(vec![Item::PushClip(Rect::from_coordinate(0, 0, 1, 1))]).to_bytes();
+        let len = bytes.len();
+        bytes[len - 16..len - 12].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            DisplayList::from_bytes(&bytes),
+            Err(DecodeError::Binary {
+                reason: "rectangle out of range",
+                ..
+            })
+        ));
+
+        // Mutated dumps decode or fail, and whatever decodes can be replayed.
+        let text = every_item().to_string();
+        let bytes = every_item().to_bytes();
+        let extremes = ["4294967295", "-2147483648", "2147483647", "0"];
+        let mut seed = 0x2545_f491_u32;
+        let mut random = move |bound: usize| {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            seed as usize % bound
+        };
+        for _ in 0..2000 {
+            let mut mutated = bytes.clone();
+            for _ in 0..=random(4) {
+                let at = random(mutated.len());
+                mutated[at] = match random(3) {
+                    0 => 0xff,
+                    1 => 0x7f,
+                    _ => random(256) as u8,
+                };
+            }
+            if let Ok(list) = DisplayList::from_bytes(&mutated) {
+                list.replay(&mut Recording::default());
+                list.replay(&mut OffscreenSurface::new(16, 16));
+            }
+
+            let mut words: Vec<&str> = text.split(' ').collect();
+            let at = random(words.len());
+            words[at] = extremes[random(extremes.len())];
+            if let Ok(list) = words.join(" ").parse::<DisplayList>() {
+                list.replay(&mut Recording::default());
+                list.replay(&mut OffscreenSurface::new(16, 16));
+            }
+        }
+
+        let list: DisplayList = "push-transform 2147483647 2147483647\n\
+            push-transform 2147483647 -5\n\
+            rect 4294967000 10 295 20 black\n\
+            line 2147483647 0 -2147483648 0 1 black"
+            .parse()
+            .unwrap();
+        list.replay(&mut Recording::default());
+        list.replay(&mut OffscreenSurface::new(16, 16));
+    }
+
+    /// Remembers what it was asked to draw
+    #[derive(Default)]
+    struct Recording {
+        calls: Vec<String>,
+    }
+
+    impl Canvas for Recording {
+        fn fill_rectangle(&mut self, area: Rect, radius: f64, _color: Color) {
+            self.calls.push(format!("fill {:?} {}", area, radius));
+        }
+
+        fn draw_text(&mut self, area: Rect, _color: Color, text: &str) {
+            self.calls.push(format!("text {:?} {}", area, text));
+        }
+
+        fn draw_line(&mut self, from: Point, to: Point, _width: u32, _color: Color) {
+            self.calls.push(format!("line {:?} {:?}", from, to));
+        }
+
+        fn fill_path(&mut self, points: &[Point], _color: Color) {
+            self.calls.push(format!("path {:?}", points));
+        }
+
+        fn draw_image(&mut self, area: Rect, _image: &Image) {
+            self.calls.push(format!("image {:?}", area));
+        }
+
+        fn clip(&mut self, clip: Option<Rect>) {
+            self.calls.push(format!("clip {:?}", clip));
+        }
+    }
+
+    #[test]
+    fn replay_resolves_transforms_and_clips() {
+        let list = DisplayList::from(vec![
+            Item::PushClip(Rect::from_coordinate(0, 0, 50, 50)),
+            Item::PushTransform(Transform::translate(10, 20)),
+            Item::PushClip(Rect::from_coordinate(30, 0, 40, 40)),
+            Item::Rectangle(Rect::from_coordinate(0, 0, 5, 5), Color::Black),
+            Item::PopClip,
+            Item::PopTransform,
+            Item::Line(Point::new(0, 0), Point::new(1, 1), 1, Color::Black),
+        ]);
+        let mut canvas = Recording::default();
+        list.replay(&mut canvas);
+        let rect =
+            |x, y, width, height| format!("{:?}", Rect::from_coordinate(x, y, width, height));
+        assert_eq!(
+            canvas.calls,
+            vec![
+                format!("clip Some({})", rect(0, 0, 50, 50)),
+                format!("clip Some({})", rect(40, 20, 10, 30)),
+                format!("fill {} 0", rect(10, 20, 5, 5)),
+                format!("clip Some({})", rect(0, 0, 50, 50)),
+                String::from("line Point { x: 0, y: 0 } Point { x: 1, y: 1 }"),
+                String::from("clip None"),
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_between_frames() {
+        let old = every_item();
+        let mut new = old.clone();
+        new.items[1] =
+            Item::RoundedRectangle(Rect::from_coordinate(4, 6, 30, 12), 4.2, Color::Black);
+        new.items.remove(3);
+        new.push(Item::PopClip);
+
+        let changes = old.diff(&new);
+        let lines: Vec<String> = changes.iter().map(|change| change.to_string()).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "- rounded-rect 4 6 30 12 4.2 white",
+                "+ rounded-rect 4 6 30 12 4.2 black",
+                "- line -3 0 10 20 2 black",
+                "+ pop-clip",
+            ]
+        );
+        assert!(matches!(changes[0], Change::Removed(1, _)));
+        assert!(matches!(changes[3], Change::Added(8, _)));
+        assert!(old.diff(&old).is_empty());
+
+        // Long lists differing at both ends are compared without a table of their lengths.
+        let rect = |i: u32| Item::Rectangle(Rect::from_coordinate(i, 0, 1, 1), Color::Black);
+        let old: DisplayList = (0..3000).map(rect).collect();
+        let new: DisplayList = (0..3000)
+            .filter(|i| i % 100 != 50)
+            .map(|i| match i {
+                0 => rect(5000),
+                i => rect(i),
+            })
+            .chain([rect(6000)])
+            .collect();
+        let changes = old.diff(&new);
+        assert_eq!(changes.len(), 1 + 30 + 2);
+        assert_eq!(changes[0], Change::Removed(0, &old.items[0]));
+        assert_eq!(changes[1], Change::Added(0, &new.items[0]));
+        assert_eq!(changes[2], Change::Removed(50, &old.items[50]));
+    }
+}
@@ -0,0 +1,93 @@
+use std::{mem, ptr::null_mut};
+
+use winapi::{
+    shared::windef::{HDC, HFONT, HGDIOBJ, SIZE},
+    um::{
+        wingdi::{
+            CreateCompatibleDC, CreateFontIndirectW, DeleteDC, DeleteObject, GetTextExtentPoint32W,
+            GetTextMetricsW, SelectObject, TEXTMETRICW,
+        },
+        winuser::{SystemParametersInfoW, NONCLIENTMETRICSW, SPI_GETNONCLIENTMETRICS},
+    },
+};
+
+use crate::layout::TextMetrics;
+
+/// Measures text in the font Windows shows message boxes in
+///
+/// acure draws text through DirectWrite without exposing the font it picks, so text cannot
+/// be measured in that font itself. The message font is the proportional font of the
+/// system's user interface, which acure's text is close to; widths may still be off by a
+/// few pixels.
+pub(crate) struct SystemFont {
+    dc: HDC,
+    font: HFONT,
+    previous: HGDIOBJ,
+    line_height: u32,
+}
+
+impl SystemFont {
+    /// Loads the message font into a memory device context
+    ///
+    /// Returns `None` if the font cannot be loaded.
+    pub fn new() -> Option<Self> {
+        unsafe {
+            let mut settings: NONCLIENTMETRICSW = mem::zeroed();
+            settings.cbSize = mem::size_of::<NONCLIENTMETRICSW>() as u32;
+            let found = SystemParametersInfoW(
+                SPI_GETNONCLIENTMETRICS,
+                settings.cbSize,
+                &mut settings as *mut NONCLIENTMETRICSW as *mut _,
+                0,
+            );
+            if found == 0 {
+                return None;
+            }
+
+            let dc = CreateCompatibleDC(null_mut());
+            if dc.is_null() {
+                return None;
+            }
+            let font = CreateFontIndirectW(&settings.lfMessageFont);
+            if font.is_null() {
+                DeleteDC(dc);
+                return None;
+            }
+            let previous = SelectObject(dc, font as HGDIOBJ);
+
+            let mut metrics: TEXTMETRICW = mem::zeroed();
+            GetTextMetricsW(dc, &mut metrics);
+            Some(Self {
+                dc,
+                font,
+                previous,
+                line_height: metrics.tmHeight.max(0) as u32,
+            })
+        }
+    }
+}
+
+impl TextMetrics for SystemFont {
+    fn text_width(&self, text: &str) -> u32 {
+        let text: Vec<u16> = text.encode_utf16().collect();
+        let mut size = SIZE { cx: 0, cy: 0 };
+        unsafe {
+            GetTextExtentPoint32W(self.dc, text.as_ptr(), text.len() as i32, &mut size);
+        }
+        size.cx.max(0) as u32
+    }
+
+    fn line_height(&self) -> u32 {
+        self.line_height
+    }
+}
+
+impl Drop for SystemFont {
+    fn drop(&mut self) {
+        unsafe {
+            SelectObject(self.dc, self.previous);
+            DeleteObject(self.font as HGDIOBJ);
+            DeleteDC(self.dc);
+        }
+    }
+}
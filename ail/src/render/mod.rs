@@ -1,62 +1,296 @@
-use acure::{d2d1::D2D1Surface, surface::Surface, Acure, AlignMode, Color, Command, LayoutMode};
-use winit::{raw_window_handle::HasWindowHandle, window::Window};
+mod display;
+mod font;
+#[cfg(target_os = "windows")]
+mod gdi;
+mod offscreen;
+#[cfg(target_os = "linux")]
+mod xlib;
 
-use crate::widget::Widget;
+pub use display::{Canvas, Change, DecodeError, DisplayList, Item, Point, Transform};
+pub use offscreen::Image;
+
+use acure::Acure;
+use winit::{
+    raw_window_handle::{HasWindowHandle, RawWindowHandle},
+    window::Window,
+};
+
+use crate::{layout::TextMetrics, widget::Widget, Color, Rect, WindowError};
+
+/// Color of the window behind the widgets
+const BACKGROUND: Color = Color::ARGB(255, 240, 240, 240);
+
+/// A drawing backend that the [`Renderer`] replays display lists onto
+///
+/// Each platform surface implements this trait so that widgets can keep producing
+/// the same [`DisplayList`] regardless of the window system.
+pub(crate) trait Target: Canvas {
+    fn resize(&mut self, width: u32, height: u32);
+
+    fn begin(&mut self);
+
+    fn clear(&mut self, color: Color);
+
+    /// Restricts drawing, clearing included, to `clip`, or lifts the restriction if it is empty
+    ///
+    /// Returns whether the surface supports clipping. Surfaces that do not, or that do not
+    /// keep their pixels between frames, are always drawn in full.
+    fn set_clip(&mut self, _clip: &[Rect]) -> bool {
+        false
+    }
+
+    fn end(&mut self);
+
+    /// Metrics of the font used by [`Item::Text`]
+    fn metrics(&self) -> &dyn TextMetrics;
+
+    /// The rendered pixels, for surfaces that keep them in memory
+    fn image(&self) -> Option<&Image> {
+        None
+    }
+}
+
+/// Forwards drawing to a surface provided by acure
+#[cfg(target_os = "windows")]
+struct AcureTarget {
+    inner: Box<dyn acure::surface::Surface>,
+    /// acure does not expose text measurement, see [`gdi::SystemFont`]
+    metrics: gdi::SystemFont,
+    /// Clip set by the display list being replayed
+    clip: Option<Rect>,
+}
+
+#[cfg(target_os = "windows")]
+impl Target for AcureTarget {
+    fn resize(&mut self, width: u32, height: u32) {
+        self.inner.surface_resize(width, height);
+    }
+
+    fn begin(&mut self) {
+        self.inner.begin();
+    }
+
+    fn clear(&mut self, color: Color) {
+        self.inner.clear(color.into());
+    }
+
+    fn end(&mut self) {
+        self.inner.end();
+    }
+
+    fn metrics(&self) -> &dyn TextMetrics {
+        &self.metrics
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl AcureTarget {
+    fn command(&mut self, command: acure::Command) {
+        self.inner.command(
+            &command,
+            acure::AlignMode::CenterAligned,
+            acure::LayoutMode::AdjustSize,
+        )
+    }
+
+    /// The part of `area` inside the clip, if any
+    fn clipped(&self, area: Rect) -> Option<Rect> {
+        match self.clip {
+            Some(clip) => clip.intersection(&area),
+            None => Some(area),
+        }
+    }
+}
+
+/// acure only fills rectangles and writes text, so lines are drawn when they are
+/// horizontal or vertical, and paths and images are skipped, see [`Canvas::supports`].
+///
+/// acure cannot clip either, so rectangles and the boxes text is laid out in are cut to
+/// the clip instead. Text is clipped to its box by the surface.
+#[cfg(target_os = "windows")]
+impl Canvas for AcureTarget {
+    fn fill_rectangle(&mut self, area: Rect, radius: f64, color: Color) {
+        let Some(area) = self.clipped(area) else {
+            return;
+        };
+        self.command(acure::Command::FillRectangle(
+            area.x(),
+            area.y(),
+            area.width(),
+            area.height(),
+            radius,
+            color.into(),
+        ));
+    }
+
+    fn draw_text(&mut self, area: Rect, color: Color, text: &str) {
+        let Some(area) = self.clipped(area) else {
+            return;
+        };
+        self.command(acure::Command::WriteString(
+            area.x(),
+            area.y(),
+            area.width(),
+            area.height(),
+            color.into(),
+            text.to_string(),
+        ));
+    }
+
+    fn draw_line(&mut self, from: Point, to: Point, width: u32, color: Color) {
+        if from.x != to.x && from.y != to.y {
+            return;
+        }
+        let half = (width / 2) as i64;
+        let edge = |value: i64| value.clamp(0, u32::MAX as i64) as u32;
+        let (left, top) = (
+            from.x.min(to.x) as i64 - half,
+            from.y.min(to.y) as i64 - half,
+        );
+        let (right, bottom) = (
+            from.x.max(to.x) as i64 + half + 1,
+            from.y.max(to.y) as i64 + half + 1,
+        );
+        let (left, top, right, bottom) = (edge(left), edge(top), edge(right), edge(bottom));
+        let area = Rect::from_coordinate(left, top, right - left, bottom - top);
+        self.fill_rectangle(area, 0.0, color);
+    }
+
+    fn fill_path(&mut self, _points: &[Point], _color: Color) {}
+
+    fn draw_image(&mut self, _area: Rect, _image: &Image) {}
+
+    fn clip(&mut self, clip: Option<Rect>) {
+        self.clip = clip;
+    }
+
+    fn supports(&self, item: &Item) -> bool {
+        match item {
+            Item::Line(from, to, ..) => from.x == to.x || from.y == to.y,
+            Item::Path(..) | Item::Image(..) => false,
+            _ => true,
+        }
+    }
+}
 
 pub struct Renderer {
     acure: Acure,
-    inner: Box<dyn Surface>,
+    inner: Box<dyn Target>,
 }
 
 impl Renderer {
-    pub fn new(window: &Window) -> Self {
-        let handle = window.window_handle().unwrap();
+    /// Creates a renderer drawing to `window`
+    ///
+    /// Fails for window systems there is no drawing backend for, such as Wayland.
+    pub fn new(window: &Window) -> Result<Self, WindowError> {
+        let handle = window
+            .window_handle()
+            .map_err(|_| WindowError::Surface("the window has no handle"))?;
 
         let acure = Acure::new();
-        let inner = Box::new(match handle.as_raw() {
-            winit::raw_window_handle::RawWindowHandle::UiKit(_) => todo!(),
-            winit::raw_window_handle::RawWindowHandle::AppKit(_) => todo!(),
-            winit::raw_window_handle::RawWindowHandle::Orbital(_) => todo!(),
-            winit::raw_window_handle::RawWindowHandle::Xlib(_) => todo!(),
-            winit::raw_window_handle::RawWindowHandle::Xcb(_) => todo!(),
-            winit::raw_window_handle::RawWindowHandle::Wayland(_) => todo!(),
-            winit::raw_window_handle::RawWindowHandle::Drm(_) => todo!(),
-            winit::raw_window_handle::RawWindowHandle::Gbm(_) => todo!(),
-            winit::raw_window_handle::RawWindowHandle::Win32(handle) => unsafe {
-                D2D1Surface::new(isize::from(handle.hwnd)).unwrap()
-            },
-            winit::raw_window_handle::RawWindowHandle::WinRt(_) => todo!(),
-            winit::raw_window_handle::RawWindowHandle::Web(_) => todo!(),
-            winit::raw_window_handle::RawWindowHandle::WebCanvas(_) => todo!(),
-            winit::raw_window_handle::RawWindowHandle::WebOffscreenCanvas(_) => todo!(),
-            winit::raw_window_handle::RawWindowHandle::AndroidNdk(_) => todo!(),
-            winit::raw_window_handle::RawWindowHandle::Haiku(_) => todo!(),
-            _ => todo!(),
-        });
-
-        Self { acure, inner }
+        let inner: Box<dyn Target> = match handle.as_raw() {
+            #[cfg(target_os = "linux")]
+            RawWindowHandle::Xlib(handle) => Box::new(xlib::XlibSurface::new(handle.window)?),
+            #[cfg(target_os = "linux")]
+            RawWindowHandle::Xcb(handle) => {
+                Box::new(xlib::XlibSurface::new(handle.window.get() as _)?)
+            }
+            #[cfg(target_os = "windows")]
+            RawWindowHandle::Win32(handle) => Box::new(AcureTarget {
+                inner: Box::new(unsafe {
+                    acure::d2d1::D2D1Surface::new(isize::from(handle.hwnd))
+                        .map_err(|_| WindowError::Surface("Direct2D could not be initialized"))?
+                }),
+                metrics: gdi::SystemFont::new()
+                    .ok_or(WindowError::Surface("the system font could not be loaded"))?,
+                clip: None,
+            }),
+            // `Window::new` asks winit for an X11 connection, so Wayland sessions go through XWayland.
+            RawWindowHandle::Wayland(_) => return Err(WindowError::Unsupported("Wayland")),
+            RawWindowHandle::UiKit(_) => return Err(WindowError::Unsupported("UIKit")),
+            RawWindowHandle::AppKit(_) => return Err(WindowError::Unsupported("AppKit")),
+            RawWindowHandle::Orbital(_) => return Err(WindowError::Unsupported("Orbital")),
+            RawWindowHandle::Drm(_) => return Err(WindowError::Unsupported("DRM")),
+            RawWindowHandle::Gbm(_) => return Err(WindowError::Unsupported("GBM")),
+            RawWindowHandle::WinRt(_) => return Err(WindowError::Unsupported("WinRT")),
+            RawWindowHandle::Web(_)
+            | RawWindowHandle::WebCanvas(_)
+            | RawWindowHandle::WebOffscreenCanvas(_) => {
+                return Err(WindowError::Unsupported("web"))
+            }
+            RawWindowHandle::AndroidNdk(_) => return Err(WindowError::Unsupported("Android")),
+            RawWindowHandle::Haiku(_) => return Err(WindowError::Unsupported("Haiku")),
+            _ => return Err(WindowError::Unsupported("unknown")),
+        };
+
+        Ok(Self { acure, inner })
+    }
+
+    /// Creates a renderer that draws into an in-memory RGBA image
+    ///
+    /// No window or display connection is needed, so this works on headless machines.
+    pub fn offscreen(width: u32, height: u32) -> Self {
+        Self {
+            acure: Acure::new(),
+            inner: Box::new(offscreen::OffscreenSurface::new(width, height)),
+        }
     }
 
     pub fn resize(&mut self, width: u32, height: u32) {
-        self.inner.surface_resize(width, height);
+        self.inner.resize(width, height);
     }
 
     pub fn begin(&mut self) {
         self.inner.begin();
-        self.inner.clear(Color::ARGB(255,240,240,240));
+        self.inner.set_clip(&[]);
+        self.inner.clear(BACKGROUND);
+    }
+
+    /// Starts a frame that only repaints `damage`, keeping the rest of the last frame
+    ///
+    /// Returns false, having started a full frame instead, if the surface cannot limit
+    /// drawing to a region.
+    pub fn begin_damaged(&mut self, damage: &[Rect]) -> bool {
+        self.inner.begin();
+        let clipped = self.inner.set_clip(damage);
+        self.inner.clear(BACKGROUND);
+        clipped
     }
 
     pub fn end(&mut self) {
         self.inner.end();
     }
 
-    pub fn render<W>(&mut self, mut widget: &mut Box<W>)
+    /// Metrics of the font text is drawn with
+    pub fn metrics(&self) -> &dyn TextMetrics {
+        self.inner.metrics()
+    }
+
+    /// Whether the surface draws `item`
+    ///
+    /// The Windows backend skips paths, images and lines that are neither horizontal
+    /// nor vertical.
+    pub fn supports(&self, item: &Item) -> bool {
+        self.inner.supports(item)
+    }
+
+    /// Obtains the rendered image if this is an offscreen renderer
+    pub fn image(&self) -> Option<&Image> {
+        self.inner.image()
+    }
+
+    /// Replays `list`, such as decorations that belong to no widget
+    pub fn draw(&mut self, list: &DisplayList) {
+        list.replay(self.inner.as_mut());
+    }
+
+    /// Draws the widget, returning what it drew
+    pub fn render<W>(&mut self, widget: &mut Box<W>) -> DisplayList
     where
         W: Widget + ?Sized,
     {
-        for i in &widget.render() {
-            self.inner
-                .command(i, AlignMode::CenterAligned, LayoutMode::AdjustSize)
-        }
+        let list = widget.render();
+        self.draw(&list);
+        list
     }
 }
@@ -0,0 +1,472 @@
+use crate::{
+    layout::{Monospace, TextMetrics},
+    Color, Rect,
+};
+
+use super::{
+    font::{glyph, GLYPH_HEIGHT, GLYPH_WIDTH},
+    Canvas, Point, Target,
+};
+
+/// Metrics of the built-in bitmap font
+const METRICS: Monospace = Monospace::new(GLYPH_WIDTH, GLYPH_HEIGHT);
+
+/// An RGBA image held in memory
+///
+/// Pixels are stored row by row, four bytes per pixel in `R, G, B, A` order.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Image {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
+impl Image {
+    /// Creates a fully transparent image
+    ///
+    /// # Panics
+    /// Panics if the size of the image in bytes does not fit in `usize`.
+    pub fn new(width: u32, height: u32) -> Self {
+        let len = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|pixels| pixels.checked_mul(4))
+            .expect("image too large");
+        Self {
+            width,
+            height,
+            data: vec![0; len],
+        }
+    }
+
+    /// Creates an image from raw RGBA data
+    ///
+    /// Returns `None` if `data` does not hold exactly `width * height` pixels.
+    pub fn from_rgba(width: u32, height: u32, data: Vec<u8>) -> Option<Self> {
+        let len = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|pixels| pixels.checked_mul(4));
+        if len != Some(data.len()) {
+            return None;
+        }
+        Some(Self {
+            width,
+            height,
+            data,
+        })
+    }
+
+    /// Get width
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Get height
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Obtains the pixel at the given coordinate as `[R, G, B, A]`
+    ///
+    /// # Panics
+    /// Panics if the coordinate is outside of the image.
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        assert!(x < self.width && y < self.height, "pixel out of bounds");
+        let i = self.index(x, y);
+        [
+            self.data[i],
+            self.data[i + 1],
+            self.data[i + 2],
+            self.data[i + 3],
+        ]
+    }
+
+    /// Overwrites the pixel at the given coordinate
+    ///
+    /// # Panics
+    /// Panics if the coordinate is outside of the image.
+    pub fn set_pixel(&mut self, x: u32, y: u32, pixel: [u8; 4]) {
+        assert!(x < self.width && y < self.height, "pixel out of bounds");
+        let i = self.index(x, y);
+        self.data[i..i + 4].copy_from_slice(&pixel);
+    }
+
+    /// Raw RGBA data
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.data
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        (y as usize * self.width as usize + x as usize) * 4
+    }
+
+    /// Draws `color` over the pixel with source-over blending
+    fn blend(&mut self, x: u32, y: u32, color: Color) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let [a, r, g, b] = color.to_argb();
+        let i = self.index(x, y);
+        if a == u8::MAX {
+            self.data[i..i + 4].copy_from_slice(&[r, g, b, a]);
+            return;
+        }
+
+        let sa = a as u32;
+        let da = self.data[i + 3] as u32;
+        let out_a = sa + da * (255 - sa) / 255;
+        if out_a == 0 {
+            return;
+        }
+        for (offset, s) in [r, g, b].into_iter().enumerate() {
+            let d = self.data[i + offset] as u32;
+            let c = (s as u32 * sa + d * da * (255 - sa) / 255) / out_a;
+            self.data[i + offset] = c as u8;
+        }
+        self.data[i + 3] = out_a as u8;
+    }
+}
+
+/// A surface that rasterizes into an [`Image`] without any window or display connection
+pub(crate) struct OffscreenSurface {
+    image: Image,
+    /// Drawing outside of these is discarded, unless there are none
+    clip: Vec<Rect>,
+    /// Clip set by the display list being replayed
+    region: Option<Rect>,
+}
+
+impl OffscreenSurface {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            image: Image::new(width, height),
+            clip: vec![],
+            region: None,
+        }
+    }
+
+    /// Draws `color` over the pixel unless it is clipped
+    fn plot(&mut self, x: u32, y: u32, color: Color) {
+        let damaged = self.clip.is_empty() || self.clip.iter().any(|clip| clip.contains(x, y));
+        if damaged && self.region.is_none_or(|region| region.contains(x, y)) {
+            self.image.blend(x, y, color);
+        }
+    }
+
+    /// The part of the image within the clip of the display list, if any
+    fn bounds(&self) -> Option<Rect> {
+        let image = Rect::from_coordinate(0, 0, self.image.width, self.image.height);
+        match self.region {
+            Some(region) => image.intersection(&region),
+            None => image.intersects(&image).then_some(image),
+        }
+    }
+
+    fn fill_rectangle(
+        &mut self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        radius: f64,
+        color: Color,
+    ) {
+        let radius = radius
+            .max(0.0)
+            .min(width as f64 / 2.0)
+            .min(height as f64 / 2.0);
+        let right = x.saturating_add(width).min(self.image.width);
+        let bottom = y.saturating_add(height).min(self.image.height);
+
+        for py in y..bottom {
+            for px in x..right {
+                if inside_rounded(px, py, x, y, width, height, radius) {
+                    self.plot(px, py, color);
+                }
+            }
+        }
+    }
+
+    fn write_string(&mut self, x: u32, y: u32, width: u32, height: u32, color: Color, text: &str) {
+        let text_width = text.chars().count() as i64 * GLYPH_WIDTH as i64;
+        let left = x as i64 + (width as i64 - text_width) / 2;
+        let top = y as i64 + (height as i64 - GLYPH_HEIGHT as i64) / 2;
+
+        // Text never spills out of the box it was given.
+        let clip_right = x.saturating_add(width).min(self.image.width) as i64;
+        let clip_bottom = y.saturating_add(height).min(self.image.height) as i64;
+
+        for (n, c) in text.chars().enumerate() {
+            let gx = left + n as i64 * GLYPH_WIDTH as i64;
+            for (row, bits) in glyph(c).iter().enumerate() {
+                let py = top + row as i64;
+                if py < y as i64 || py >= clip_bottom {
+                    continue;
+                }
+                for column in 0..GLYPH_WIDTH {
+                    let px = gx + column as i64;
+                    if px < x as i64 || px >= clip_right {
+                        continue;
+                    }
+                    if bits & (1 << (GLYPH_WIDTH - 1 - column)) != 0 {
+                        self.plot(px as u32, py as u32, color);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Target for OffscreenSurface {
+    fn resize(&mut self, width: u32, height: u32) {
+        self.image = Image::new(width, height);
+    }
+
+    fn begin(&mut self) {}
+
+    fn clear(&mut self, color: Color) {
+        let [a, r, g, b] = color.to_argb();
+        let width = self.image.width;
+        for (i, pixel) in self.image.data.chunks_exact_mut(4).enumerate() {
+            let (x, y) = (i as u32 % width, i as u32 / width);
+            if self.clip.is_empty() || self.clip.iter().any(|clip| clip.contains(x, y)) {
+                pixel.copy_from_slice(&[r, g, b, a]);
+            }
+        }
+    }
+
+    fn set_clip(&mut self, clip: &[Rect]) -> bool {
+        self.clip = clip.to_vec();
+        true
+    }
+
+    fn end(&mut self) {}
+
+    fn metrics(&self) -> &dyn TextMetrics {
+        &METRICS
+    }
+
+    fn image(&self) -> Option<&Image> {
+        Some(&self.image)
+    }
+}
+
+impl Canvas for OffscreenSurface {
+    fn fill_rectangle(&mut self, area: Rect, radius: f64, color: Color) {
+        let (x, y, width, height) = (area.x(), area.y(), area.width(), area.height());
+        OffscreenSurface::fill_rectangle(self, x, y, width, height, radius, color);
+    }
+
+    fn draw_text(&mut self, area: Rect, color: Color, text: &str) {
+        let (x, y, width, height) = (area.x(), area.y(), area.width(), area.height());
+        self.write_string(x, y, width, height, color, text);
+    }
+
+    /// Steps along the longer axis, drawing a square of the line's width at every step
+    ///
+    /// Only the pixels within the bounds are visited, each of them once.
+    fn draw_line(&mut self, from: Point, to: Point, width: u32, color: Color) {
+        let Some(bounds) = self.bounds() else {
+            return;
+        };
+        let (before, after) = ((width as i64 - 1) / 2, width as i64 / 2);
+        let (from, to) = ((from.x as i64, from.y as i64), (to.x as i64, to.y as i64));
+        let left = (from.0.min(to.0) - before).max(bounds.x() as i64);
+        let right = (from.0.max(to.0) + after).min((bounds.x() + bounds.width()) as i64 - 1);
+        let top = (from.1.min(to.1) - before).max(bounds.y() as i64);
+        let bottom = (from.1.max(to.1) + after).min((bounds.y() + bounds.height()) as i64 - 1);
+        let vertical = (to.1 - from.1).abs() > (to.0 - from.0).abs();
+        let swap = |(x, y): (i64, i64)| if vertical { (y, x) } else { (x, y) };
+        for py in top..=bottom {
+            for px in left..=right {
+                if covers(swap(from), swap(to), swap((px, py)), before, after) {
+                    self.plot(px as u32, py as u32, color);
+                }
+            }
+        }
+    }
+
+    /// Fills the pixels whose centers lie inside the polygon by the even-odd rule
+    fn fill_path(&mut self, points: &[Point], color: Color) {
+        if points.len() < 3 {
+            return;
+        }
+        let top = points.iter().map(|p| p.y).min().unwrap().max(0) as u32;
+        let bottom =
+            (points.iter().map(|p| p.y).max().unwrap().max(0) as u32).min(self.image.height);
+        for py in top..bottom {
+            let cy = py as f64 + 0.5;
+            let mut crossings: Vec<f64> = points
+                .iter()
+                .zip(points.iter().cycle().skip(1))
+                .filter(|(a, b)| (a.y as f64 <= cy) != (b.y as f64 <= cy))
+                .map(|(a, b)| {
+                    let t = (cy - a.y as f64) / (b.y - a.y) as f64;
+                    a.x as f64 + t * (b.x - a.x) as f64
+                })
+                .collect();
+            crossings.sort_by(f64::total_cmp);
+            for span in crossings.chunks_exact(2) {
+                let left = (span[0] - 0.5).ceil().max(0.0) as u32;
+                let right = ((span[1] - 0.5).ceil().max(0.0) as u32).min(self.image.width);
+                for px in left..right {
+                    self.plot(px, py, color);
+                }
+            }
+        }
+    }
+
+    /// Scales the image to the area by picking the nearest pixel
+    fn draw_image(&mut self, area: Rect, image: &Image) {
+        if image.width == 0 || image.height == 0 {
+            return;
+        }
+        let Some(visible) = self.bounds().and_then(|bounds| bounds.intersection(&area)) else {
+            return;
+        };
+        for y in visible.y()..visible.y() + visible.height() {
+            for x in visible.x()..visible.x() + visible.width() {
+                let (px, py) = (x - area.x(), y - area.y());
+                let sx = (px as u64 * image.width as u64 / area.width() as u64) as u32;
+                let sy = (py as u64 * image.height as u64 / area.height() as u64) as u32;
+                let [r, g, b, a] = image.pixel(sx, sy);
+                self.plot(x, y, Color::ARGB(a, r, g, b));
+            }
+        }
+    }
+
+    fn clip(&mut self, clip: Option<Rect>) {
+        self.region = clip;
+    }
+}
+
+/// Offset of a line at `step` of `steps`, of which it moves `delta` in all
+fn position(delta: i64, step: i64, steps: i64) -> i64 {
+    (delta as i128 * step as i128 + steps as i128 / 2).div_euclid(steps as i128) as i64
+}
+
+/// Whether a square reaching `before` pixels back and `after` pixels on from its center,
+/// stepped from `from` to `to`, covers `pixel`
+///
+/// The first coordinates are those of the longer axis, along which every step moves by one.
+fn covers(from: (i64, i64), to: (i64, i64), pixel: (i64, i64), before: i64, after: i64) -> bool {
+    let (major, minor) = (to.0 - from.0, to.1 - from.1);
+    let steps = major.abs().max(1);
+    // The steps whose square reaches the pixel along the longer axis
+    let (low, high) = (pixel.0 - after - from.0, pixel.0 + before - from.0);
+    let (first, last) = match major.signum() {
+        1 => (low.max(0), high.min(steps)),
+        -1 => ((-high).max(0), (-low).min(steps)),
+        _ if low <= 0 && 0 <= high => (0, steps),
+        _ => return false,
+    };
+    if first > last {
+        return false;
+    }
+    // The other coordinate moves by at most one per step, so it passes every value in between.
+    let a = from.1 + position(minor, first, steps);
+    let b = from.1 + position(minor, last, steps);
+    a.min(b) <= pixel.1 + before && a.max(b) >= pixel.1 - after
+}
+
+/// Tests whether the center of pixel (`px`, `py`) lies in the rounded rectangle
+fn inside_rounded(px: u32, py: u32, x: u32, y: u32, width: u32, height: u32, radius: f64) -> bool {
+    if radius <= 0.0 {
+        return true;
+    }
+    let cx = px as f64 + 0.5;
+    let cy = py as f64 + 0.5;
+    let left = x as f64 + radius;
+    let right = (x + width) as f64 - radius;
+    let top = y as f64 + radius;
+    let bottom = (y + height) as f64 - radius;
+
+    let dx = if cx < left {
+        left - cx
+    } else if cx > right {
+        cx - right
+    } else {
+        0.0
+    };
+    let dy = if cy < top {
+        top - cy
+    } else if cy > bottom {
+        cy - bottom
+    } else {
+        0.0
+    };
+    dx * dx + dy * dy <= radius * radius
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Image, OffscreenSurface};
+    use crate::{
+        layout::{Flex, Padding},
+        render::{Canvas, DisplayList},
+        testing::Driver,
+        widget::{Button, Panel},
+        Color, Rect, Theme,
+    };
+
+    fn rgba(color: crate::Color) -> [u8; 4] {
+        let acure::Color::ARGB(a, r, g, b) = color.into();
+        [r, g, b, a]
+    }
+
+    #[test]
+    fn button_in_every_theme() {
+        for theme in [
+            Theme::LIGHT,
+            Theme::DARK,
+            Theme::LIGHT_HIGH_CONTRAST,
+            Theme::DARK_HIGH_CONTRAST,
+        ] {
+            let mut panel = Panel::new("panel");
+            panel.set_layout(Flex::column().padding(Padding::all(10)));
+            panel.push(Button::new("button"));
+            let mut driver = Driver::with_theme(panel, theme, 300, 80);
+            driver.step();
+
+            let image = driver.image();
+            assert_eq!(image.pixel(30, 10), rgba(theme.normal.shadow));
+            assert_eq!(image.pixel(30, 12), rgba(theme.normal.bgr));
+            assert_eq!(image.pixel(5, 5), [240, 240, 240, 255]);
+            // The rounded corner leaves the outermost pixel untouched.
+            assert_eq!(image.pixel(10, 10), [240, 240, 240, 255]);
+        }
+    }
+
+    #[test]
+    fn rectangles_reaching_past_the_coordinate_range() {
+        let mut surface = OffscreenSurface::new(4, 4);
+        surface.fill_rectangle(2, 2, u32::MAX, u32::MAX, 0.0, Color::Black);
+        assert_eq!(surface.image.pixel(3, 3), [0, 0, 0, 255]);
+        assert_eq!(surface.image.pixel(1, 1), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn lines_and_images_spanning_the_coordinate_range() {
+        let list: DisplayList = "line 2147483647 0 -2147483648 0 1 black\n\
+            line -2147483648 -2147483648 2147483647 2147483647 4294967295 white\n\
+            push-clip 1 1 2 2\n\
+            line -2147483648 2 2147483647 2 1 black\n\
+            pop-clip"
+            .parse()
+            .unwrap();
+        let mut surface = OffscreenSurface::new(4, 4);
+        list.replay(&mut surface);
+        assert_eq!(surface.image.pixel(0, 0), [255, 255, 255, 255]);
+        assert_eq!(surface.image.pixel(1, 2), [0, 0, 0, 255]);
+        assert_eq!(surface.image.pixel(3, 2), [255, 255, 255, 255]);
+
+        let mut image = Image::new(1, 1);
+        image.data.copy_from_slice(&[0, 0, 0, 255]);
+        let area = Rect::from_coordinate(0, 0, u32::MAX, u32::MAX);
+        surface.draw_image(area, &image);
+        assert_eq!(surface.image.pixel(3, 3), [0, 0, 0, 255]);
+    }
+}
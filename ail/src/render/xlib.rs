@@ -0,0 +1,649 @@
+use std::{
+    ffi::{c_int, c_uint, c_ulong},
+    mem::MaybeUninit,
+    ptr::{null, null_mut},
+};
+
+use x11::xlib::{
+    CapRound, Complex, CoordModeOrigin, Display, JoinRound, LineSolid, Unsorted, XAllPlanes,
+    XChar2b, XCloseDisplay, XCopyArea, XCreateGC, XCreatePixmap, XDestroyImage, XDrawLine,
+    XDrawString16, XFillArc, XFillPolygon, XFillRectangle, XFlush, XFontStruct, XFreeFont, XFreeGC,
+    XFreePixmap, XGetImage, XGetPixel, XGetWindowAttributes, XImage, XLoadQueryFont, XOpenDisplay,
+    XPoint, XPutImage, XPutPixel, XRectangle, XSetClipMask, XSetClipRectangles, XSetFont,
+    XSetForeground, XSetLineAttributes, XTextWidth16, XWindowAttributes, ZPixmap, GC,
+};
+
+use crate::{layout::TextMetrics, Color, Rect, WindowError};
+
+use super::{Canvas, Image, Point, Target};
+
+/// A window surface drawn with plain Xlib calls
+///
+/// Drawing goes to an off-screen pixmap which is copied to the window in [`Target::end`],
+/// so a frame never shows up half-drawn.
+pub(crate) struct XlibSurface {
+    display: *mut Display,
+    window: c_ulong,
+    gc: GC,
+    font: CoreFont,
+    buffer: c_ulong,
+    depth: c_uint,
+    width: u32,
+    height: u32,
+    /// Damaged areas set through [`Target::set_clip`], everything if empty
+    damage: Vec<Rect>,
+    /// Clip set by the display list being replayed
+    region: Option<Rect>,
+}
+
+impl XlibSurface {
+    /// Opens a connection to the X server and prepares drawing to `window`
+    ///
+    /// Fails if the display cannot be opened or the window is not found on it.
+    pub fn new(window: c_ulong) -> Result<Self, WindowError> {
+        unsafe {
+            let display = XOpenDisplay(null());
+            if display.is_null() {
+                return Err(WindowError::Surface("the X display could not be opened"));
+            }
+
+            let mut attributes: MaybeUninit<XWindowAttributes> = MaybeUninit::uninit();
+            if XGetWindowAttributes(display, window, attributes.as_mut_ptr()) == 0 {
+                XCloseDisplay(display);
+                return Err(WindowError::Surface("the X window could not be queried"));
+            }
+            let attributes = attributes.assume_init();
+
+            let gc = XCreateGC(display, window, 0, null_mut());
+            // The Unicode encoding of the default font, falling back to the Latin-1 one.
+            let mut font = XLoadQueryFont(
+                display,
+                c"-misc-fixed-medium-r-semicondensed--13-*-*-*-*-*-iso10646-1".as_ptr(),
+            );
+            if font.is_null() {
+                font = XLoadQueryFont(display, c"fixed".as_ptr());
+            }
+            if !font.is_null() {
+                XSetFont(display, gc, (*font).fid);
+            }
+
+            let width = attributes.width.max(1) as u32;
+            let height = attributes.height.max(1) as u32;
+            let depth = attributes.depth as c_uint;
+            let buffer = XCreatePixmap(display, window, width, height, depth);
+
+            Ok(Self {
+                display,
+                window,
+                gc,
+                font: CoreFont(font),
+                buffer,
+                depth,
+                width,
+                height,
+                damage: vec![],
+                region: None,
+            })
+        }
+    }
+
+    /// Prepares drawing with `color` within `area`, which is finished by [`XlibSurface::blend`]
+    ///
+    /// Returns `None` if nothing would be visible. The core protocol only replaces pixels,
+    /// so translucent colors are drawn opaque and the pixels that changed are mixed with
+    /// those under them afterwards.
+    fn set_color(&mut self, color: Color, area: Option<Rect>) -> Option<Paint> {
+        let [a, r, g, b] = color.to_argb();
+        if a == 0 {
+            return None;
+        }
+        unsafe {
+            XSetForeground(self.display, self.gc, pixel(r, g, b));
+        }
+        if a == u8::MAX {
+            return Some(Paint::Opaque);
+        }
+        let area = area?;
+        let backdrop = self.get_image(area)?;
+        Some(Paint::Translucent {
+            backdrop,
+            area,
+            color,
+        })
+    }
+
+    /// Mixes translucent drawing into the pixels that were under it
+    fn blend(&mut self, paint: Paint) {
+        let Paint::Translucent {
+            backdrop,
+            area,
+            color,
+        } = paint
+        else {
+            return;
+        };
+        unsafe {
+            if let Some(drawn) = self.get_image(area) {
+                for y in 0..area.height() as c_int {
+                    for x in 0..area.width() as c_int {
+                        let under = XGetPixel(backdrop, x, y);
+                        if XGetPixel(drawn, x, y) != under {
+                            XPutPixel(drawn, x, y, mix(under, color));
+                        }
+                    }
+                }
+                self.put_image(drawn, area);
+            }
+            XDestroyImage(backdrop);
+        }
+    }
+
+    /// The part of the buffer between the given edges, if any
+    fn bounds(&self, left: i64, top: i64, right: i64, bottom: i64) -> Option<Rect> {
+        let (left, top) = (left.max(0), top.max(0));
+        let right = right.min(self.width as i64);
+        let bottom = bottom.min(self.height as i64);
+        (left < right && top < bottom).then(|| {
+            Rect::from_coordinate(
+                left as u32,
+                top as u32,
+                (right - left) as u32,
+                (bottom - top) as u32,
+            )
+        })
+    }
+
+    /// Reads back `area` of the buffer, which must lie within it
+    fn get_image(&self, area: Rect) -> Option<*mut XImage> {
+        let image = unsafe {
+            XGetImage(
+                self.display,
+                self.buffer,
+                area.x() as c_int,
+                area.y() as c_int,
+                area.width(),
+                area.height(),
+                XAllPlanes(),
+                ZPixmap,
+            )
+        };
+        (!image.is_null()).then_some(image)
+    }
+
+    /// Writes `image` to `area` of the buffer through the clip and frees it
+    fn put_image(&mut self, image: *mut XImage, area: Rect) {
+        unsafe {
+            XPutImage(
+                self.display,
+                self.buffer,
+                self.gc,
+                image,
+                0,
+                0,
+                area.x() as c_int,
+                area.y() as c_int,
+                area.width(),
+                area.height(),
+            );
+            XDestroyImage(image);
+        }
+    }
+
+    fn fill_rectangle(
+        &mut self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        radius: f64,
+        color: Color,
+    ) {
+        let (left, top) = (x as i64, y as i64);
+        let area = self.bounds(left, top, left + width as i64, top + height as i64);
+        let Some(paint) = self.set_color(color, area) else {
+            return;
+        };
+        let radius = (radius.round().max(0.0) as u32)
+            .min(width / 2)
+            .min(height / 2);
+        let (x, y) = (x as c_int, y as c_int);
+        unsafe {
+            if radius == 0 {
+                XFillRectangle(self.display, self.buffer, self.gc, x, y, width, height);
+                self.blend(paint);
+                return;
+            }
+
+            let r = radius as c_int;
+            let d = radius * 2;
+            XFillRectangle(
+                self.display,
+                self.buffer,
+                self.gc,
+                x + r,
+                y,
+                width - d,
+                height,
+            );
+            XFillRectangle(
+                self.display,
+                self.buffer,
+                self.gc,
+                x,
+                y + r,
+                width,
+                height - d,
+            );
+
+            let right = x + width as c_int - d as c_int;
+            let bottom = y + height as c_int - d as c_int;
+            for (cx, cy) in [(x, y), (right, y), (x, bottom), (right, bottom)] {
+                XFillArc(
+                    self.display,
+                    self.buffer,
+                    self.gc,
+                    cx,
+                    cy,
+                    d,
+                    d,
+                    0,
+                    360 * 64,
+                );
+            }
+        }
+        self.blend(paint);
+    }
+
+    /// Clips the GC to the damaged areas within the region of the display list
+    fn apply_clip(&mut self) {
+        let mut clip = self.damage.clone();
+        if let Some(region) = self.region {
+            clip = match clip.is_empty() {
+                true => vec![region],
+                false => clip
+                    .iter()
+                    .filter_map(|d| d.intersection(&region))
+                    .collect(),
+            };
+            // Nothing may be drawn, which an empty list of rectangles would not express.
+            if clip.is_empty() {
+                clip.push(Rect::from_coordinate(0, 0, 0, 0));
+            }
+        }
+        unsafe {
+            if clip.is_empty() {
+                XSetClipMask(self.display, self.gc, 0);
+                return;
+            }
+            let mut rectangles: Vec<XRectangle> = clip
+                .iter()
+                .map(|rect| XRectangle {
+                    x: rect.x().min(i16::MAX as u32) as i16,
+                    y: rect.y().min(i16::MAX as u32) as i16,
+                    width: rect.width().min(u16::MAX as u32) as u16,
+                    height: rect.height().min(u16::MAX as u32) as u16,
+                })
+                .collect();
+            XSetClipRectangles(
+                self.display,
+                self.gc,
+                0,
+                0,
+                rectangles.as_mut_ptr(),
+                rectangles.len() as c_int,
+                Unsorted,
+            );
+        }
+    }
+
+    fn write_string(&mut self, x: u32, y: u32, width: u32, height: u32, color: Color, text: &str) {
+        let chars = ucs2(text);
+        let len = chars.len() as c_int;
+
+        let font = self.font.0;
+        let (text_width, ascent, descent) = if font.is_null() {
+            (0, 0, 0)
+        } else {
+            unsafe {
+                (
+                    XTextWidth16(font, chars.as_ptr(), len),
+                    (*font).ascent,
+                    (*font).descent,
+                )
+            }
+        };
+
+        let tx = x as c_int + (width as c_int - text_width) / 2;
+        let ty = y as c_int + (height as c_int - (ascent + descent)) / 2 + ascent;
+        let (left, top) = (tx as i64, (ty - ascent) as i64);
+        let area = self.bounds(
+            left,
+            top,
+            left + text_width as i64,
+            top + (ascent + descent) as i64,
+        );
+        let Some(paint) = self.set_color(color, area) else {
+            return;
+        };
+        unsafe {
+            XDrawString16(
+                self.display,
+                self.buffer,
+                self.gc,
+                tx,
+                ty,
+                chars.as_ptr(),
+                len,
+            );
+        }
+        self.blend(paint);
+    }
+}
+
+/// How the drawing started by [`XlibSurface::set_color`] is finished
+enum Paint {
+    Opaque,
+    /// The drawing is mixed into the `backdrop`, read from `area` of the buffer beforehand
+    Translucent {
+        backdrop: *mut XImage,
+        area: Rect,
+        color: Color,
+    },
+}
+
+/// The pixel value of a color
+///
+/// Windows created by winit use a TrueColor visual, so the pixel value is plain RGB.
+fn pixel(r: u8, g: u8, b: u8) -> c_ulong {
+    ((r as c_ulong) << 16) | ((g as c_ulong) << 8) | b as c_ulong
+}
+
+/// Draws `color` over the opaque pixel `under` with source-over blending
+fn mix(under: c_ulong, color: Color) -> c_ulong {
+    let [a, r, g, b] = color.to_argb();
+    let a = a as c_ulong;
+    let channel = |c: u8, shift: u32| {
+        let d = (under >> shift) & 0xFF;
+        ((c as c_ulong * a + d * (255 - a)) / 255) as u8
+    };
+    pixel(channel(r, 16), channel(g, 8), channel(b, 0))
+}
+
+/// A core X font, measured on the client from the glyph metrics the server sent
+///
+/// The pointer is null if no font could be loaded, in which case text takes no space.
+struct CoreFont(*mut XFontStruct);
+
+impl TextMetrics for CoreFont {
+    fn text_width(&self, text: &str) -> u32 {
+        if self.0.is_null() {
+            return 0;
+        }
+        let chars = ucs2(text);
+        unsafe { XTextWidth16(self.0, chars.as_ptr(), chars.len() as c_int).max(0) as u32 }
+    }
+
+    fn line_height(&self) -> u32 {
+        if self.0.is_null() {
+            return 0;
+        }
+        unsafe { ((*self.0).ascent + (*self.0).descent).max(0) as u32 }
+    }
+}
+
+impl Target for XlibSurface {
+    fn resize(&mut self, width: u32, height: u32) {
+        let width = width.max(1);
+        let height = height.max(1);
+        unsafe {
+            XFreePixmap(self.display, self.buffer);
+            self.buffer = XCreatePixmap(self.display, self.window, width, height, self.depth);
+        }
+        self.width = width;
+        self.height = height;
+    }
+
+    fn begin(&mut self) {}
+
+    fn clear(&mut self, color: Color) {
+        let (width, height) = (self.width, self.height);
+        XlibSurface::fill_rectangle(self, 0, 0, width, height, 0.0, color);
+    }
+
+    /// The clip also applies to copying the buffer in [`Target::end`], so only the damaged
+    /// part of the window is updated.
+    fn set_clip(&mut self, clip: &[Rect]) -> bool {
+        self.damage = clip.to_vec();
+        self.region = None;
+        self.apply_clip();
+        true
+    }
+
+    fn end(&mut self) {
+        unsafe {
+            XCopyArea(
+                self.display,
+                self.buffer,
+                self.window,
+                self.gc,
+                0,
+                0,
+                self.width,
+                self.height,
+                0,
+                0,
+            );
+            XFlush(self.display);
+        }
+    }
+
+    fn metrics(&self) -> &dyn TextMetrics {
+        &self.font
+    }
+}
+
+impl Canvas for XlibSurface {
+    fn fill_rectangle(&mut self, area: Rect, radius: f64, color: Color) {
+        let (x, y, width, height) = (area.x(), area.y(), area.width(), area.height());
+        XlibSurface::fill_rectangle(self, x, y, width, height, radius, color);
+    }
+
+    fn draw_text(&mut self, area: Rect, color: Color, text: &str) {
+        let (x, y, width, height) = (area.x(), area.y(), area.width(), area.height());
+        self.write_string(x, y, width, height, color, text);
+    }
+
+    fn draw_line(&mut self, from: Point, to: Point, width: u32, color: Color) {
+        let half = width as i64 / 2 + 1;
+        let area = self.bounds(
+            from.x.min(to.x) as i64 - half,
+            from.y.min(to.y) as i64 - half,
+            from.x.max(to.x) as i64 + half + 1,
+            from.y.max(to.y) as i64 + half + 1,
+        );
+        let Some(paint) = self.set_color(color, area) else {
+            return;
+        };
+        unsafe {
+            XSetLineAttributes(self.display, self.gc, width, LineSolid, CapRound, JoinRound);
+            XDrawLine(
+                self.display,
+                self.buffer,
+                self.gc,
+                from.x,
+                from.y,
+                to.x,
+                to.y,
+            );
+        }
+        self.blend(paint);
+    }
+
+    fn fill_path(&mut self, points: &[Point], color: Color) {
+        if points.len() < 3 {
+            return;
+        }
+        let (xs, ys) = (points.iter().map(|p| p.x), points.iter().map(|p| p.y));
+        let area = self.bounds(
+            xs.clone().min().unwrap() as i64,
+            ys.clone().min().unwrap() as i64,
+            xs.max().unwrap() as i64 + 1,
+            ys.max().unwrap() as i64 + 1,
+        );
+        let Some(paint) = self.set_color(color, area) else {
+            return;
+        };
+        let mut points: Vec<XPoint> = points
+            .iter()
+            .map(|point| XPoint {
+                x: point.x.clamp(i16::MIN as i32, i16::MAX as i32) as i16,
+                y: point.y.clamp(i16::MIN as i32, i16::MAX as i32) as i16,
+            })
+            .collect();
+        unsafe {
+            XFillPolygon(
+                self.display,
+                self.buffer,
+                self.gc,
+                points.as_mut_ptr(),
+                points.len() as c_int,
+                Complex,
+                CoordModeOrigin,
+            );
+        }
+        self.blend(paint);
+    }
+
+    /// Blends the nearest pixel of the image over every pixel of the area in the buffer
+    fn draw_image(&mut self, area: Rect, image: &Image) {
+        if image.width() == 0 || image.height() == 0 {
+            return;
+        }
+        let (left, top) = (area.x() as i64, area.y() as i64);
+        let visible = self.bounds(
+            left,
+            top,
+            left + area.width() as i64,
+            top + area.height() as i64,
+        );
+        let Some(visible) = visible else {
+            return;
+        };
+        let Some(pixels) = self.get_image(visible) else {
+            return;
+        };
+        for y in 0..visible.height() {
+            for x in 0..visible.width() {
+                let (px, py) = (visible.x() - area.x() + x, visible.y() - area.y() + y);
+                let sx = (px as u64 * image.width() as u64 / area.width() as u64) as u32;
+                let sy = (py as u64 * image.height() as u64 / area.height() as u64) as u32;
+                let [r, g, b, a] = image.pixel(sx, sy);
+                unsafe {
+                    let under = XGetPixel(pixels, x as c_int, y as c_int);
+                    let pixel = mix(under, Color::ARGB(a, r, g, b));
+                    XPutPixel(pixels, x as c_int, y as c_int, pixel);
+                }
+            }
+        }
+        self.put_image(pixels, visible);
+    }
+
+    fn clip(&mut self, clip: Option<Rect>) {
+        self.region = clip;
+        self.apply_clip();
+    }
+}
+
+/// Converts text for the 16-bit calls of the core X fonts, which index glyphs by UCS-2
+///
+/// Characters outside of the Basic Multilingual Plane are replaced with U+FFFD. With the
+/// Latin-1 fallback font, the characters it lacks are drawn as its default character.
+fn ucs2(text: &str) -> Vec<XChar2b> {
+    text.chars()
+        .map(|c| u16::try_from(c as u32).unwrap_or(0xFFFD))
+        .map(|c| XChar2b {
+            byte1: (c >> 8) as u8,
+            byte2: c as u8,
+        })
+        .collect()
+}
+
+impl Drop for XlibSurface {
+    fn drop(&mut self) {
+        unsafe {
+            XFreePixmap(self.display, self.buffer);
+            if !self.font.0.is_null() {
+                XFreeFont(self.display, self.font.0);
+            }
+            XFreeGC(self.display, self.gc);
+            XCloseDisplay(self.display);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{mem::MaybeUninit, ptr::null_mut};
+
+    use x11::xlib::{XCharStruct, XFontStruct};
+
+    use super::{mix, pixel, CoreFont};
+    use crate::{layout::TextMetrics, widget::preedit::Preedit, Color};
+
+    /// A font covering the Basic Multilingual Plane up to U+67FF, as the server describes it
+    fn font(chars: &mut [XCharStruct]) -> XFontStruct {
+        let mut font: XFontStruct = unsafe { MaybeUninit::zeroed().assume_init() };
+        font.min_byte1 = 0x00;
+        font.max_byte1 = 0x67;
+        font.min_char_or_byte2 = 0x00;
+        font.max_char_or_byte2 = 0xFF;
+        font.default_char = '?' as u32;
+        // Xlib measures fonts whose glyphs share a width from the bounds alone.
+        font.min_bounds = *chars.iter().min_by_key(|glyph| glyph.width).unwrap();
+        font.max_bounds = *chars.iter().max_by_key(|glyph| glyph.width).unwrap();
+        font.per_char = chars.as_mut_ptr();
+        font.ascent = 10;
+        font.descent = 3;
+        font
+    }
+
+    #[test]
+    fn non_latin1_preedit_is_measured() {
+        let glyph = |width| XCharStruct {
+            lbearing: 0,
+            rbearing: width,
+            width,
+            ascent: 10,
+            descent: 3,
+            attributes: 0,
+        };
+        let mut chars = vec![glyph(6); 0x68 * 0x100];
+        // Ideographs are twice as wide as the question mark they used to be drawn as.
+        for c in ['日', '本'] {
+            chars[c as usize] = glyph(12);
+        }
+        let mut font = font(&mut chars);
+        let metrics = CoreFont(&mut font);
+
+        let mut preedit = Preedit::default();
+        preedit.set("日本", None);
+        preedit.arrange(&metrics);
+        assert_eq!(preedit.area(0, 0, 13, (0, 100)).width(), 24);
+        assert_eq!(metrics.text_width("aé日"), 6 + 6 + 12);
+        assert_eq!(metrics.line_height(), 13);
+
+        let empty = CoreFont(null_mut());
+        assert_eq!(empty.text_width("日本"), 0);
+    }
+
+    #[test]
+    fn translucent_colors_are_mixed() {
+        let white = pixel(255, 255, 255);
+        assert_eq!(mix(white, Color::ARGB(96, 0, 0, 0)), pixel(159, 159, 159));
+        assert_eq!(mix(white, Color::ARGB(255, 10, 20, 30)), pixel(10, 20, 30));
+        assert_eq!(
+            mix(pixel(0, 0, 200), Color::ARGB(128, 200, 0, 0)),
+            pixel(100, 0, 99)
+        );
+    }
+}
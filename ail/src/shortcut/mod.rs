@@ -0,0 +1,579 @@
+//! Keyboard shortcuts bound to messages or callbacks
+//!
+//! A [`Shortcut`] is a sequence of key strokes, so chords such as Ctrl+K Ctrl+C work the same
+//! way as single strokes like Ctrl+S. Shortcuts are looked at before the focused widget sees
+//! the key, and are bound to a [`Scope`]: the whole window, or a widget and its descendants
+//! while one of them has focus.
+//!
+//! ```
+//! use ail::shortcut::{Scope, Shortcuts};
+//! use aom::ID;
+//!
+//! let mut shortcuts = Shortcuts::new();
+//! shortcuts
+//!     .bind("Ctrl+S".parse().unwrap(), Scope::Window, ID::from("save"))
+//!     .unwrap();
+//! shortcuts
+//!     .bind("Ctrl+K Ctrl+C".parse().unwrap(), Scope::Window, ID::from("comment"))
+//!     .unwrap();
+//!
+//! // Ctrl+K would never fire, since it starts the chord above.
+//! let conflict = shortcuts.bind("Ctrl+K".parse().unwrap(), Scope::Window, ID::from("kill"));
+//! assert!(conflict.is_err());
+//! ```
+
+use std::{fmt, str::FromStr};
+
+use aom::ID;
+
+use crate::{management::WidgetRegistry, Key, KeyCode, Modifiers};
+
+/// A single key pressed together with modifiers
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Stroke {
+    pub modifiers: Modifiers,
+    pub code: KeyCode,
+}
+
+impl Stroke {
+    pub const fn new(modifiers: Modifiers, code: KeyCode) -> Self {
+        Self { modifiers, code }
+    }
+
+    /// Identifies a key press
+    ///
+    /// Letters and digits follow the keyboard layout, so Ctrl+Z is the key labelled Z.
+    /// Other keys are identified by their position.
+    pub(crate) fn of_key(key: &Key, code: KeyCode, modifiers: Modifiers) -> Self {
+        let code = match KeyCode::from_logical(key) {
+            KeyCode::Unidentified => code,
+            logical => logical,
+        };
+        Self { modifiers, code }
+    }
+
+    /// Whether the key is a modifier, which only ever begins a stroke
+    fn is_modifier(&self) -> bool {
+        matches!(
+            self.code,
+            KeyCode::ShiftLeft
+                | KeyCode::ShiftRight
+                | KeyCode::ControlLeft
+                | KeyCode::ControlRight
+                | KeyCode::AltLeft
+                | KeyCode::AltRight
+                | KeyCode::SuperLeft
+                | KeyCode::SuperRight
+        )
+    }
+}
+
+impl fmt::Display for Stroke {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [
+            (Modifiers::CONTROL, "Ctrl"),
+            (Modifiers::ALT, "Alt"),
+            (Modifiers::SHIFT, "Shift"),
+            (Modifiers::SUPER, "Super"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{}+", name)?;
+            }
+        }
+        match self.code {
+            KeyCode::ArrowUp => write!(f, "Up"),
+            KeyCode::ArrowDown => write!(f, "Down"),
+            KeyCode::ArrowLeft => write!(f, "Left"),
+            KeyCode::ArrowRight => write!(f, "Right"),
+            code => {
+                let name = format!("{:?}", code);
+                write!(f, "{}", name.strip_prefix("Digit").unwrap_or(&name))
+            }
+        }
+    }
+}
+
+impl FromStr for Stroke {
+    type Err = ShortcutError;
+
+    /// Parses strokes such as `Ctrl+Shift+P` or `F5`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ShortcutError::Parse(s.to_string());
+        let mut parts: Vec<&str> = s.split('+').map(str::trim).collect();
+        let key = parts
+            .pop()
+            .filter(|key| !key.is_empty())
+            .ok_or_else(error)?;
+
+        let mut modifiers = Modifiers::empty();
+        for part in parts {
+            modifiers |= match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => Modifiers::CONTROL,
+                "shift" => Modifiers::SHIFT,
+                "alt" | "option" => Modifiers::ALT,
+                "super" | "cmd" | "command" | "win" | "meta" => Modifiers::SUPER,
+                _ => return Err(error()),
+            };
+        }
+        let code = key_from_name(key).ok_or_else(error)?;
+        Ok(Self::new(modifiers, code))
+    }
+}
+
+/// A sequence of strokes that triggers an action
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Shortcut {
+    strokes: Vec<Stroke>,
+}
+
+impl Shortcut {
+    pub fn new(modifiers: Modifiers, code: KeyCode) -> Self {
+        Self {
+            strokes: vec![Stroke::new(modifiers, code)],
+        }
+    }
+
+    /// Adds a stroke to be pressed after the previous ones, making a chord
+    pub fn then(mut self, modifiers: Modifiers, code: KeyCode) -> Self {
+        self.strokes.push(Stroke::new(modifiers, code));
+        self
+    }
+
+    pub fn strokes(&self) -> &[Stroke] {
+        &self.strokes
+    }
+
+    /// Whether one of the shortcuts is the beginning of the other, so both cannot be bound
+    fn overlaps(&self, other: &Shortcut) -> bool {
+        self.strokes.starts_with(&other.strokes) || other.strokes.starts_with(&self.strokes)
+    }
+}
+
+impl fmt::Display for Shortcut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, stroke) in self.strokes.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", stroke)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Shortcut {
+    type Err = ShortcutError;
+
+    /// Parses strokes separated by spaces, such as `Ctrl+K Ctrl+C`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let strokes = s
+            .split_whitespace()
+            .map(Stroke::from_str)
+            .collect::<Result<Vec<_>, _>>()?;
+        match strokes.is_empty() {
+            true => Err(ShortcutError::Parse(s.to_string())),
+            false => Ok(Self { strokes }),
+        }
+    }
+}
+
+/// Where a shortcut is active
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Scope {
+    /// Anywhere in the window
+    Window,
+    /// While the widget or one of its descendants has focus
+    ///
+    /// Shortcuts of the innermost scope win over the same shortcut bound further out.
+    Widget(ID),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ShortcutError {
+    /// The text does not describe a shortcut
+    Parse(String),
+    /// A shortcut bound in the same scope equals or begins the new one, or the other way round
+    Conflict {
+        shortcut: Shortcut,
+        existing: Shortcut,
+        scope: Scope,
+    },
+}
+
+impl fmt::Display for ShortcutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShortcutError::Parse(text) => write!(f, "`{}` is not a shortcut", text),
+            ShortcutError::Conflict {
+                shortcut,
+                existing,
+                scope,
+            } => write!(
+                f,
+                "`{}` conflicts with `{}` bound in {:?}",
+                shortcut, existing, scope
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ShortcutError {}
+
+type Callback = Box<dyn FnMut(&mut WidgetRegistry)>;
+
+enum Action {
+    Message(ID),
+    Callback(Callback),
+}
+
+struct Binding {
+    shortcut: Shortcut,
+    scope: Scope,
+    action: Action,
+}
+
+/// What became of a key press
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Outcome {
+    /// No shortcut uses the key, so it goes to the focused widget
+    Ignored,
+    /// The key began or continued a chord, ran a callback or cancelled a chord
+    Consumed,
+    /// The key completed a shortcut bound to a message
+    Message(ID),
+}
+
+/// The shortcuts of a window
+///
+/// Shortcuts bound to a message are reported as [`ApplicationEvent::Shortcut`].
+///
+/// [`ApplicationEvent::Shortcut`]: crate::ApplicationEvent::Shortcut
+#[derive(Default)]
+pub struct Shortcuts {
+    bindings: Vec<Binding>,
+    /// Strokes of a chord pressed so far
+    pending: Vec<Stroke>,
+}
+
+impl Shortcuts {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Binds `shortcut` to report `message` to the application callback
+    pub fn bind(
+        &mut self,
+        shortcut: Shortcut,
+        scope: Scope,
+        message: ID,
+    ) -> Result<(), ShortcutError> {
+        self.insert(shortcut, scope, Action::Message(message))
+    }
+
+    /// Binds `shortcut` to run `callback`
+    pub fn bind_fn<F>(
+        &mut self,
+        shortcut: Shortcut,
+        scope: Scope,
+        callback: F,
+    ) -> Result<(), ShortcutError>
+    where
+        F: FnMut(&mut WidgetRegistry) + 'static,
+    {
+        self.insert(shortcut, scope, Action::Callback(Box::new(callback)))
+    }
+
+    /// Removes the binding of `shortcut` in `scope`, returning whether there was one
+    pub fn unbind(&mut self, shortcut: &Shortcut, scope: Scope) -> bool {
+        let len = self.bindings.len();
+        self.bindings
+            .retain(|binding| !(binding.shortcut == *shortcut && binding.scope == scope));
+        self.pending.clear();
+        self.bindings.len() != len
+    }
+
+    /// Whether the first strokes of a chord were pressed and the next one is awaited
+    pub fn is_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    fn insert(
+        &mut self,
+        shortcut: Shortcut,
+        scope: Scope,
+        action: Action,
+    ) -> Result<(), ShortcutError> {
+        let conflict = self
+            .bindings
+            .iter()
+            .find(|binding| binding.scope == scope && binding.shortcut.overlaps(&shortcut));
+        if let Some(binding) = conflict {
+            return Err(ShortcutError::Conflict {
+                shortcut,
+                existing: binding.shortcut.clone(),
+                scope,
+            });
+        }
+        self.bindings.push(Binding {
+            shortcut,
+            scope,
+            action,
+        });
+        Ok(())
+    }
+
+    /// Matches a key press against the shortcuts active while `focused` has focus
+    pub(crate) fn handle(
+        &mut self,
+        stroke: Stroke,
+        focused: Option<ID>,
+        registry: &mut WidgetRegistry,
+    ) -> Outcome {
+        if stroke.is_modifier() {
+            return Outcome::Ignored;
+        }
+
+        let mut scopes = vec![];
+        let mut next = focused;
+        while let Some(id) = next {
+            scopes.push(Scope::Widget(id));
+            next = registry.parent(id);
+        }
+        scopes.push(Scope::Window);
+
+        let chord = !self.pending.is_empty();
+        let mut strokes = std::mem::take(&mut self.pending);
+        strokes.push(stroke);
+        for scope in scopes {
+            let mut bindings = self.bindings.iter_mut().filter(|b| b.scope == scope);
+            if let Some(binding) = bindings.find(|b| b.shortcut.strokes.starts_with(&strokes)) {
+                if binding.shortcut.strokes.len() > strokes.len() {
+                    self.pending = strokes;
+                    return Outcome::Consumed;
+                }
+                return match &mut binding.action {
+                    Action::Message(id) => Outcome::Message(*id),
+                    Action::Callback(callback) => {
+                        callback(registry);
+                        Outcome::Consumed
+                    }
+                };
+            }
+        }
+
+        // A chord that went wrong swallows the stroke that broke it.
+        match chord {
+            true => Outcome::Consumed,
+            false => Outcome::Ignored,
+        }
+    }
+}
+
+impl fmt::Debug for Shortcuts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Shortcuts")
+            .field(
+                "bindings",
+                &self
+                    .bindings
+                    .iter()
+                    .map(|b| (b.shortcut.to_string(), b.scope))
+                    .collect::<Vec<_>>(),
+            )
+            .field("pending", &self.pending)
+            .finish()
+    }
+}
+
+/// Looks up a key by the name used in shortcuts, ignoring case
+fn key_from_name(name: &str) -> Option<KeyCode> {
+    const FUNCTION_KEYS: [KeyCode; 24] = [
+        KeyCode::F1,
+        KeyCode::F2,
+        KeyCode::F3,
+        KeyCode::F4,
+        KeyCode::F5,
+        KeyCode::F6,
+        KeyCode::F7,
+        KeyCode::F8,
+        KeyCode::F9,
+        KeyCode::F10,
+        KeyCode::F11,
+        KeyCode::F12,
+        KeyCode::F13,
+        KeyCode::F14,
+        KeyCode::F15,
+        KeyCode::F16,
+        KeyCode::F17,
+        KeyCode::F18,
+        KeyCode::F19,
+        KeyCode::F20,
+        KeyCode::F21,
+        KeyCode::F22,
+        KeyCode::F23,
+        KeyCode::F24,
+    ];
+
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return match KeyCode::from_logical(&Key::Character(c.to_string().into())) {
+            KeyCode::Unidentified => None,
+            code => Some(code),
+        };
+    }
+
+    let name = name.to_ascii_lowercase();
+    if let Some(n) = name.strip_prefix('f').and_then(|n| n.parse::<usize>().ok()) {
+        return FUNCTION_KEYS.get(n.checked_sub(1)?).copied();
+    }
+    let code = match name.as_str() {
+        "enter" | "return" => KeyCode::Enter,
+        "esc" | "escape" => KeyCode::Escape,
+        "tab" => KeyCode::Tab,
+        "space" => KeyCode::Space,
+        "backspace" => KeyCode::Backspace,
+        "delete" | "del" => KeyCode::Delete,
+        "insert" | "ins" => KeyCode::Insert,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" | "pgup" => KeyCode::PageUp,
+        "pagedown" | "pgdn" => KeyCode::PageDown,
+        "up" => KeyCode::ArrowUp,
+        "down" => KeyCode::ArrowDown,
+        "left" => KeyCode::ArrowLeft,
+        "right" => KeyCode::ArrowRight,
+        _ => return None,
+    };
+    Some(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use winit::keyboard::NamedKey;
+
+    use super::*;
+    use crate::{
+        testing::Driver,
+        widget::{Panel, TextArea, TextInput},
+        ApplicationEvent,
+    };
+
+    #[test]
+    fn parse_and_display() {
+        let shortcut: Shortcut = "ctrl+shift+p".parse().unwrap();
+        assert_eq!(
+            shortcut,
+            Shortcut::new(Modifiers::CONTROL | Modifiers::SHIFT, KeyCode::P)
+        );
+        assert_eq!(shortcut.to_string(), "Ctrl+Shift+P");
+
+        let chord: Shortcut = "Ctrl+K  Ctrl+1".parse().unwrap();
+        assert_eq!(chord.to_string(), "Ctrl+K Ctrl+1");
+        assert_eq!(
+            "F5".parse(),
+            Ok(Shortcut::new(Modifiers::empty(), KeyCode::F5))
+        );
+        assert_eq!(
+            "Alt+Up".parse(),
+            Ok(Shortcut::new(Modifiers::ALT, KeyCode::ArrowUp))
+        );
+
+        for text in ["", "Ctrl+", "Hyper+A", "F25", "Ctrl+Nothing"] {
+            assert!(text.parse::<Shortcut>().is_err(), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn conflicts() {
+        let mut shortcuts = Shortcuts::new();
+        let save: Shortcut = "Ctrl+S".parse().unwrap();
+        shortcuts
+            .bind(save.clone(), Scope::Window, ID::from("save"))
+            .unwrap();
+        assert!(matches!(
+            shortcuts.bind(save.clone(), Scope::Window, ID::from("again")),
+            Err(ShortcutError::Conflict { .. })
+        ));
+        assert!(shortcuts
+            .bind(
+                "Ctrl+S Ctrl+A".parse().unwrap(),
+                Scope::Window,
+                ID::from("all")
+            )
+            .is_err());
+
+        // Another scope may override the shortcut.
+        let editor = Scope::Widget(ID::from("editor"));
+        assert!(shortcuts
+            .bind(save.clone(), editor, ID::from("other"))
+            .is_ok());
+        assert!(shortcuts.unbind(&save, editor));
+        assert!(!shortcuts.unbind(&save, editor));
+    }
+
+    #[test]
+    fn chords_and_scopes() {
+        let mut panel = Panel::new("panel");
+        panel.push(TextInput::new("input"));
+        panel.push(TextArea::new("area"));
+        let mut driver = Driver::new(panel, 400, 300);
+
+        let comments = Rc::new(Cell::new(0));
+        let counter = comments.clone();
+        let shortcuts = driver.shortcuts();
+        shortcuts
+            .bind("Ctrl+S".parse().unwrap(), Scope::Window, ID::from("save"))
+            .unwrap();
+        shortcuts
+            .bind(
+                "Ctrl+S".parse().unwrap(),
+                Scope::Widget(ID::from("area")),
+                ID::from("save_area"),
+            )
+            .unwrap();
+        shortcuts
+            .bind_fn("Ctrl+K Ctrl+C".parse().unwrap(), Scope::Window, move |_| {
+                counter.set(counter.get() + 1)
+            })
+            .unwrap();
+
+        let ctrl = |driver: &mut Driver, c: &str| {
+            driver
+                .modifiers(Modifiers::CONTROL)
+                .key_press(Key::Character(c.into()))
+                .modifiers(Modifiers::empty());
+        };
+        let messages = |driver: &mut Driver| {
+            driver
+                .take_events()
+                .into_iter()
+                .filter_map(|event| match event {
+                    ApplicationEvent::Shortcut(id) => Some(id),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        ctrl(&mut driver, "s");
+        assert_eq!(messages(&mut driver), vec![ID::from("save")]);
+
+        driver.key_press(Key::Named(NamedKey::Tab));
+        driver.key_press(Key::Named(NamedKey::Tab));
+        ctrl(&mut driver, "s");
+        assert_eq!(messages(&mut driver), vec![ID::from("save_area")]);
+
+        ctrl(&mut driver, "k");
+        assert!(driver.shortcuts().is_pending());
+        ctrl(&mut driver, "c");
+        assert_eq!(comments.get(), 1);
+
+        // A broken chord swallows the key, so nothing is typed.
+        ctrl(&mut driver, "k");
+        driver.type_text("x").type_text("y");
+        let registry = driver.registry();
+        let area = registry.get::<TextArea>(ID::from("area")).unwrap();
+        assert_eq!(area.text(), "y");
+    }
+}
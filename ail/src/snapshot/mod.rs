@@ -0,0 +1,434 @@
+//! Golden-image snapshot testing for widgets
+//!
+//! A [`Snapshot`] renders a widget with the offscreen renderer, encodes the result to PNG
+//! and compares it against a golden file stored next to the tests.
+//!
+//! ```no_run
+//! use ail::{snapshot::Snapshot, widget::Button, Theme};
+//!
+//! Snapshot::new("button_dark")
+//!     .size(260, 60)
+//!     .theme(Theme::DARK)
+//!     .tolerance(2)
+//!     .assert(Button::new("button"));
+//! ```
+//!
+//! Missing golden files are written on the first run, unless the `CI` environment variable
+//! is set: a golden file that is missing on a CI runner fails the test instead.
+//! Set `AIL_UPDATE_SNAPSHOTS=1` to write them there too, or to overwrite existing ones after an
+//! intended visual change.
+//! When a comparison fails, `<name>.actual.png` and `<name>.diff.png` are written beside the
+//! golden file. The diff marks mismatching pixels in red over a faded copy of the golden image.
+//! They are removed again once the comparison passes.
+
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{management::RenderManager, render::Image, widget::Widget, Stage, Theme};
+
+/// Environment variable that makes [`Snapshot`] overwrite golden files instead of comparing
+pub const UPDATE_ENV: &str = "AIL_UPDATE_SNAPSHOTS";
+
+/// Environment variable set by CI services, where missing golden files are not written
+pub const CI_ENV: &str = "CI";
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    Encode(png::EncodingError),
+    Decode(png::DecodingError),
+    /// The golden image does not exist and may not be recorded, see [`CI_ENV`]
+    Missing(PathBuf),
+    /// The rendered image does not have the size of the golden image
+    SizeMismatch {
+        expected: (u32, u32),
+        actual: (u32, u32),
+    },
+    /// Some pixels differ by more than the tolerance
+    Mismatch {
+        pixels: usize,
+        actual: PathBuf,
+        diff: PathBuf,
+    },
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Io(e) => write!(f, "snapshot i/o failed: {}", e),
+            SnapshotError::Encode(e) => write!(f, "failed to encode snapshot: {}", e),
+            SnapshotError::Decode(e) => write!(f, "failed to decode golden image: {}", e),
+            SnapshotError::Missing(path) => write!(
+                f,
+                "golden image {} is missing, set {}=1 to record it",
+                path.display(),
+                UPDATE_ENV
+            ),
+            SnapshotError::SizeMismatch { expected, actual } => write!(
+                f,
+                "snapshot is {}x{} but the golden image is {}x{}",
+                actual.0, actual.1, expected.0, expected.1
+            ),
+            SnapshotError::Mismatch {
+                pixels,
+                actual,
+                diff,
+            } => write!(
+                f,
+                "{} pixels differ from the golden image (actual: {}, diff: {})",
+                pixels,
+                actual.display(),
+                diff.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<io::Error> for SnapshotError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<png::EncodingError> for SnapshotError {
+    fn from(value: png::EncodingError) -> Self {
+        Self::Encode(value)
+    }
+}
+
+impl From<png::DecodingError> for SnapshotError {
+    fn from(value: png::DecodingError) -> Self {
+        Self::Decode(value)
+    }
+}
+
+/// Describes how a widget is rendered and where its golden image lives
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    name: String,
+    width: u32,
+    height: u32,
+    theme: Theme,
+    tolerance: u8,
+    directory: PathBuf,
+}
+
+impl Snapshot {
+    /// Creates a snapshot named `name`
+    ///
+    /// The golden image is `tests/snapshots/<name>.png` inside the crate being tested.
+    pub fn new<T>(name: T) -> Self
+    where
+        T: Into<String>,
+    {
+        let root = std::env::var_os("CARGO_MANIFEST_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_default();
+        Self {
+            name: name.into(),
+            width: 800,
+            height: 600,
+            theme: Theme::LIGHT,
+            tolerance: 0,
+            directory: root.join("tests").join("snapshots"),
+        }
+    }
+
+    /// Sets the size of the rendered image
+    pub fn size(mut self, width: u32, height: u32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Sets the largest difference allowed per color channel of a pixel
+    pub fn tolerance(mut self, tolerance: u8) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Sets the directory that holds the golden images
+    pub fn directory<P>(mut self, directory: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.directory = directory.into();
+        self
+    }
+
+    /// Path of the golden image
+    pub fn path(&self) -> PathBuf {
+        self.file("png")
+    }
+
+    /// Renders `widget` with the configured size and theme
+    ///
+    /// The widget is laid out to fill the whole image, just like the root widget of a window.
+    pub fn render<W>(&self, mut widget: W) -> Image
+    where
+        W: Widget + 'static,
+    {
+        let mut manager = RenderManager::offscreen(self.width, self.height);
+        widget.theme(self.theme);
+        let id = widget.id();
+        manager.register(widget);
+        let mut stage = Stage::new(manager, vec![id], self.theme, self.width, self.height);
+        stage.render();
+        stage.render_manager.image().unwrap().clone()
+    }
+
+    /// Renders `widget` and compares it against the golden image
+    pub fn check<W>(&self, widget: W) -> Result<(), SnapshotError>
+    where
+        W: Widget + 'static,
+    {
+        let update = std::env::var_os(UPDATE_ENV).is_some();
+        let record = update || std::env::var_os(CI_ENV).is_none();
+        self.verify(&self.render(widget), update, record)
+    }
+
+    /// Compares `actual` against the golden image, writing it instead if `update` is set
+    /// or if the golden image is missing and `record` is set
+    fn verify(&self, actual: &Image, update: bool, record: bool) -> Result<(), SnapshotError> {
+        let path = self.path();
+        let actual_path = self.file("actual.png");
+        let diff_path = self.file("diff.png");
+
+        if update || (record && !path.exists()) {
+            fs::create_dir_all(&self.directory)?;
+            fs::write(&path, encode(actual)?)?;
+            return remove_stale(&[&actual_path, &diff_path]);
+        }
+        if !path.exists() {
+            return Err(SnapshotError::Missing(path));
+        }
+
+        let expected = decode(&fs::read(&path)?)?;
+        if (expected.width(), expected.height()) != (actual.width(), actual.height()) {
+            return Err(SnapshotError::SizeMismatch {
+                expected: (expected.width(), expected.height()),
+                actual: (actual.width(), actual.height()),
+            });
+        }
+
+        let comparison = compare(&expected, actual, self.tolerance);
+        if comparison.mismatched == 0 {
+            return remove_stale(&[&actual_path, &diff_path]);
+        }
+
+        fs::write(&actual_path, encode(actual)?)?;
+        fs::write(&diff_path, encode(&comparison.diff)?)?;
+        Err(SnapshotError::Mismatch {
+            pixels: comparison.mismatched,
+            actual: actual_path,
+            diff: diff_path,
+        })
+    }
+
+    /// Same as [`Snapshot::check`] but panics on failure
+    pub fn assert<W>(&self, widget: W)
+    where
+        W: Widget + 'static,
+    {
+        if let Err(e) = self.check(widget) {
+            panic!("snapshot `{}` failed: {}", self.name, e);
+        }
+    }
+
+    fn file(&self, extension: &str) -> PathBuf {
+        self.directory.join(format!("{}.{}", self.name, extension))
+    }
+}
+
+/// Removes the images left behind by an earlier failed comparison
+fn remove_stale(paths: &[&Path]) -> Result<(), SnapshotError> {
+    for path in paths {
+        match fs::remove_file(path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Result of comparing two images of the same size
+#[derive(Clone, Debug)]
+pub struct Comparison {
+    /// Number of pixels whose difference exceeds the tolerance
+    pub mismatched: usize,
+    /// Mismatching pixels in red over a faded copy of the expected image
+    pub diff: Image,
+}
+
+/// Compares two images pixel by pixel
+///
+/// A pixel mismatches when any of its channels differs by more than `tolerance`.
+///
+/// # Panics
+/// Panics if the images do not have the same size.
+pub fn compare(expected: &Image, actual: &Image, tolerance: u8) -> Comparison {
+    assert_eq!(
+        (expected.width(), expected.height()),
+        (actual.width(), actual.height()),
+        "images must have the same size"
+    );
+
+    let mut diff = Image::new(expected.width(), expected.height());
+    let mut mismatched = 0;
+    for y in 0..expected.height() {
+        for x in 0..expected.width() {
+            let e = expected.pixel(x, y);
+            let a = actual.pixel(x, y);
+            let differs = e
+                .iter()
+                .zip(a.iter())
+                .any(|(e, a)| e.abs_diff(*a) > tolerance);
+            if differs {
+                mismatched += 1;
+                diff.set_pixel(x, y, [255, 0, 0, 255]);
+            } else {
+                let gray = ((e[0] as u32 + e[1] as u32 + e[2] as u32) / 3) as u8;
+                let faded = 255 - (255 - gray) / 4;
+                diff.set_pixel(x, y, [faded, faded, faded, 255]);
+            }
+        }
+    }
+
+    Comparison { mismatched, diff }
+}
+
+/// Encodes an image as an 8-bit RGBA PNG
+pub fn encode(image: &Image) -> Result<Vec<u8>, png::EncodingError> {
+    let mut buffer = vec![];
+    {
+        let mut encoder = png::Encoder::new(&mut buffer, image.width(), image.height());
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(image.as_bytes())?;
+    }
+    Ok(buffer)
+}
+
+/// Decodes a PNG into an RGBA image
+pub fn decode(bytes: &[u8]) -> Result<Image, png::DecodingError> {
+    let mut decoder = png::Decoder::new(bytes);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info()?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer)?;
+    buffer.truncate(info.buffer_size());
+
+    let data = match info.color_type {
+        png::ColorType::Rgba => buffer,
+        png::ColorType::Rgb => buffer
+            .chunks_exact(3)
+            .flat_map(|p| [p[0], p[1], p[2], 255])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => buffer
+            .chunks_exact(2)
+            .flat_map(|p| [p[0], p[0], p[0], p[1]])
+            .collect(),
+        png::ColorType::Grayscale => buffer.iter().flat_map(|g| [*g, *g, *g, 255]).collect(),
+        png::ColorType::Indexed => unreachable!("palettes are expanded while decoding"),
+    };
+
+    Ok(Image::from_rgba(info.width, info.height, data).unwrap())
+}
+
+/// Reads and decodes a PNG file
+pub fn load<P>(path: P) -> Result<Image, SnapshotError>
+where
+    P: AsRef<Path>,
+{
+    Ok(decode(&fs::read(path)?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::widget::Button;
+
+    /// Guards the shadow border of buttons, which is easily drawn one pixel off
+    #[test]
+    fn button_goldens() {
+        for (name, theme) in [
+            ("light", Theme::LIGHT),
+            ("dark", Theme::DARK),
+            ("light_high_contrast", Theme::LIGHT_HIGH_CONTRAST),
+            ("dark_high_contrast", Theme::DARK_HIGH_CONTRAST),
+        ] {
+            let mut button = Button::new("button");
+            button.set_text("Save");
+            Snapshot::new(format!("button_{}", name))
+                .size(120, 40)
+                .theme(theme)
+                .assert(button);
+        }
+    }
+
+    #[test]
+    fn png_round_trip() {
+        let image = Snapshot::new("round_trip")
+            .size(120, 60)
+            .render(Button::new("button"));
+        let decoded = decode(&encode(&image).unwrap()).unwrap();
+        assert_eq!(image, decoded);
+    }
+
+    #[test]
+    fn tolerance() {
+        let mut expected = Image::new(2, 1);
+        expected.set_pixel(0, 0, [100, 100, 100, 255]);
+        expected.set_pixel(1, 0, [100, 100, 100, 255]);
+        let mut actual = expected.clone();
+        actual.set_pixel(1, 0, [103, 100, 100, 255]);
+
+        assert_eq!(compare(&expected, &actual, 3).mismatched, 0);
+        let comparison = compare(&expected, &actual, 2);
+        assert_eq!(comparison.mismatched, 1);
+        assert_eq!(comparison.diff.pixel(1, 0), [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn golden_file() {
+        let directory = std::env::temp_dir().join(format!("ail-snapshot-{}", std::process::id()));
+        let snapshot = Snapshot::new("button").size(260, 60).directory(&directory);
+        let light = snapshot.render(Button::new("button"));
+        let dark = snapshot
+            .clone()
+            .theme(Theme::DARK)
+            .render(Button::new("button"));
+
+        // A missing golden image fails unless it may be recorded, as on a CI runner.
+        assert!(matches!(
+            snapshot.verify(&light, false, false),
+            Err(SnapshotError::Missing(_))
+        ));
+        // The first run records the golden image, the second one matches it.
+        snapshot.verify(&light, false, true).unwrap();
+        snapshot.verify(&light, false, false).unwrap();
+
+        let diff = match snapshot.verify(&dark, false, false) {
+            Err(SnapshotError::Mismatch { pixels, diff, .. }) => {
+                assert!(pixels > 0);
+                assert!(diff.exists());
+                diff
+            }
+            other => panic!("expected a mismatch, got {:?}", other),
+        };
+        snapshot.verify(&light, false, false).unwrap();
+        assert!(!diff.exists());
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
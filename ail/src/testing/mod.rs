@@ -0,0 +1,612 @@
+//! Headless driver for testing widgets and application callbacks
+//!
+//! A [`Driver`] feeds synthetic input through the same dispatch logic as [`Application::run`]
+//! and renders with the offscreen renderer, so no window or display is needed.
+//!
+//! ```
+//! use ail::{testing::Driver, widget::Button, ApplicationEvent, MouseButton, WidgetEvent};
+//! use aom::ID;
+//!
+//! let mut driver = Driver::new(Button::new("my_button"), 300, 80);
+//! driver.pointer_move(20, 20);
+//! driver.press(MouseButton::Left);
+//! driver.release(MouseButton::Left);
+//!
+//! let clicks = driver
+//!     .events()
+//!     .iter()
+//!     .filter(|e| matches!(e, ApplicationEvent::OnEvent(WidgetEvent::OnClick, id) if *id == ID::from("my_button")))
+//!     .count();
+//! assert_eq!(clicks, 1);
+//! ```
+//!
+//! [`Application::run`]: crate::Application::run
+
+use std::sync::MutexGuard;
+
+use aom::ID;
+
+use winit::keyboard::NamedKey;
+
+use crate::{
+    binding::Bindings,
+    event::{Ime, Input},
+    management::{RenderManager, WidgetRegistry},
+    render::{DisplayList, Image},
+    shortcut::Shortcuts,
+    widget::Widget,
+    ApplicationEvent, Key, KeyCode, Modifiers, MouseButton, Rect, Stage, Theme,
+};
+
+type Callback = Box<dyn FnMut(ApplicationEvent, &mut WidgetRegistry)>;
+
+/// Steps a widget through input and rendering without a window
+pub struct Driver {
+    stage: Stage,
+    callback: Option<Callback>,
+    events: Vec<ApplicationEvent>,
+}
+
+impl Driver {
+    /// Creates a driver for `widget` rendered at the given size with [`Theme::LIGHT`]
+    ///
+    /// Like the root widget of a window, `widget` is laid out to fill the whole size.
+    pub fn new<W>(widget: W, width: u32, height: u32) -> Self
+    where
+        W: Widget + 'static,
+    {
+        Self::with_theme(widget, Theme::LIGHT, width, height)
+    }
+
+    pub fn with_theme<W>(mut widget: W, theme: Theme, width: u32, height: u32) -> Self
+    where
+        W: Widget + 'static,
+    {
+        let id = widget.id();
+        widget.theme(theme);
+        let mut render_manager = RenderManager::offscreen(width, height);
+        render_manager.register(widget);
+        Self {
+            stage: Stage::new(render_manager, vec![id], theme, width, height),
+            callback: None,
+            events: vec![],
+        }
+    }
+
+    /// Sets the callback that receives events, as passed to [`Application::run`]
+    ///
+    /// Events are recorded in [`Driver::events`] either way.
+    ///
+    /// [`Application::run`]: crate::Application::run
+    pub fn callback<F>(&mut self, callback: F) -> &mut Self
+    where
+        F: FnMut(ApplicationEvent, &mut WidgetRegistry) + 'static,
+    {
+        self.callback = Some(Box::new(callback));
+        self
+    }
+
+    /// Delivers an arbitrary input event
+    pub fn input(&mut self, input: Input) -> &mut Self {
+        let events = &mut self.events;
+        let callback = &mut self.callback;
+        self.stage.input(input, &mut |event, registry| {
+            events.push(event);
+            if let Some(callback) = callback {
+                callback(event, registry);
+            }
+        });
+        self.stage.apply_bindings();
+        self.report_rejected();
+        self
+    }
+
+    /// Reports the widgets dropped while laying out, as a running application does
+    fn report_rejected(&mut self) {
+        let events = &mut self.events;
+        let callback = &mut self.callback;
+        self.stage.report_rejected(&mut |event, registry| {
+            events.push(event);
+            if let Some(callback) = callback {
+                callback(event, registry);
+            }
+        });
+    }
+
+    pub fn pointer_move(&mut self, x: i32, y: i32) -> &mut Self {
+        self.input(Input::CursorMoved { x, y })
+    }
+
+    pub fn pointer_leave(&mut self) -> &mut Self {
+        self.input(Input::CursorLeft)
+    }
+
+    pub fn press(&mut self, button: MouseButton) -> &mut Self {
+        self.input(Input::MouseInput {
+            button,
+            pressed: true,
+        })
+    }
+
+    pub fn release(&mut self, button: MouseButton) -> &mut Self {
+        self.input(Input::MouseInput {
+            button,
+            pressed: false,
+        })
+    }
+
+    /// Moves the pointer to the coordinate and clicks the left button
+    pub fn click(&mut self, x: i32, y: i32) -> &mut Self {
+        self.pointer_move(x, y)
+            .press(MouseButton::Left)
+            .release(MouseButton::Left)
+    }
+
+    /// Turns the mouse wheel by the given number of lines
+    pub fn scroll(&mut self, dx: f32, dy: f32) -> &mut Self {
+        self.input(Input::Wheel { dx, dy })
+    }
+
+    /// Presses a key, delivering the text it produces like a keyboard would
+    pub fn key_press(&mut self, key: Key) -> &mut Self {
+        self.key(key, true, false)
+    }
+
+    /// Repeats a key that is held down
+    pub fn key_repeat(&mut self, key: Key) -> &mut Self {
+        self.key(key, true, true)
+    }
+
+    pub fn key_release(&mut self, key: Key) -> &mut Self {
+        self.key(key, false, false)
+    }
+
+    fn key(&mut self, key: Key, pressed: bool, repeat: bool) -> &mut Self {
+        let text = key.to_text().filter(|_| pressed).map(str::to_string);
+        self.input(Input::Key {
+            code: KeyCode::from_logical(&key),
+            key,
+            pressed,
+            repeat,
+            text,
+        })
+    }
+
+    /// Gives keyboard focus to the widget `id`, as [`Application::set_focus`] does
+    ///
+    /// Nothing happens if there is no such widget or it cannot take focus.
+    ///
+    /// [`Application::set_focus`]: crate::Application::set_focus
+    pub fn focus(&mut self, id: ID) -> &mut Self {
+        let events = &mut self.events;
+        let callback = &mut self.callback;
+        self.stage.focus(id, &mut |event, registry| {
+            events.push(event);
+            if let Some(callback) = callback {
+                callback(event, registry);
+            }
+        });
+        self
+    }
+
+    /// Shows `dialog` over the widget, as [`Windows::show_dialog`] does
+    ///
+    /// [`Windows::show_dialog`]: crate::Windows::show_dialog
+    pub fn show_dialog<W>(&mut self, dialog: W) -> &mut Self
+    where
+        W: Widget + 'static,
+    {
+        let events = &mut self.events;
+        let callback = &mut self.callback;
+        self.stage
+            .show_dialog(Box::new(dialog), &mut |event, registry| {
+                events.push(event);
+                if let Some(callback) = callback {
+                    callback(event, registry);
+                }
+            });
+        self
+    }
+
+    /// Shows text being composed by an input method
+    pub fn ime_preedit(&mut self, text: &str, cursor: Option<(usize, usize)>) -> &mut Self {
+        self.input(Input::Ime(Ime::Preedit(text.to_string(), cursor)))
+    }
+
+    /// Commits text composed by an input method
+    pub fn ime_commit(&mut self, text: &str) -> &mut Self {
+        self.input(Input::Ime(Ime::Commit(text.to_string())))
+    }
+
+    /// Where the focused widget takes text, which is where the input method's window goes
+    pub fn text_input_area(&self) -> Option<Rect> {
+        self.stage.text_input_area()
+    }
+
+    /// Changes the held modifier keys
+    pub fn modifiers(&mut self, modifiers: Modifiers) -> &mut Self {
+        self.input(Input::Modifiers(modifiers))
+    }
+
+    /// Presses and releases a key for every character of `text`
+    pub fn type_text(&mut self, text: &str) -> &mut Self {
+        for c in text.chars() {
+            let key = match c {
+                ' ' => Key::Named(NamedKey::Space),
+                c => Key::Character(c.to_string().into()),
+            };
+            self.key_press(key.clone()).key_release(key);
+        }
+        self
+    }
+
+    /// Resizes the render target, as if the window had been resized
+    pub fn resize(&mut self, width: u32, height: u32) -> &mut Self {
+        self.stage.resize(width, height);
+        self
+    }
+
+    /// Renders a frame if any widget was invalidated since the last step
+    ///
+    /// Signals changed since the last input are passed to their bindings first.
+    /// Returns whether a frame was rendered.
+    pub fn step(&mut self) -> bool {
+        self.stage.apply_bindings();
+        if !self.stage.needs_redraw() {
+            return false;
+        }
+        self.stage.render();
+        self.report_rejected();
+        true
+    }
+
+    /// The last rendered frame
+    pub fn image(&self) -> &Image {
+        self.stage.render_manager.image().unwrap()
+    }
+
+    /// What the last frame shows, as a display list
+    pub fn frame(&self) -> DisplayList {
+        self.stage.render_manager.frame()
+    }
+
+    /// Events reported so far
+    pub fn events(&self) -> &[ApplicationEvent] {
+        &self.events
+    }
+
+    /// Returns the events reported so far and forgets them
+    pub fn take_events(&mut self) -> Vec<ApplicationEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// The keyboard shortcuts, as [`Application::shortcuts`]
+    ///
+    /// [`Application::shortcuts`]: crate::Application::shortcuts
+    pub fn shortcuts(&mut self) -> &mut Shortcuts {
+        &mut self.stage.shortcuts
+    }
+
+    /// The bindings of widgets to signals, as [`Application::bindings`]
+    ///
+    /// [`Application::bindings`]: crate::Application::bindings
+    pub fn bindings(&mut self) -> &mut Bindings {
+        &mut self.stage.bindings
+    }
+
+    pub fn registry(&self) -> MutexGuard<'_, WidgetRegistry> {
+        self.stage.render_manager.registry.lock().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use super::*;
+    use crate::{
+        event::KeyEvent,
+        layout::{Flex, Padding},
+        widget::{Button, Drawable, EventListener, Layout, Panel, TextArea, TextInput},
+        WidgetEvent,
+    };
+
+    /// Records the keys it receives
+    #[derive(Debug, Default)]
+    struct Recorder {
+        area: Rect,
+        keys: Vec<String>,
+    }
+
+    impl Widget for Recorder {}
+    impl Drawable for Recorder {}
+
+    impl Layout for Recorder {
+        fn area(&self) -> Vec<Rect> {
+            vec![self.area]
+        }
+
+        fn arrange(&mut self, area: Rect, _metrics: &dyn crate::layout::TextMetrics) {
+            self.area = area;
+        }
+    }
+
+    impl EventListener for Recorder {
+        fn focusable(&self) -> bool {
+            true
+        }
+
+        fn on_key_down(&mut self, event: &KeyEvent) {
+            let repeat = if event.repeat { " repeat" } else { "" };
+            self.keys.push(format!("down {:?}{}", event.key, repeat));
+        }
+
+        fn on_key_up(&mut self, event: &KeyEvent) {
+            self.keys.push(format!("up {:?}", event.key));
+        }
+
+        fn on_char(&mut self, text: &str) {
+            self.keys.push(format!("char {}", text));
+        }
+    }
+
+    impl aom::Object for Recorder {
+        fn id(&self) -> ID {
+            ID::from("recorder")
+        }
+    }
+
+    #[test]
+    fn click_is_reported_once() {
+        let clicks = Rc::new(Cell::new(0));
+        let counter = clicks.clone();
+
+        let mut panel = Panel::new("panel");
+        panel.set_layout(Flex::column().padding(Padding::all(10)));
+        panel.push(Button::new("my_button"));
+
+        let mut driver = Driver::new(panel, 300, 80);
+        driver.callback(move |event, _| {
+            if let ApplicationEvent::OnEvent(WidgetEvent::OnClick, id) = event {
+                assert_eq!(id, ID::from("my_button"));
+                counter.set(counter.get() + 1);
+            }
+        });
+
+        driver.pointer_move(20, 20);
+        driver.pointer_move(21, 20);
+        driver.press(MouseButton::Left);
+        driver.release(MouseButton::Left);
+        assert_eq!(clicks.get(), 1);
+
+        // Releasing outside of the button cancels the click.
+        driver.press(MouseButton::Left);
+        driver.pointer_move(290, 70);
+        driver.release(MouseButton::Left);
+        assert_eq!(clicks.get(), 1);
+    }
+
+    #[test]
+    fn right_and_bottom_edges_are_outside() {
+        let mut panel = Panel::new("panel");
+        panel.set_layout(Flex::row().padding(Padding::all(10)));
+        panel.push(Button::new("button"));
+        let mut driver = Driver::new(panel, 300, 80);
+        let area = driver
+            .registry()
+            .get::<Button>(ID::from("button"))
+            .unwrap()
+            .area()[0];
+
+        let clicks = Rc::new(Cell::new(0));
+        let counter = clicks.clone();
+        driver.callback(move |event, _| {
+            if let ApplicationEvent::OnEvent(WidgetEvent::OnClick, _) = event {
+                counter.set(counter.get() + 1);
+            }
+        });
+        let (right, bottom) = (
+            (area.x() + area.width()) as i32,
+            (area.y() + area.height()) as i32,
+        );
+        for (x, y) in [
+            (right, bottom - 1),
+            (right - 1, bottom),
+            (right - 1, bottom - 1),
+        ] {
+            driver
+                .pointer_move(x, y)
+                .press(MouseButton::Left)
+                .release(MouseButton::Left);
+        }
+        assert_eq!(clicks.get(), 1);
+    }
+
+    #[test]
+    fn hover_changes_the_rendered_frame() {
+        let mut driver = Driver::new(Button::new("button"), 300, 80);
+        assert!(driver.step());
+        let normal = driver.image().clone();
+        assert!(!driver.step());
+
+        driver.pointer_move(20, 20);
+        assert!(driver.step());
+        assert_ne!(&normal, driver.image());
+
+        driver.pointer_leave();
+        assert!(driver.step());
+        assert_eq!(&normal, driver.image());
+    }
+
+    #[test]
+    fn only_invalidated_widgets_cause_a_frame() {
+        let mut panel = Panel::new("panel");
+        panel.set_layout(Flex::column().padding(Padding::all(10)));
+        panel.push(Button::new("first"));
+        panel.push(Button::new("second"));
+        let mut driver = Driver::new(panel, 300, 120);
+        driver.step();
+
+        // Moving within the hovered widget changes nothing.
+        driver.pointer_move(20, 20);
+        assert!(driver.step());
+        driver.pointer_move(25, 22);
+        assert!(!driver.step());
+
+        // Changes made by callbacks in between are drawn together.
+        driver.callback(|event, registry| {
+            if let ApplicationEvent::OnEvent(WidgetEvent::OnClick, _) = event {
+                for id in ["first", "second"] {
+                    registry
+                        .get_mut::<Button>(ID::from(id))
+                        .unwrap()
+                        .set_text(id);
+                }
+            }
+        });
+        driver.press(MouseButton::Left).release(MouseButton::Left);
+        assert!(driver.registry().is_dirty());
+        assert!(driver.step());
+        assert!(!driver.registry().is_dirty());
+        assert!(!driver.step());
+    }
+
+    #[test]
+    fn only_changes_to_the_layout_cause_one() {
+        let mut panel = Panel::new("panel");
+        panel.set_layout(Flex::column().padding(Padding::all(10)));
+        panel.push(Button::new("button"));
+        panel.push(TextInput::new("input"));
+        let mut driver = Driver::new(panel, 300, 120);
+        driver.step();
+
+        // Hover, presses and focus only change the looks of the widgets.
+        driver.pointer_move(20, 20);
+        driver.press(MouseButton::Left).release(MouseButton::Left);
+        driver.focus(ID::from("input"));
+        assert!(driver.registry().is_dirty());
+        assert!(!driver.registry().needs_layout());
+        driver.step();
+
+        // Text is placed anew when it changes.
+        driver.type_text("a");
+        assert!(driver.registry().needs_layout());
+        driver.step();
+        assert!(!driver.registry().needs_layout());
+    }
+
+    #[test]
+    fn resize() {
+        let mut driver = Driver::new(Button::new("button"), 300, 80);
+        driver.resize(400, 100);
+        assert!(driver.step());
+        assert_eq!(
+            (driver.image().width(), driver.image().height()),
+            (400, 100)
+        );
+    }
+
+    #[test]
+    fn keys_reach_the_focused_widget() {
+        let mut driver = Driver::new(Recorder::default(), 100, 100);
+        let a = Key::Character("a".into());
+        driver.key_press(a.clone());
+        driver.click(10, 10);
+        driver
+            .key_press(a.clone())
+            .key_repeat(a.clone())
+            .key_release(a.clone());
+        driver.key_press(Key::Named(NamedKey::Enter));
+        driver.modifiers(Modifiers::CONTROL).key_press(a);
+
+        let registry = driver.registry();
+        let recorder = registry.get::<Recorder>(ID::from("recorder")).unwrap();
+        assert_eq!(
+            recorder.keys,
+            vec![
+                "down Character(\"a\")",
+                "char a",
+                "down Character(\"a\") repeat",
+                "char a",
+                "up Character(\"a\")",
+                "down Named(Enter)",
+                "down Character(\"a\")",
+            ]
+        );
+    }
+
+    #[test]
+    fn focus_requests_for_unfocusable_widgets_are_ignored() {
+        let mut panel = Panel::new("panel");
+        panel.push(TextInput::new("input"));
+        panel.push(Button::new("button"));
+        let mut driver = Driver::new(panel, 400, 300);
+        driver
+            .registry()
+            .get_mut::<Button>(ID::from("button"))
+            .unwrap()
+            .set_enabled(false);
+
+        driver.focus(ID::from("input"));
+        driver.take_events();
+        driver
+            .focus(ID::from("missing"))
+            .focus(ID::from("panel"))
+            .focus(ID::from("button"));
+        assert!(driver.take_events().is_empty());
+        assert_eq!(driver.stage.dispatcher.focused(), Some(ID::from("input")));
+    }
+
+    #[test]
+    fn tab_moves_focus() {
+        let mut first = Button::new("first");
+        first.set_tab_index(Some(0));
+        let mut panel = Panel::new("panel");
+        panel.push(TextInput::new("input"));
+        panel.push(TextArea::new("area"));
+        panel.push(first);
+
+        let mut driver = Driver::new(panel, 400, 300);
+        let tab = Key::Named(NamedKey::Tab);
+        let focused = |driver: &Driver| driver.stage.dispatcher.focused();
+        driver.step();
+        let unfocused = driver.image().clone();
+
+        driver.key_press(tab.clone());
+        assert_eq!(focused(&driver), Some(ID::from("first")));
+        assert!(driver.step());
+        assert_ne!(&unfocused, driver.image());
+
+        driver.key_press(Key::Named(NamedKey::Enter));
+        assert!(driver.take_events().iter().any(|event| matches!(
+            event,
+            ApplicationEvent::OnEvent(WidgetEvent::OnClick, id) if *id == ID::from("first")
+        )));
+
+        driver.key_press(tab.clone()).key_press(tab.clone());
+        assert_eq!(focused(&driver), Some(ID::from("area")));
+
+        // The editor keeps Tab for itself, and Control+Tab leaves it.
+        driver.key_press(tab.clone());
+        assert_eq!(focused(&driver), Some(ID::from("area")));
+        driver.modifiers(Modifiers::CONTROL).key_press(tab.clone());
+        assert_eq!(focused(&driver), Some(ID::from("first")));
+
+        driver.take_events();
+        driver.modifiers(Modifiers::SHIFT).key_press(tab);
+        assert_eq!(focused(&driver), Some(ID::from("area")));
+        let events = driver.take_events();
+        assert!(matches!(
+            events[..],
+            [
+                ApplicationEvent::OnEvent(WidgetEvent::OnBlur, _),
+                ApplicationEvent::OnEvent(WidgetEvent::OnFocus, _),
+            ]
+        ));
+
+        // Focus given by clicking is not outlined.
+        driver.click(10, 10).step();
+        assert_eq!(focused(&driver), Some(ID::from("input")));
+        assert!(!driver.stage.dispatcher.focus_visible());
+    }
+}
@@ -1,18 +1,34 @@
 use std::fmt::Debug;
 
-use acure::{Color, Command};
 use aom::{Object, ID};
 
-use crate::{Rect, Theme};
+use winit::keyboard::NamedKey;
+
+use crate::{
+    event::KeyEvent,
+    layout::{Size, TextMetrics},
+    render::{DisplayList, Item},
+    ColorPair, Key, Rect, Theme,
+};
 
 use super::{Drawable, EventListener, Layout, Widget, WidgetState};
 
+/// Space between the text and the left and right edges
+const PADDING_X: u32 = 16;
+/// Space between the text and the top and bottom edges
+const PADDING_Y: u32 = 10;
+
 #[derive(Debug)]
 pub struct Button {
     id: ID,
     text: String,
     theme: Theme,
     state: WidgetState,
+    area: Rect,
+    tab_index: Option<u32>,
+    enabled: bool,
+    /// Colors replacing those of the theme while the button is not hovered or pressed
+    colors: Option<ColorPair>,
 }
 
 impl Button {
@@ -22,22 +38,70 @@ impl Button {
             text: String::from("Button"),
             theme: Theme::LIGHT,
             state: WidgetState::Unfocus,
+            area: Rect::from_coordinate(0, 0, 240, 40),
+            tab_index: None,
+            enabled: true,
+            colors: None,
         }
     }
 
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
     pub fn set_text<T>(&mut self, text: T)
     where
         T: Into<String>,
     {
         self.text = text.into();
     }
+
+    /// Sets the position of the button in the Tab order
+    pub fn set_tab_index(&mut self, tab_index: Option<u32>) {
+        self.tab_index = tab_index;
+    }
+
+    /// Sets whether the button can be clicked and focused, disabled buttons are greyed out
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.state = WidgetState::Unfocus;
+        }
+    }
+
+    /// Replaces the normal colors of the theme, or restores them with `None`
+    pub fn set_colors(&mut self, colors: Option<ColorPair>) {
+        self.colors = colors;
+    }
 }
 
-impl Widget for Button {}
+impl Widget for Button {
+    fn reconcile(&mut self, new: &mut dyn Widget) -> bool {
+        let Some(new) = new.as_any_mut().downcast_mut::<Self>() else {
+            return false;
+        };
+        self.text = std::mem::take(&mut new.text);
+        self.tab_index = new.tab_index;
+        self.colors = new.colors;
+        self.set_enabled(new.enabled);
+        true
+    }
+}
 
 impl Layout for Button {
     fn area(&self) -> Vec<Rect> {
-        vec![Rect::from_coordinate(10, 10, 240, 40)]
+        vec![self.area]
+    }
+
+    fn measure(&self, metrics: &dyn TextMetrics) -> Size {
+        Size::new(
+            metrics.text_width(&self.text) + PADDING_X * 2,
+            metrics.line_height() + PADDING_Y * 2,
+        )
+    }
+
+    fn arrange(&mut self, area: Rect, _metrics: &dyn TextMetrics) {
+        self.area = area;
     }
 }
 
@@ -46,8 +110,10 @@ impl Drawable for Button {
         self.theme = theme;
     }
 
-    fn render(&mut self) -> Vec<acure::Command> {
+    fn render(&mut self) -> DisplayList {
+        let normal = self.colors.unwrap_or(self.theme.normal);
         let (bgr, color, shadow) = match self.state {
+            _ if !self.enabled => (normal.bgr, normal.shadow, normal.shadow),
             WidgetState::Hover => (
                 self.theme.hover.bgr,
                 self.theme.hover.color,
@@ -58,18 +124,33 @@ impl Drawable for Button {
                 self.theme.click.color,
                 self.theme.click.shadow,
             ),
-            WidgetState::Unfocus => (
-                self.theme.normal.bgr,
-                self.theme.normal.color,
-                self.theme.normal.shadow,
-            ),
+            WidgetState::Unfocus => (normal.bgr, normal.color, normal.shadow),
         };
 
-        vec![
-            Command::FillRectangle(10, 10, 240, 40, 4.2, shadow.into()),
-            Command::FillRectangle(10 + 1, 10 + 1, 240 - 2, 40 - 2, 4.2, bgr.into()),
-            Command::WriteString(10, 10, 240, 40, color.into(), self.text.clone()),
-        ]
+        let (x, y, width, height) = (
+            self.area.x(),
+            self.area.y(),
+            self.area.width(),
+            self.area.height(),
+        );
+        DisplayList::from(vec![
+            Item::RoundedRectangle(Rect::from_coordinate(x, y, width, height), 4.2, shadow),
+            Item::RoundedRectangle(
+                Rect::from_coordinate(
+                    x + 1,
+                    y + 1,
+                    width.saturating_sub(2),
+                    height.saturating_sub(2),
+                ),
+                4.2,
+                bgr,
+            ),
+            Item::Text(
+                Rect::from_coordinate(x, y, width, height),
+                color,
+                self.text.clone(),
+            ),
+        ])
     }
 }
 
@@ -84,6 +165,27 @@ impl EventListener for Button {
         self.state = WidgetState::Unfocus;
     }
     fn on_update(&mut self) {}
+
+    fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn focusable(&self) -> bool {
+        self.enabled
+    }
+
+    fn tab_index(&self) -> Option<u32> {
+        self.tab_index
+    }
+
+    /// Enter and Space click a focused button
+    fn activates(&self, event: &KeyEvent) -> bool {
+        let key = matches!(
+            event.key,
+            Key::Named(NamedKey::Enter) | Key::Named(NamedKey::Space)
+        );
+        key && !event.repeat && event.modifiers.is_empty() && self.enabled
+    }
 }
 
 impl Object for Button {
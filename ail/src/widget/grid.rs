@@ -0,0 +1,174 @@
+use aom::{Object, ID};
+
+use crate::{
+    layout::{Grid, GridItem, Size},
+    Rect, Theme,
+};
+
+use super::{Container, Drawable, EventListener, Layout, Widget};
+
+/// A container that places its children in the cells of a [`Grid`]
+///
+/// Every child is pushed together with the cell it occupies.
+/// Children are drawn in the order they were pushed.
+/// Like with [`super::Panel`], children pushed after the panel was registered are registered
+/// when the window is laid out next.
+#[derive(Debug)]
+pub struct GridPanel {
+    id: ID,
+    children: Vec<ID>,
+    items: Vec<GridItem>,
+    pending: Vec<Box<dyn Widget>>,
+    /// Theme given to the panel, passed on to children pushed later
+    theme: Option<Theme>,
+    layout: Grid,
+}
+
+impl GridPanel {
+    pub fn new(id: &'static str) -> Self {
+        Self {
+            id: ID::from(id),
+            children: vec![],
+            items: vec![],
+            pending: vec![],
+            theme: None,
+            layout: Grid::default(),
+        }
+    }
+
+    pub fn set_layout(&mut self, layout: Grid) {
+        self.layout = layout;
+    }
+
+    /// Adds a child in the given cell
+    pub fn push<W>(&mut self, widget: W, item: GridItem)
+    where
+        W: Widget + 'static,
+    {
+        self.children.push(widget.id());
+        self.items.push(item);
+        let mut widget: Box<dyn Widget> = Box::new(widget);
+        if let Some(theme) = self.theme {
+            widget.theme(theme);
+        }
+        self.pending.push(widget);
+    }
+
+    fn grid_children(&self, sizes: &[Size]) -> Vec<(Size, GridItem)> {
+        sizes
+            .iter()
+            .copied()
+            .zip(self.items.iter().copied())
+            .collect()
+    }
+}
+
+impl Widget for GridPanel {
+    fn as_container(&self) -> Option<&dyn Container> {
+        Some(self)
+    }
+
+    fn as_container_mut(&mut self) -> Option<&mut dyn Container> {
+        Some(self)
+    }
+
+    fn reconcile(&mut self, new: &mut dyn Widget) -> bool {
+        let Some(new) = new.as_any_mut().downcast_mut::<Self>() else {
+            return false;
+        };
+        self.children = std::mem::take(&mut new.children);
+        self.items = std::mem::take(&mut new.items);
+        self.layout = std::mem::take(&mut new.layout);
+        true
+    }
+}
+
+impl Container for GridPanel {
+    fn children(&self) -> Vec<ID> {
+        self.children.clone()
+    }
+
+    fn take_children(&mut self) -> Vec<Box<dyn Widget>> {
+        std::mem::take(&mut self.pending)
+    }
+
+    fn reject_child(&mut self, id: ID) {
+        // Rejected children were pushed last, after any widget of the same ID.
+        if let Some(i) = self.children.iter().rposition(|child| *child == id) {
+            self.children.remove(i);
+            self.items.remove(i);
+        }
+    }
+
+    fn measure_children(&self, children: &[Size]) -> Size {
+        self.layout.measure(&self.grid_children(children))
+    }
+
+    fn arrange_children(&self, area: Rect, children: &[Size]) -> Vec<Rect> {
+        self.layout.arrange(area, &self.grid_children(children))
+    }
+}
+
+impl Drawable for GridPanel {
+    fn theme(&mut self, theme: Theme) {
+        self.theme = Some(theme);
+        for child in &mut self.pending {
+            child.theme(theme);
+        }
+    }
+}
+
+impl Layout for GridPanel {
+    fn area(&self) -> Vec<Rect> {
+        vec![]
+    }
+}
+
+impl EventListener for GridPanel {}
+
+impl Object for GridPanel {
+    fn id(&self) -> ID {
+        self.id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        layout::{Padding, Track},
+        testing::Driver,
+        widget::Button,
+    };
+
+    #[test]
+    fn cells_follow_the_window_size() {
+        let mut grid = GridPanel::new("grid");
+        grid.set_layout(
+            Grid::new()
+                .columns([Track::Auto, Track::Fraction(1.0)])
+                .gap(10, 10)
+                .padding(Padding::all(10)),
+        );
+        grid.push(Button::new("label"), GridItem::new(0, 0));
+        grid.push(Button::new("field"), GridItem::new(0, 1));
+        grid.push(Button::new("submit"), GridItem::new(1, 0).span(1, 2));
+
+        let mut driver = Driver::new(grid, 800, 200);
+        let area = |driver: &Driver, id| driver.registry().search(&ID::from(id)).area()[0];
+        assert_eq!(
+            area(&driver, "field"),
+            Rect::from_coordinate(88, 10, 702, 30)
+        );
+        assert_eq!(
+            area(&driver, "submit"),
+            Rect::from_coordinate(10, 50, 780, 30)
+        );
+
+        driver.resize(600, 200);
+        assert_eq!(
+            area(&driver, "field"),
+            Rect::from_coordinate(88, 10, 502, 30)
+        );
+    }
+}
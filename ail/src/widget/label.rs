@@ -0,0 +1,342 @@
+use aom::{Object, ID};
+
+use crate::{
+    layout::{Size, TextMetrics},
+    render::{DisplayList, Item},
+    Rect, Theme,
+};
+
+use super::{Drawable, EventListener, Layout, Widget};
+
+/// Appended to text that had to be cut off
+const ELLIPSIS: &str = "...";
+
+/// How text that is wider than its label is broken into lines
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Wrap {
+    /// Only explicit line breaks start a new line
+    #[default]
+    None,
+    /// Break between words, and inside words that do not fit on a line of their own
+    Word,
+    /// Break between any two characters
+    Character,
+}
+
+/// Horizontal alignment of each line of text
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// Static text
+///
+/// The preferred size of a label is the size of its text without any wrapping.
+/// Wrapping happens within the area the label is given, so a wrapping label needs to be
+/// allowed to grow to show more than one line.
+#[derive(Debug)]
+pub struct Label {
+    id: ID,
+    text: String,
+    theme: Theme,
+    wrap: Wrap,
+    align: TextAlign,
+    ellipsis: bool,
+    area: Rect,
+    lines: Vec<(Rect, String)>,
+}
+
+impl Label {
+    pub fn new(id: &'static str) -> Self {
+        Self {
+            id: ID::from(id),
+            text: String::new(),
+            theme: Theme::LIGHT,
+            wrap: Wrap::None,
+            align: TextAlign::Left,
+            ellipsis: false,
+            area: Rect::from_coordinate(0, 0, 0, 0),
+            lines: vec![],
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn set_text<T>(&mut self, text: T)
+    where
+        T: Into<String>,
+    {
+        self.text = text.into();
+    }
+
+    pub fn set_wrap(&mut self, wrap: Wrap) {
+        self.wrap = wrap;
+    }
+
+    pub fn set_align(&mut self, align: TextAlign) {
+        self.align = align;
+    }
+
+    /// Sets whether text that does not fit ends with `...` instead of being clipped
+    pub fn set_ellipsis(&mut self, ellipsis: bool) {
+        self.ellipsis = ellipsis;
+    }
+
+    /// Breaks the text into the lines that fit in `width` and `height`
+    fn lines(&self, width: u32, height: u32, metrics: &dyn TextMetrics) -> Vec<String> {
+        let mut lines = vec![];
+        for paragraph in self.text.split('\n') {
+            match self.wrap {
+                Wrap::None => lines.push(paragraph.to_string()),
+                Wrap::Word => lines.extend(wrap_words(paragraph, width, metrics)),
+                Wrap::Character => lines.extend(wrap_characters(paragraph, width, metrics)),
+            }
+        }
+
+        let line_height = metrics.line_height().max(1);
+        let visible = ((height / line_height) as usize).max(1);
+        let cut = lines.len() > visible;
+        lines.truncate(visible);
+
+        if self.ellipsis {
+            let last = lines.len() - 1;
+            for (i, line) in lines.iter_mut().enumerate() {
+                if (cut && i == last) || metrics.text_width(line) > width {
+                    *line = ellipsize(line, width, metrics);
+                }
+            }
+        }
+        lines
+    }
+}
+
+impl Widget for Label {
+    fn reconcile(&mut self, new: &mut dyn Widget) -> bool {
+        let Some(new) = new.as_any_mut().downcast_mut::<Self>() else {
+            return false;
+        };
+        self.text = std::mem::take(&mut new.text);
+        self.wrap = new.wrap;
+        self.align = new.align;
+        self.ellipsis = new.ellipsis;
+        true
+    }
+}
+
+impl Layout for Label {
+    fn area(&self) -> Vec<Rect> {
+        vec![self.area]
+    }
+
+    fn measure(&self, metrics: &dyn TextMetrics) -> Size {
+        let lines: Vec<&str> = self.text.split('\n').collect();
+        let width = lines
+            .iter()
+            .map(|line| metrics.text_width(line))
+            .max()
+            .unwrap_or(0);
+        Size::new(width, metrics.line_height() * lines.len() as u32)
+    }
+
+    fn arrange(&mut self, area: Rect, metrics: &dyn TextMetrics) {
+        self.area = area;
+        let line_height = metrics.line_height();
+        self.lines = self
+            .lines(area.width(), area.height(), metrics)
+            .into_iter()
+            .enumerate()
+            .map(|(i, line)| {
+                // Text is drawn centered in its rectangle, which therefore has the width of
+                // the text even if it overflows. Its end is clipped then, like on the left.
+                let width = metrics.text_width(&line);
+                let x = match self.align {
+                    TextAlign::Left => 0,
+                    TextAlign::Center => area.width().saturating_sub(width) / 2,
+                    TextAlign::Right => area.width().saturating_sub(width),
+                };
+                let rect = Rect::from_coordinate(
+                    area.x() + x,
+                    area.y() + i as u32 * line_height,
+                    width,
+                    line_height,
+                );
+                (rect, line)
+            })
+            .collect();
+    }
+}
+
+impl Drawable for Label {
+    fn theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    fn render(&mut self) -> DisplayList {
+        let text = self
+            .lines
+            .iter()
+            .map(|(rect, line)| Item::Text(*rect, self.theme.normal.color, line.clone()));
+        let right = self.area.x() + self.area.width();
+        let bottom = self.area.y() + self.area.height();
+        let overflows = self
+            .lines
+            .iter()
+            .any(|(rect, _)| rect.x() + rect.width() > right || rect.y() + rect.height() > bottom);
+        if !overflows {
+            return text.collect();
+        }
+        let mut list = DisplayList::from(vec![Item::PushClip(self.area)]);
+        list.extend(text);
+        list.push(Item::PopClip);
+        list
+    }
+}
+
+impl EventListener for Label {}
+
+impl Object for Label {
+    fn id(&self) -> ID {
+        self.id
+    }
+}
+
+fn wrap_words(text: &str, width: u32, metrics: &dyn TextMetrics) -> Vec<String> {
+    let mut lines = vec![];
+    let mut line = String::new();
+    for word in text.split(' ') {
+        let candidate = if line.is_empty() {
+            word.to_string()
+        } else {
+            format!("{} {}", line, word)
+        };
+        if metrics.text_width(&candidate) <= width {
+            line = candidate;
+            continue;
+        }
+
+        if !line.is_empty() {
+            lines.push(std::mem::take(&mut line));
+        }
+        if metrics.text_width(word) <= width {
+            line = word.to_string();
+        } else {
+            let mut pieces = wrap_characters(word, width, metrics);
+            line = pieces.pop().unwrap_or_default();
+            lines.extend(pieces);
+        }
+    }
+    lines.push(line);
+    lines
+}
+
+fn wrap_characters(text: &str, width: u32, metrics: &dyn TextMetrics) -> Vec<String> {
+    let mut lines = vec![];
+    let mut line = String::new();
+    for c in text.chars() {
+        line.push(c);
+        // Every line keeps at least one character, even if it does not fit.
+        if metrics.text_width(&line) > width && line.chars().count() > 1 {
+            line.pop();
+            lines.push(std::mem::replace(&mut line, c.to_string()));
+        }
+    }
+    lines.push(line);
+    lines
+}
+
+/// Shortens `text` so that it fits in `width` together with the ellipsis
+fn ellipsize(text: &str, width: u32, metrics: &dyn TextMetrics) -> String {
+    let mut text = text.trim_end().to_string();
+    loop {
+        let candidate = format!("{}{}", text, ELLIPSIS);
+        if text.is_empty() || metrics.text_width(&candidate) <= width {
+            return candidate;
+        }
+        text.pop();
+        text.truncate(text.trim_end().len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::Monospace;
+
+    const METRICS: Monospace = Monospace::new(6, 10);
+
+    fn lines(label: &mut Label, width: u32, height: u32) -> Vec<String> {
+        label.arrange(Rect::from_coordinate(0, 0, width, height), &METRICS);
+        label
+            .render()
+            .into_iter()
+            .filter_map(|item| match item {
+                Item::Text(_, _, text) => Some(text),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn measure() {
+        let mut label = Label::new("label");
+        label.set_text("Hello\nworld!");
+        assert_eq!(label.measure(&METRICS), Size::new(36, 20));
+    }
+
+    #[test]
+    fn wrapping() {
+        let mut label = Label::new("label");
+        label.set_text("the quick brown fox");
+        assert_eq!(lines(&mut label, 60, 100), vec!["the quick brown fox"]);
+
+        label.set_wrap(Wrap::Word);
+        assert_eq!(lines(&mut label, 60, 100), vec!["the quick", "brown fox"]);
+
+        label.set_wrap(Wrap::Character);
+        assert_eq!(
+            lines(&mut label, 36, 100),
+            vec!["the qu", "ick br", "own fo", "x"]
+        );
+    }
+
+    #[test]
+    fn ellipsis_and_alignment() {
+        let mut label = Label::new("label");
+        label.set_text("the quick brown fox");
+        label.set_ellipsis(true);
+        assert_eq!(lines(&mut label, 60, 100), vec!["the qui..."]);
+
+        label.set_wrap(Wrap::Word);
+        assert_eq!(lines(&mut label, 60, 10), vec!["the qui..."]);
+
+        label.set_text("fox");
+        label.set_align(TextAlign::Right);
+        label.arrange(Rect::from_coordinate(10, 0, 60, 10), &METRICS);
+        assert_eq!(label.lines[0].0, Rect::from_coordinate(52, 0, 18, 10));
+    }
+
+    #[test]
+    fn overflowing_text_keeps_its_start() {
+        let mut label = Label::new("label");
+        label.set_text("the quick brown fox");
+        label.arrange(Rect::from_coordinate(10, 0, 60, 10), &METRICS);
+        let items = label.render();
+        assert_eq!(
+            items.items(),
+            [
+                Item::PushClip(Rect::from_coordinate(10, 0, 60, 10)),
+                Item::Text(
+                    Rect::from_coordinate(10, 0, 114, 10),
+                    Theme::LIGHT.normal.color,
+                    "the quick brown fox".to_string()
+                ),
+                Item::PopClip,
+            ]
+        );
+    }
+}
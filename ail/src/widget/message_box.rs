@@ -0,0 +1,443 @@
+use aom::{Object, ID};
+use winit::keyboard::NamedKey;
+
+use crate::{
+    event::KeyEvent,
+    layout::TextMetrics,
+    render::{DisplayList, Item},
+    Color, Key, Rect, Theme,
+};
+
+use super::{Choice, Dialog, Drawable, EventListener, Layout, Widget};
+
+/// Space between the edges of the box and its content
+const PADDING: u32 = 16;
+/// Space between the title, the message and the buttons, and between buttons
+const GAP: u32 = 12;
+const MIN_WIDTH: u32 = 280;
+const BUTTON_WIDTH: u32 = 80;
+const BUTTON_PADDING_X: u32 = 16;
+const BUTTON_PADDING_Y: u32 = 8;
+/// Height of the colored stripe along the top edge of the box
+const STRIPE: u32 = 4;
+/// Dims the window behind the box
+const SCRIM: Color = Color::ARGB(96, 0, 0, 0);
+const WARNING: Color = Color::ARGB(255, 230, 160, 0);
+
+/// The standard kinds of message box
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MessageKind {
+    /// A message acknowledged with OK
+    #[default]
+    Info,
+    /// A warning acknowledged with OK
+    Warning,
+    /// A question answered with OK or Cancel
+    OkCancel,
+    /// A question answered with Yes or No
+    YesNo,
+}
+
+impl MessageKind {
+    fn choices(self) -> &'static [Choice] {
+        match self {
+            MessageKind::Info | MessageKind::Warning => &[Choice::Ok],
+            MessageKind::OkCancel => &[Choice::Ok, Choice::Cancel],
+            MessageKind::YesNo => &[Choice::Yes, Choice::No],
+        }
+    }
+
+    /// The choice made by pressing Escape
+    fn dismiss(self) -> Choice {
+        match self {
+            MessageKind::Info | MessageKind::Warning => Choice::Ok,
+            MessageKind::OkCancel => Choice::Cancel,
+            MessageKind::YesNo => Choice::No,
+        }
+    }
+}
+
+/// A modal box with a message and a row of buttons
+///
+/// Shown with [`crate::Windows::show_dialog`], it covers the window and takes all input
+/// until one of the buttons is chosen, with the mouse, or with the arrow keys and Enter.
+/// Escape chooses Cancel or No. The choice is reported as
+/// [`crate::ApplicationEvent::DialogClosed`].
+#[derive(Debug)]
+pub struct MessageBox {
+    id: ID,
+    kind: MessageKind,
+    title: String,
+    message: String,
+    theme: Theme,
+    /// Button chosen by Enter
+    selected: usize,
+    /// Button the left mouse button went down on
+    pressed: Option<usize>,
+    pointer: (i32, i32),
+    choice: Option<Choice>,
+    window: Rect,
+    frame: Rect,
+    title_area: Rect,
+    lines: Vec<(Rect, String)>,
+    buttons: Vec<Rect>,
+}
+
+impl MessageBox {
+    pub fn new(id: &'static str, kind: MessageKind) -> Self {
+        Self {
+            id: ID::from(id),
+            kind,
+            title: String::new(),
+            message: String::new(),
+            theme: Theme::LIGHT,
+            selected: 0,
+            pressed: None,
+            pointer: (0, 0),
+            choice: None,
+            window: Rect::default(),
+            frame: Rect::default(),
+            title_area: Rect::default(),
+            lines: vec![],
+            buttons: vec![],
+        }
+    }
+
+    pub fn set_title<T>(&mut self, title: T)
+    where
+        T: Into<String>,
+    {
+        self.title = title.into();
+    }
+
+    /// Sets the message, which is broken into lines at line breaks only
+    pub fn set_message<T>(&mut self, message: T)
+    where
+        T: Into<String>,
+    {
+        self.message = message.into();
+    }
+
+    pub fn kind(&self) -> MessageKind {
+        self.kind
+    }
+
+    fn button_at(&self, x: i32, y: i32) -> Option<usize> {
+        self.buttons.iter().position(|area| {
+            x >= area.x() as i32
+                && x < (area.x() + area.width()) as i32
+                && y >= area.y() as i32
+                && y < (area.y() + area.height()) as i32
+        })
+    }
+}
+
+impl Widget for MessageBox {
+    fn as_dialog_mut(&mut self) -> Option<&mut dyn Dialog> {
+        Some(self)
+    }
+}
+
+impl Dialog for MessageBox {
+    fn take_choice(&mut self) -> Option<Choice> {
+        self.choice.take()
+    }
+}
+
+impl Layout for MessageBox {
+    /// The whole window, so that clicks next to the box do not take the focus away from it
+    fn area(&self) -> Vec<Rect> {
+        vec![self.window]
+    }
+
+    /// Centers the box in `area`, which it dims as a whole
+    fn arrange(&mut self, area: Rect, metrics: &dyn TextMetrics) {
+        let line_height = metrics.line_height();
+        let button_height = line_height + BUTTON_PADDING_Y * 2;
+        let choices = self.kind.choices();
+        let widths: Vec<u32> = choices
+            .iter()
+            .map(|c| (metrics.text_width(c.text()) + BUTTON_PADDING_X * 2).max(BUTTON_WIDTH))
+            .collect();
+        let row = widths.iter().sum::<u32>() + GAP * (widths.len() as u32 - 1);
+        let message: Vec<&str> = self.message.lines().collect();
+        let content = message
+            .iter()
+            .map(|line| metrics.text_width(line))
+            .chain([metrics.text_width(&self.title), row])
+            .max()
+            .unwrap_or_default();
+        let title_height = match self.title.is_empty() {
+            true => 0,
+            false => line_height + GAP,
+        };
+        let message_height = match message.is_empty() {
+            true => 0,
+            false => line_height * message.len() as u32 + GAP,
+        };
+
+        let width = (content + PADDING * 2).max(MIN_WIDTH).min(area.width());
+        let height = (STRIPE + PADDING * 2 + title_height + message_height + button_height)
+            .min(area.height());
+        let x = area.x() + (area.width() - width) / 2;
+        let y = area.y() + (area.height() - height) / 2;
+        let inner = width.saturating_sub(PADDING * 2);
+
+        let mut top = y + STRIPE + PADDING;
+        self.title_area = Rect::from_coordinate(x + PADDING, top, inner, line_height);
+        top += title_height;
+        self.lines = message
+            .iter()
+            .enumerate()
+            .map(|(i, line)| {
+                let area = Rect::from_coordinate(
+                    x + PADDING,
+                    top + line_height * i as u32,
+                    metrics.text_width(line).min(inner),
+                    line_height,
+                );
+                (area, line.to_string())
+            })
+            .collect();
+
+        // Buttons are aligned to the right edge.
+        let mut left = (x + width).saturating_sub(PADDING + row).max(x);
+        let bottom = (y + height).saturating_sub(PADDING + button_height);
+        self.buttons = widths
+            .into_iter()
+            .map(|width| {
+                let area = Rect::from_coordinate(left, bottom, width, button_height);
+                left += width + GAP;
+                area
+            })
+            .collect();
+
+        self.window = area;
+        self.frame = Rect::from_coordinate(x, y, width, height);
+    }
+}
+
+impl Drawable for MessageBox {
+    fn theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    fn render(&mut self) -> DisplayList {
+        let theme = self.theme;
+        let stripe = match self.kind {
+            MessageKind::Warning => WARNING,
+            _ => theme.focus,
+        };
+        let (x, y, width, height) = (
+            self.frame.x(),
+            self.frame.y(),
+            self.frame.width(),
+            self.frame.height(),
+        );
+        let mut list = DisplayList::from(vec![
+            Item::Rectangle(
+                Rect::from_coordinate(
+                    self.window.x(),
+                    self.window.y(),
+                    self.window.width(),
+                    self.window.height(),
+                ),
+                SCRIM,
+            ),
+            Item::RoundedRectangle(
+                Rect::from_coordinate(x, y, width, height),
+                4.2,
+                theme.normal.shadow,
+            ),
+            Item::RoundedRectangle(
+                Rect::from_coordinate(
+                    x + 1,
+                    y + 1,
+                    width.saturating_sub(2),
+                    height.saturating_sub(2),
+                ),
+                4.2,
+                theme.normal.bgr,
+            ),
+            Item::Rectangle(
+                Rect::from_coordinate(x + 1, y + 1, width.saturating_sub(2), STRIPE),
+                stripe,
+            ),
+        ]);
+        if !self.title.is_empty() {
+            let area = self.title_area;
+            let text_width = area.width();
+            list.push(Item::Text(
+                Rect::from_coordinate(area.x(), area.y(), text_width, area.height()),
+                theme.normal.color,
+                self.title.clone(),
+            ));
+        }
+        for (area, line) in &self.lines {
+            list.push(Item::Text(
+                Rect::from_coordinate(area.x(), area.y(), area.width(), area.height()),
+                theme.normal.color,
+                line.clone(),
+            ));
+        }
+        for (i, (area, choice)) in self.buttons.iter().zip(self.kind.choices()).enumerate() {
+            let colors = match self.pressed == Some(i) {
+                true => theme.click,
+                false => theme.normal,
+            };
+            let border = match self.selected == i {
+                true => theme.focus,
+                false => colors.shadow,
+            };
+            let (x, y, width, height) = (area.x(), area.y(), area.width(), area.height());
+            list.extend([
+                Item::RoundedRectangle(Rect::from_coordinate(x, y, width, height), 4.2, border),
+                Item::RoundedRectangle(
+                    Rect::from_coordinate(
+                        x + 1,
+                        y + 1,
+                        width.saturating_sub(2),
+                        height.saturating_sub(2),
+                    ),
+                    4.2,
+                    colors.bgr,
+                ),
+                Item::Text(
+                    Rect::from_coordinate(x, y, width, height),
+                    colors.color,
+                    choice.text().to_string(),
+                ),
+            ]);
+        }
+        list
+    }
+}
+
+impl EventListener for MessageBox {
+    /// Chooses the button the mouse button went down on if it is released over it
+    fn on_hover(&mut self) {
+        if let Some(i) = self.pressed.take() {
+            if self.button_at(self.pointer.0, self.pointer.1) == Some(i) {
+                self.choice = Some(self.kind.choices()[i]);
+            }
+        }
+    }
+
+    fn unfocus(&mut self) {
+        self.pressed = None;
+    }
+
+    fn on_press(&mut self, x: i32, y: i32) {
+        self.pointer = (x, y);
+        self.pressed = self.button_at(x, y);
+        if let Some(i) = self.pressed {
+            self.selected = i;
+        }
+    }
+
+    fn on_drag(&mut self, x: i32, y: i32) {
+        self.pointer = (x, y);
+    }
+
+    fn focusable(&self) -> bool {
+        true
+    }
+
+    /// Tab moves between the buttons, the box keeps the focus
+    fn captures_tab(&self) -> bool {
+        true
+    }
+
+    fn on_key_down(&mut self, event: &KeyEvent) {
+        let count = self.kind.choices().len();
+        match &event.key {
+            Key::Named(NamedKey::ArrowLeft) => self.selected = self.selected.saturating_sub(1),
+            Key::Named(NamedKey::ArrowRight) => self.selected = (self.selected + 1).min(count - 1),
+            Key::Named(NamedKey::Tab) => {
+                self.selected = match event.modifiers.shift_key() {
+                    true => (self.selected + count - 1) % count,
+                    false => (self.selected + 1) % count,
+                }
+            }
+            Key::Named(NamedKey::Enter) | Key::Named(NamedKey::Space) if !event.repeat => {
+                self.choice = Some(self.kind.choices()[self.selected]);
+            }
+            Key::Named(NamedKey::Escape) => self.choice = Some(self.kind.dismiss()),
+            _ => {}
+        }
+    }
+}
+
+impl Object for MessageBox {
+    fn id(&self) -> ID {
+        self.id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testing::Driver, ApplicationEvent, MouseButton};
+
+    fn closed(driver: &mut Driver) -> Vec<(ID, Choice)> {
+        driver
+            .take_events()
+            .into_iter()
+            .filter_map(|event| match event {
+                ApplicationEvent::DialogClosed(id, choice) => Some((id, choice)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn blocks_the_window_until_answered() {
+        let mut driver = Driver::new(crate::widget::Button::new("behind"), 400, 300);
+        let mut dialog = MessageBox::new("confirm", MessageKind::YesNo);
+        dialog.set_title("Save changes?");
+        dialog.set_message("The document has been modified.");
+        driver.show_dialog(dialog);
+        driver.step();
+
+        // The button behind the box does not see the click.
+        driver.click(5, 5);
+        assert!(!driver.take_events().iter().any(|event| matches!(
+            event,
+            ApplicationEvent::OnEvent(_, id) if *id == ID::from("behind")
+        )));
+
+        driver.key_press(Key::Named(NamedKey::ArrowRight));
+        driver.key_press(Key::Named(NamedKey::Enter));
+        assert_eq!(closed(&mut driver), vec![(ID::from("confirm"), Choice::No)]);
+        assert!(!driver.registry().contains(ID::from("confirm")));
+
+        // Once the box is gone the window takes input again.
+        driver.click(5, 5);
+        assert!(driver.take_events().iter().any(|event| matches!(
+            event,
+            ApplicationEvent::OnEvent(_, id) if *id == ID::from("behind")
+        )));
+    }
+
+    #[test]
+    fn buttons_and_escape() {
+        let mut driver = Driver::new(crate::widget::Label::new("behind"), 400, 300);
+        driver.show_dialog(MessageBox::new("ask", MessageKind::OkCancel));
+        driver.key_press(Key::Named(NamedKey::Escape));
+        assert_eq!(closed(&mut driver), vec![(ID::from("ask"), Choice::Cancel)]);
+
+        driver.show_dialog(MessageBox::new("ask", MessageKind::OkCancel));
+        let ok = driver
+            .registry()
+            .get::<MessageBox>(ID::from("ask"))
+            .unwrap()
+            .buttons[0];
+        let (x, y) = (ok.x() as i32 + 5, ok.y() as i32 + 5);
+        driver.pointer_move(x, y).press(MouseButton::Left);
+        // Releasing away from the button does not choose it.
+        driver.pointer_move(x - 20, y).release(MouseButton::Left);
+        assert!(closed(&mut driver).is_empty());
+        driver.click(x, y);
+        assert_eq!(closed(&mut driver), vec![(ID::from("ask"), Choice::Ok)]);
+    }
+}
@@ -1,13 +1,30 @@
 mod button;
+mod grid;
+mod label;
+mod message_box;
+mod panel;
+pub(crate) mod preedit;
+mod text_area;
+mod text_input;
 
-use std::fmt::Debug;
+use std::{any::Any, fmt::Debug};
 
 pub use button::*;
+pub use grid::*;
+pub use label::*;
+pub use message_box::*;
+pub use panel::*;
+pub use text_area::*;
+pub use text_input::*;
 
-use acure::Command;
-use aom::Object;
+use aom::{Object, ID};
 
-use crate::{Rect, Theme};
+use crate::{
+    event::KeyEvent,
+    layout::{Size, TextMetrics},
+    render::DisplayList,
+    Rect, Theme,
+};
 
 #[derive(Clone, Copy, Debug)]
 pub enum WidgetState {
@@ -21,18 +38,218 @@ pub trait EventListener {
     fn on_hover(&mut self) {}
     fn unfocus(&mut self) {}
     fn on_update(&mut self) {}
+
+    /// Called with the pointer position in window coordinates when the left button is
+    /// pressed over the widget
+    fn on_press(&mut self, _x: i32, _y: i32) {}
+
+    /// Called when the pointer moves while the left button, pressed over the widget, is held
+    fn on_drag(&mut self, _x: i32, _y: i32) {}
+
+    /// Called when the mouse wheel is turned over the widget, in lines
+    ///
+    /// Positive `dy` scrolls towards the top of the content.
+    fn on_scroll(&mut self, _dx: f32, _dy: f32) {}
+
+    /// Whether the widget responds to the pointer
+    ///
+    /// Disabled widgets still cover the widgets below them.
+    fn enabled(&self) -> bool {
+        true
+    }
+
+    /// Whether the widget can receive keyboard focus, by clicking it or with Tab
+    fn focusable(&self) -> bool {
+        false
+    }
+
+    /// Position of the widget in the Tab order
+    ///
+    /// Widgets with an index come first, in ascending order, followed by the others in
+    /// tree order.
+    fn tab_index(&self) -> Option<u32> {
+        None
+    }
+
+    /// Whether Tab and Shift+Tab are delivered to the widget instead of moving focus
+    ///
+    /// Control+Tab moves focus out of such widgets.
+    fn captures_tab(&self) -> bool {
+        false
+    }
+
+    /// Whether a key press activates the focused widget as if it had been clicked
+    fn activates(&self, _event: &KeyEvent) -> bool {
+        false
+    }
+
+    fn on_focus(&mut self) {}
+
+    fn on_blur(&mut self) {}
+
+    /// Called when a key is pressed, or repeated while held, while the widget has keyboard focus
+    fn on_key_down(&mut self, _event: &KeyEvent) {}
+
+    /// Called when a key is released while the widget has keyboard focus
+    fn on_key_up(&mut self, _event: &KeyEvent) {}
+
+    /// Called after [`EventListener::on_key_down`] with the text the key press produced
+    ///
+    /// Control characters such as Enter and Tab, and presses with Control, Alt or Super held,
+    /// do not produce text. Text committed by an input method is delivered here as well.
+    fn on_char(&mut self, _text: &str) {}
+
+    /// Called while an input method composes text, with the text composed so far
+    ///
+    /// `cursor` is the byte range of `text` the input method highlights, or `None` if no
+    /// caret should be shown. An empty text ends the composition.
+    fn on_preedit(&mut self, _text: &str, _cursor: Option<(usize, usize)>) {}
+
+    /// Whether the user changed the value of the widget since the last call
+    ///
+    /// Asked after input is delivered to the focused widget, a change is reported as
+    /// [`crate::WidgetEvent::OnChange`].
+    fn take_change(&mut self) -> bool {
+        false
+    }
+
+    /// Where text is entered, in window coordinates, while the widget has focus
+    ///
+    /// Input methods are enabled while a widget returning an area has focus, and their
+    /// candidate window is placed next to the area.
+    fn text_input_area(&self) -> Option<Rect> {
+        None
+    }
 }
 
 pub trait Drawable {
     fn theme(&mut self, theme: Theme) {}
 
-    fn render(&mut self) -> Vec<Command> {
-        vec![]
+    /// Describes what the widget draws, in window coordinates
+    fn render(&mut self) -> DisplayList {
+        DisplayList::new()
     }
 }
 
 pub trait Layout {
     fn area(&self) -> Vec<Rect>;
+
+    /// Preferred size of the widget, used by its container during layout
+    fn measure(&self, _metrics: &dyn TextMetrics) -> Size {
+        Size::ZERO
+    }
+
+    /// Places the widget in the area assigned by its container
+    fn arrange(&mut self, _area: Rect, _metrics: &dyn TextMetrics) {}
+}
+
+/// Gives access to a value as [`Any`] so that widgets can be downcast safely
+///
+/// This is implemented for every `'static` type, widgets do not need to implement it themselves.
+pub trait AsAny: Any {
+    fn as_any(&self) -> &dyn Any;
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
 }
 
-pub trait Widget: std::fmt::Debug + Object + Drawable + Layout + EventListener {}
+impl<T> AsAny for T
+where
+    T: Any,
+{
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// A widget that holds other widgets
+pub trait Container {
+    /// IDs of the children in drawing order
+    fn children(&self) -> Vec<ID>;
+
+    /// Hands over the children that have not been registered yet
+    ///
+    /// The registry calls this when the container is registered and stores the
+    /// returned widgets alongside it.
+    fn take_children(&mut self) -> Vec<Box<dyn Widget>> {
+        vec![]
+    }
+
+    /// Stops listing a child handed over by [`Container::take_children`] that was dropped
+    /// because its ID is taken by another widget
+    fn reject_child(&mut self, _id: ID) {}
+
+    /// Computes the preferred size of the container from the preferred sizes of its children
+    ///
+    /// `children` is in the same order as [`Container::children`].
+    /// By default children are stacked on top of each other.
+    fn measure_children(&self, children: &[Size]) -> Size {
+        children.iter().fold(Size::ZERO, |acc, size| {
+            Size::new(acc.width.max(size.width), acc.height.max(size.height))
+        })
+    }
+
+    /// Splits `area` between the children
+    ///
+    /// Returns one rectangle per child. By default every child gets the whole area.
+    fn arrange_children(&self, area: Rect, children: &[Size]) -> Vec<Rect> {
+        vec![area; children.len()]
+    }
+}
+
+/// The answer given to a dialog
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Choice {
+    Ok,
+    Cancel,
+    Yes,
+    No,
+}
+
+impl Choice {
+    /// Text of the button making the choice
+    pub fn text(self) -> &'static str {
+        match self {
+            Choice::Ok => "OK",
+            Choice::Cancel => "Cancel",
+            Choice::Yes => "Yes",
+            Choice::No => "No",
+        }
+    }
+}
+
+/// A widget shown over a window that takes all of its input until it is answered
+///
+/// See [`crate::Windows::show_dialog`].
+pub trait Dialog {
+    /// Takes the answer once the user has given one, which closes the dialog
+    fn take_choice(&mut self) -> Option<Choice>;
+}
+
+pub trait Widget: std::fmt::Debug + Object + Drawable + Layout + EventListener + AsAny {
+    /// Returns the widget as a container if it holds children
+    fn as_container(&self) -> Option<&dyn Container> {
+        None
+    }
+
+    fn as_container_mut(&mut self) -> Option<&mut dyn Container> {
+        None
+    }
+
+    /// Returns the widget as a dialog if it can be answered
+    fn as_dialog_mut(&mut self) -> Option<&mut dyn Dialog> {
+        None
+    }
+
+    /// Takes over the properties of `new`, a widget of the same type and ID built for a
+    /// newer view, keeping the state the user gave this one, such as hover or the caret
+    ///
+    /// Returns `false` if the widget should be replaced by `new` instead, which is the default.
+    /// Containers take over the IDs of their new children, which are reconciled on their own.
+    fn reconcile(&mut self, _new: &mut dyn Widget) -> bool {
+        false
+    }
+}
@@ -1,60 +1,266 @@
 use aom::{Object, ID};
 
-use crate::Application;
-
-use super::{Container, Drawable, Layout, Widget};
-
-pub struct PanelBuilder<W> 
-where
-    W: Widget + 'static
-{
-    inner: Vec<Box<W>>
-}
-
-impl<W> PanelBuilder<W> 
-where
-    W: Widget + 'static
-{
-    pub fn build(self,mut application: Application<W>) -> Panel {
-        let registry = application.registry();
-        let mut w_id = vec![];
-        self.inner.iter().map(|i| {
-            w_id.push(i.id());
-        });
-        for i in self.inner {
-            registry.register(i);
+use crate::{
+    layout::{Flex, FlexItem, Size},
+    Rect, Theme,
+};
+
+use super::{Container, Drawable, EventListener, Layout, Widget};
+
+/// A container that groups other widgets
+///
+/// Children are laid out in a row or column as described by its [`Flex`] layout,
+/// which defaults to a column. They are drawn in the order they were pushed.
+/// Once the panel is registered, its children live in the [`WidgetRegistry`] like any
+/// other widget and can be looked up by their IDs.
+/// Children pushed after that are registered when the window is laid out next, so they can
+/// only be looked up from then on. Their IDs must not be taken by other widgets.
+///
+/// [`WidgetRegistry`]: crate::management::WidgetRegistry
+#[derive(Debug)]
+pub struct Panel {
+    id: ID,
+    children: Vec<ID>,
+    items: Vec<FlexItem>,
+    pending: Vec<Box<dyn Widget>>,
+    /// Theme given to the panel, passed on to children pushed later
+    theme: Option<Theme>,
+    layout: Flex,
+}
+
+impl Panel {
+    pub fn new(id: &'static str) -> Self {
+        Self {
+            id: ID::from(id),
+            children: vec![],
+            items: vec![],
+            pending: vec![],
+            theme: None,
+            layout: Flex::default(),
         }
-        Panel {
-            id: ID::from("panel"),
-            w_id
+    }
+
+    pub fn set_layout(&mut self, layout: Flex) {
+        self.layout = layout;
+    }
+
+    /// Adds a child at the end of the panel
+    pub fn push<W>(&mut self, widget: W)
+    where
+        W: Widget + 'static,
+    {
+        self.push_with(widget, FlexItem::default());
+    }
+
+    /// Adds a child at the end of the panel with its own flex parameters
+    pub fn push_with<W>(&mut self, widget: W, item: FlexItem)
+    where
+        W: Widget + 'static,
+    {
+        self.children.push(widget.id());
+        self.items.push(item);
+        let mut widget: Box<dyn Widget> = Box::new(widget);
+        if let Some(theme) = self.theme {
+            widget.theme(theme);
         }
+        self.pending.push(widget);
+    }
+
+    /// Pairs the measured sizes with the flex parameters of the children
+    fn flex_children(&self, sizes: &[Size]) -> Vec<(Size, FlexItem)> {
+        sizes
+            .iter()
+            .copied()
+            .zip(self.items.iter().copied())
+            .collect()
     }
 }
 
-#[derive(Debug)]
-pub struct Panel {
-    id: ID,
-    w_id: Vec<ID>
+impl Widget for Panel {
+    fn as_container(&self) -> Option<&dyn Container> {
+        Some(self)
+    }
+
+    fn as_container_mut(&mut self) -> Option<&mut dyn Container> {
+        Some(self)
+    }
+
+    fn reconcile(&mut self, new: &mut dyn Widget) -> bool {
+        let Some(new) = new.as_any_mut().downcast_mut::<Self>() else {
+            return false;
+        };
+        self.children = std::mem::take(&mut new.children);
+        self.items = std::mem::take(&mut new.items);
+        self.layout = std::mem::take(&mut new.layout);
+        true
+    }
 }
 
 impl Container for Panel {
-    fn format(&mut self) -> Vec<aom::ID> {
-        vec![]
+    fn children(&self) -> Vec<ID> {
+        self.children.clone()
+    }
+
+    fn take_children(&mut self) -> Vec<Box<dyn Widget>> {
+        std::mem::take(&mut self.pending)
+    }
+
+    fn reject_child(&mut self, id: ID) {
+        // Rejected children were pushed last, after any widget of the same ID.
+        if let Some(i) = self.children.iter().rposition(|child| *child == id) {
+            self.children.remove(i);
+            self.items.remove(i);
+        }
+    }
+
+    fn measure_children(&self, children: &[Size]) -> Size {
+        self.layout.measure(&self.flex_children(children))
+    }
+
+    fn arrange_children(&self, area: Rect, children: &[Size]) -> Vec<Rect> {
+        self.layout.arrange(area, &self.flex_children(children))
     }
 }
 
 impl Drawable for Panel {
-
+    fn theme(&mut self, theme: Theme) {
+        self.theme = Some(theme);
+        for child in &mut self.pending {
+            child.theme(theme);
+        }
+    }
 }
 
 impl Layout for Panel {
-    fn area(&self) -> Vec<crate::Rect> {
+    fn area(&self) -> Vec<Rect> {
         vec![]
     }
 }
 
+impl EventListener for Panel {}
+
 impl Object for Panel {
-    fn id(&self) -> aom::ID {
+    fn id(&self) -> ID {
         self.id
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        layout::{Monospace, Padding},
+        management::{RegistryError, WidgetRegistry},
+        testing::Driver,
+        widget::Button,
+        ApplicationEvent, WidgetEvent,
+    };
+
+    #[test]
+    fn descendants_are_registered() {
+        let mut inner = Panel::new("inner");
+        inner.push(Button::new("button"));
+        let mut outer = Panel::new("outer");
+        outer.push(inner);
+
+        let mut registry = WidgetRegistry::new();
+        registry.register(outer);
+
+        assert!(registry.get::<Button>(ID::from("button")).is_some());
+        assert_eq!(registry.parent(ID::from("button")), Some(ID::from("inner")));
+        assert_eq!(registry.parent(ID::from("inner")), Some(ID::from("outer")));
+        assert_eq!(
+            registry.descendants(ID::from("outer")),
+            vec![ID::from("outer"), ID::from("inner"), ID::from("button")]
+        );
+    }
+
+    #[test]
+    fn children_pushed_later_are_registered() {
+        let mut driver = Driver::new(Panel::new("panel"), 300, 80);
+        driver.step();
+        driver
+            .registry()
+            .get_mut::<Panel>(ID::from("panel"))
+            .unwrap()
+            .push(Button::new("late"));
+        driver.step();
+        assert_eq!(
+            driver.registry().parent(ID::from("late")),
+            Some(ID::from("panel"))
+        );
+
+        driver.click(20, 20);
+        assert!(driver.events().iter().any(|e| matches!(
+            e,
+            ApplicationEvent::OnEvent(WidgetEvent::OnClick, id) if *id == ID::from("late")
+        )));
+    }
+
+    #[test]
+    fn children_with_taken_ids_are_dropped() {
+        let mut panel = Panel::new("panel");
+        panel.push(Button::new("button"));
+        let mut driver = Driver::new(panel, 300, 80);
+        driver.step();
+        driver
+            .registry()
+            .get_mut::<Panel>(ID::from("panel"))
+            .unwrap()
+            .push(Button::new("button"));
+        driver.step();
+
+        let registry = driver.registry();
+        let panel = registry.get::<Panel>(ID::from("panel")).unwrap();
+        assert_eq!(panel.children(), vec![ID::from("button")]);
+        assert!(driver.events().iter().any(|e| matches!(
+            e,
+            ApplicationEvent::WidgetRejected(RegistryError::Duplicate(id))
+                if *id == ID::from("button")
+        )));
+    }
+
+    #[test]
+    fn clicks_reach_children() {
+        let mut panel = Panel::new("panel");
+        panel.push(Button::new("button"));
+
+        let mut driver = Driver::new(panel, 300, 80);
+        driver.click(20, 20);
+        assert!(driver.events().iter().any(|e| matches!(
+            e,
+            ApplicationEvent::OnEvent(WidgetEvent::OnClick, id) if *id == ID::from("button")
+        )));
+    }
+
+    #[test]
+    fn children_follow_the_window_size() {
+        let mut panel = Panel::new("panel");
+        panel.set_layout(Flex::row().gap(10).padding(Padding::all(10)));
+        panel.push(Button::new("fixed"));
+        panel.push_with(Button::new("grows"), FlexItem::new().grow(1.0));
+
+        let mut driver = Driver::new(panel, 600, 60);
+        let area = |driver: &Driver, id| driver.registry().search(&ID::from(id)).area()[0];
+        assert_eq!(
+            area(&driver, "fixed"),
+            Rect::from_coordinate(10, 10, 68, 30)
+        );
+        assert_eq!(
+            area(&driver, "grows"),
+            Rect::from_coordinate(88, 10, 502, 30)
+        );
+
+        driver.resize(800, 60);
+        assert_eq!(
+            area(&driver, "grows"),
+            Rect::from_coordinate(88, 10, 702, 30)
+        );
+        assert_eq!(
+            driver
+                .registry()
+                .measure(ID::from("panel"), &Monospace::new(6, 10)),
+            Size::new(166, 50)
+        );
+    }
+}
@@ -0,0 +1,89 @@
+use crate::{
+    layout::TextMetrics,
+    render::{DisplayList, Item},
+    Rect, Theme,
+};
+
+/// Text being composed by an input method, shown over the caret until it is committed
+///
+/// The composition is drawn in a box of its own at the caret, underlined, with the caret
+/// the input method asks for. Text after the caret is hidden under the box meanwhile.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Preedit {
+    text: String,
+    /// Byte range highlighted by the input method
+    cursor: Option<(usize, usize)>,
+    width: u32,
+    cursor_x: Option<u32>,
+}
+
+impl Preedit {
+    pub(crate) fn set(&mut self, text: &str, cursor: Option<(usize, usize)>) {
+        self.text = text.to_string();
+        self.cursor = cursor.filter(|(start, end)| {
+            start <= end && text.is_char_boundary(*start) && text.is_char_boundary(*end)
+        });
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.set("", None);
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    pub(crate) fn arrange(&mut self, metrics: &dyn TextMetrics) {
+        self.width = metrics.text_width(&self.text);
+        self.cursor_x = self
+            .cursor
+            .map(|(start, _)| metrics.text_width(&self.text[..start]));
+    }
+
+    /// Draws the composition at the caret `x`, keeping it within the horizontal `bounds` if it fits
+    pub(crate) fn render(
+        &self,
+        x: u32,
+        y: u32,
+        line_height: u32,
+        bounds: (u32, u32),
+        theme: &Theme,
+    ) -> DisplayList {
+        if self.is_empty() {
+            return DisplayList::new();
+        }
+        let x = self.left(x, bounds);
+        let mut list = DisplayList::from(vec![
+            Item::Rectangle(
+                Rect::from_coordinate(x, y, self.width, line_height),
+                theme.normal.bgr,
+            ),
+            Item::Text(
+                Rect::from_coordinate(x, y, self.width, line_height),
+                theme.normal.color,
+                self.text.clone(),
+            ),
+            Item::Rectangle(
+                Rect::from_coordinate(x, y + line_height.saturating_sub(1), self.width, 1),
+                theme.normal.color,
+            ),
+        ]);
+        if let Some(cursor_x) = self.cursor_x {
+            list.push(Item::Rectangle(
+                Rect::from_coordinate(x + cursor_x, y, 1, line_height),
+                theme.normal.color,
+            ));
+        }
+        list
+    }
+
+    /// Area of the composition, or of the caret when there is none, for placing the input
+    /// method's candidate window
+    pub(crate) fn area(&self, x: u32, y: u32, line_height: u32, bounds: (u32, u32)) -> Rect {
+        Rect::from_coordinate(self.left(x, bounds), y, self.width.max(1), line_height)
+    }
+
+    fn left(&self, x: u32, (left, right): (u32, u32)) -> u32 {
+        x.min(right.saturating_sub(self.width)).max(left)
+    }
+}
@@ -0,0 +1,147 @@
+use std::ops::Range;
+
+/// Text stored in a gap buffer, indexed by characters
+///
+/// Edits close to each other only move the characters between them, and the start of
+/// every line is tracked so that lines can be found without scanning the text.
+#[derive(Clone, Debug)]
+pub(crate) struct GapBuffer {
+    chars: Vec<char>,
+    gap: Range<usize>,
+    /// Position of the first character of every line
+    lines: Vec<usize>,
+}
+
+impl GapBuffer {
+    pub fn new() -> Self {
+        Self {
+            chars: vec![],
+            gap: 0..0,
+            lines: vec![0],
+        }
+    }
+
+    /// Number of characters
+    pub fn len(&self) -> usize {
+        self.chars.len() - self.gap.len()
+    }
+
+    pub fn char_at(&self, position: usize) -> char {
+        match position < self.gap.start {
+            true => self.chars[position],
+            false => self.chars[position + self.gap.len()],
+        }
+    }
+
+    pub fn slice(&self, range: Range<usize>) -> String {
+        range.map(|i| self.char_at(i)).collect()
+    }
+
+    pub fn text(&self) -> String {
+        self.slice(0..self.len())
+    }
+
+    pub fn insert(&mut self, position: usize, text: &str) {
+        let text: Vec<char> = text.chars().collect();
+        self.reserve(text.len());
+        self.move_gap(position);
+        self.chars[self.gap.start..self.gap.start + text.len()].copy_from_slice(&text);
+        self.gap.start += text.len();
+
+        let line = self.line_of(position);
+        for start in &mut self.lines[line + 1..] {
+            *start += text.len();
+        }
+        let starts = text
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| **c == '\n')
+            .map(|(i, _)| position + i + 1);
+        self.lines.splice(line + 1..line + 1, starts);
+    }
+
+    /// Removes the characters in `range` and returns them
+    pub fn remove(&mut self, range: Range<usize>) -> String {
+        let removed = self.slice(range.clone());
+        self.move_gap(range.start);
+        self.gap.end += range.len();
+
+        // Lines starting after a removed line break are merged into the previous line.
+        let first = self.lines.partition_point(|start| *start <= range.start);
+        let last = self.lines.partition_point(|start| *start <= range.end);
+        self.lines.drain(first..last);
+        for start in &mut self.lines[first..] {
+            *start -= range.len();
+        }
+        removed
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Index of the line containing `position`
+    pub fn line_of(&self, position: usize) -> usize {
+        self.lines.partition_point(|start| *start <= position) - 1
+    }
+
+    pub fn line_start(&self, line: usize) -> usize {
+        self.lines[line]
+    }
+
+    /// Position of the end of the line, before its line break
+    pub fn line_end(&self, line: usize) -> usize {
+        match self.lines.get(line + 1) {
+            Some(next) => next - 1,
+            None => self.len(),
+        }
+    }
+
+    fn move_gap(&mut self, position: usize) {
+        let gap = self.gap.clone();
+        if position < gap.start {
+            let n = gap.start - position;
+            self.chars.copy_within(position..gap.start, gap.end - n);
+            self.gap = position..gap.end - n;
+        } else if position > gap.start {
+            let n = position - gap.start;
+            self.chars.copy_within(gap.end..gap.end + n, gap.start);
+            self.gap = gap.start + n..gap.end + n;
+        }
+    }
+
+    /// Makes sure the gap can hold `additional` characters
+    fn reserve(&mut self, additional: usize) {
+        if self.gap.len() >= additional {
+            return;
+        }
+        let grow = additional.max(self.chars.len()).max(64);
+        self.chars
+            .splice(self.gap.end..self.gap.end, std::iter::repeat_n('\0', grow));
+        self.gap.end += grow;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edits_keep_lines() {
+        let mut buffer = GapBuffer::new();
+        buffer.insert(0, "one\nthree");
+        buffer.insert(4, "two\n");
+        assert_eq!(buffer.text(), "one\ntwo\nthree");
+        assert_eq!(buffer.line_count(), 3);
+        assert_eq!((buffer.line_start(2), buffer.line_end(2)), (8, 13));
+        assert_eq!(buffer.line_of(7), 1);
+
+        assert_eq!(buffer.remove(2..9), "e\ntwo\nt");
+        assert_eq!(buffer.text(), "onhree");
+        assert_eq!(buffer.line_count(), 1);
+
+        buffer.insert(6, "\n");
+        assert_eq!(buffer.line_of(7), 1);
+        assert_eq!((buffer.line_start(1), buffer.line_end(1)), (7, 7));
+    }
+}
@@ -0,0 +1,860 @@
+mod buffer;
+
+use std::ops::Range;
+
+use aom::{Object, ID};
+use winit::keyboard::NamedKey;
+
+use crate::{
+    event::KeyEvent,
+    layout::{Size, TextMetrics},
+    render::{DisplayList, Item},
+    Color, Key, Rect, Theme,
+};
+
+use self::buffer::GapBuffer;
+
+use super::{preedit::Preedit, Drawable, EventListener, Layout, Widget, WidgetState};
+
+/// Space between the text and the edges
+const PADDING: u32 = 6;
+/// Number of characters that fit in a line of the preferred size
+const WIDTH_CHARS: usize = 40;
+/// Number of lines that fit in the preferred size
+const HEIGHT_LINES: u32 = 10;
+/// Rows scrolled by one step of the mouse wheel
+const WHEEL_ROWS: f32 = 3.0;
+
+/// A line of text as shown on screen
+///
+/// Lines longer than the editor are wrapped into several rows.
+#[derive(Clone, Debug)]
+struct Row {
+    line: usize,
+    start: usize,
+    end: usize,
+    /// Horizontal position of every caret position from `start` to `end`
+    offsets: Vec<u32>,
+}
+
+impl Row {
+    fn x(&self, position: usize) -> u32 {
+        self.offsets[position.clamp(self.start, self.end) - self.start]
+    }
+
+    /// Finds the caret position closest to `x`
+    fn position_at(&self, x: i32) -> usize {
+        (0..self.offsets.len())
+            .min_by_key(|i| (self.offsets[*i] as i32 - x).abs())
+            .unwrap_or(0)
+            + self.start
+    }
+}
+
+/// A change to the text that can be undone
+#[derive(Clone, Debug)]
+struct Edit {
+    position: usize,
+    removed: String,
+    inserted: String,
+    /// Caret and selection anchor before the change
+    before: (usize, usize),
+}
+
+/// Multi-line editable text
+///
+/// Text is kept in a gap buffer, so editing large documents only touches the text around
+/// the caret, and only the rows that are visible are wrapped and drawn.
+/// Besides the keys understood by [`TextInput`], Up, Down, Page Up and Page Down move
+/// between rows, Tab inserts spaces up to the next tab stop, Control+Z undoes and
+/// Control+Y or Control+Shift+Z redoes.
+/// Since Tab is taken, Control+Tab moves focus to the next widget.
+/// Text composed with an input method is shown at the caret until it is committed.
+///
+/// Positions such as [`TextArea::caret`] count characters, not bytes.
+///
+/// [`TextInput`]: super::TextInput
+#[derive(Debug)]
+pub struct TextArea {
+    id: ID,
+    buffer: GapBuffer,
+    caret: usize,
+    anchor: usize,
+    wrap: bool,
+    tab_width: usize,
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+    focused: bool,
+    theme: Theme,
+    state: WidgetState,
+    area: Rect,
+    tab_index: Option<u32>,
+    line_height: u32,
+    /// First visible row, as a line and the row within that line
+    top: (usize, usize),
+    /// Rows to scroll by at the next layout
+    scroll: i32,
+    /// Whether the next layout should scroll the caret into view
+    reveal: bool,
+    /// Horizontal position kept while moving up and down
+    goal_x: Option<u32>,
+    /// Wrapped rows from the line before the first visible row to the line after the last
+    rows: Vec<Row>,
+    first_visible: usize,
+    visible: usize,
+    preedit: Preedit,
+    /// Whether the text was edited since the last [`EventListener::take_change`]
+    changed: bool,
+}
+
+impl TextArea {
+    pub fn new(id: &'static str) -> Self {
+        Self {
+            id: ID::from(id),
+            buffer: GapBuffer::new(),
+            caret: 0,
+            anchor: 0,
+            wrap: true,
+            tab_width: 4,
+            undo: vec![],
+            redo: vec![],
+            focused: false,
+            theme: Theme::LIGHT,
+            state: WidgetState::Unfocus,
+            area: Rect::from_coordinate(0, 0, 0, 0),
+            tab_index: None,
+            line_height: 0,
+            top: (0, 0),
+            scroll: 0,
+            reveal: false,
+            goal_x: None,
+            rows: vec![],
+            first_visible: 0,
+            visible: 0,
+            preedit: Preedit::default(),
+            changed: false,
+        }
+    }
+
+    pub fn text(&self) -> String {
+        self.buffer.text()
+    }
+
+    /// Replaces the text, moving the caret to the start and forgetting the undo history
+    pub fn set_text<T>(&mut self, text: T)
+    where
+        T: AsRef<str>,
+    {
+        self.buffer = GapBuffer::new();
+        self.buffer.insert(0, text.as_ref());
+        self.undo.clear();
+        self.redo.clear();
+        self.top = (0, 0);
+        self.move_to(0, false);
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.buffer.line_count()
+    }
+
+    /// Sets whether lines wider than the editor are wrapped or cut off
+    pub fn set_wrap(&mut self, wrap: bool) {
+        self.wrap = wrap;
+    }
+
+    /// Sets the number of columns between two tab stops
+    pub fn set_tab_width(&mut self, tab_width: usize) {
+        self.tab_width = tab_width.max(1);
+    }
+
+    /// Sets the position of the editor in the Tab order
+    pub fn set_tab_index(&mut self, tab_index: Option<u32>) {
+        self.tab_index = tab_index;
+    }
+
+    /// Position of the caret
+    pub fn caret(&self) -> usize {
+        self.caret
+    }
+
+    /// Range of the selected characters, if any
+    pub fn selection(&self) -> Option<Range<usize>> {
+        match self.caret == self.anchor {
+            true => None,
+            false => Some(self.caret.min(self.anchor)..self.caret.max(self.anchor)),
+        }
+    }
+
+    pub fn selected_text(&self) -> String {
+        self.selection()
+            .map(|range| self.buffer.slice(range))
+            .unwrap_or_default()
+    }
+
+    /// Selects the characters in `range`, leaving the caret at its end
+    pub fn select(&mut self, range: Range<usize>) {
+        let len = self.buffer.len();
+        self.anchor = range.start.min(len);
+        self.caret = range.end.min(len);
+        self.reveal = true;
+    }
+
+    pub fn select_all(&mut self) {
+        self.select(0..self.buffer.len());
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Reverts the last edit
+    pub fn undo(&mut self) {
+        if let Some(edit) = self.undo.pop() {
+            let inserted = edit.inserted.chars().count();
+            self.buffer.remove(edit.position..edit.position + inserted);
+            self.buffer.insert(edit.position, &edit.removed);
+            self.caret = edit.before.0;
+            self.anchor = edit.before.1;
+            self.reveal = true;
+            self.changed = true;
+            self.redo.push(edit);
+        }
+    }
+
+    /// Applies the last undone edit again
+    pub fn redo(&mut self) {
+        if let Some(edit) = self.redo.pop() {
+            let removed = edit.removed.chars().count();
+            self.buffer.remove(edit.position..edit.position + removed);
+            self.buffer.insert(edit.position, &edit.inserted);
+            self.move_to(edit.position + edit.inserted.chars().count(), false);
+            self.changed = true;
+            self.undo.push(edit);
+        }
+    }
+
+    /// Replaces `range` with `text` and records the change for undo
+    ///
+    /// With `coalesce`, typing continues the previous edit, so whole words are undone at once.
+    fn replace(&mut self, range: Range<usize>, text: &str, coalesce: bool) {
+        if range.is_empty() && text.is_empty() {
+            return;
+        }
+        let continues = coalesce
+            && range.is_empty()
+            && self.undo.last().is_some_and(|last| {
+                last.removed.is_empty()
+                    && last.position + last.inserted.chars().count() == range.start
+                    && !last.inserted.ends_with(char::is_whitespace)
+            });
+
+        let removed = self.buffer.remove(range.clone());
+        self.buffer.insert(range.start, text);
+        match self.undo.last_mut() {
+            Some(last) if continues => last.inserted.push_str(text),
+            _ => self.undo.push(Edit {
+                position: range.start,
+                removed,
+                inserted: text.to_string(),
+                before: (self.caret, self.anchor),
+            }),
+        }
+        self.redo.clear();
+        self.move_to(range.start + text.chars().count(), false);
+        self.changed = true;
+    }
+
+    /// Replaces the selection, or inserts at the caret
+    fn insert(&mut self, text: &str, coalesce: bool) {
+        let range = self.selection().unwrap_or(self.caret..self.caret);
+        self.replace(range, text, coalesce);
+    }
+
+    fn move_to(&mut self, position: usize, select: bool) {
+        self.caret = position.min(self.buffer.len());
+        if !select {
+            self.anchor = self.caret;
+        }
+        self.reveal = true;
+        self.goal_x = None;
+    }
+
+    /// Start of the word before the caret
+    fn previous_word(&self) -> usize {
+        let mut i = self.caret;
+        while i > 0 && self.buffer.char_at(i - 1).is_whitespace() {
+            i -= 1;
+        }
+        while i > 0 && !self.buffer.char_at(i - 1).is_whitespace() {
+            i -= 1;
+        }
+        i
+    }
+
+    /// End of the word after the caret
+    fn next_word(&self) -> usize {
+        let len = self.buffer.len();
+        let mut i = self.caret;
+        while i < len && self.buffer.char_at(i).is_whitespace() {
+            i += 1;
+        }
+        while i < len && !self.buffer.char_at(i).is_whitespace() {
+            i += 1;
+        }
+        i
+    }
+
+    /// Index in [`TextArea::rows`] of the row showing `position`
+    fn row_of(&self, position: usize) -> Option<usize> {
+        self.rows.iter().position(|row| {
+            let last = self.buffer.line_end(row.line) == row.end;
+            row.start <= position && (position < row.end || (last && position == row.end))
+        })
+    }
+
+    /// Moves the caret by `rows` rows, keeping its horizontal position
+    fn move_rows(&mut self, rows: isize, select: bool) {
+        let Some(current) = self.row_of(self.caret) else {
+            return;
+        };
+        let x = self
+            .goal_x
+            .unwrap_or_else(|| self.rows[current].x(self.caret));
+        let target = current as isize + rows;
+        let position = if target < 0 {
+            0
+        } else if target as usize >= self.rows.len() {
+            self.buffer.len()
+        } else {
+            self.rows[target as usize].position_at(x as i32)
+        };
+        self.move_to(position, select);
+        self.goal_x = Some(x);
+    }
+
+    /// Moves the caret by `lines` lines, keeping its column
+    fn move_lines(&mut self, lines: isize, select: bool) {
+        let line = self.buffer.line_of(self.caret);
+        let column = self.caret - self.buffer.line_start(line);
+        let target = (line as isize + lines).clamp(0, self.buffer.line_count() as isize - 1);
+        let start = self.buffer.line_start(target as usize);
+        let end = self.buffer.line_end(target as usize);
+        self.move_to((start + column).min(end), select);
+    }
+
+    fn inner_width(&self) -> u32 {
+        self.area.width().saturating_sub(PADDING * 2)
+    }
+
+    /// Window coordinates of the caret and the horizontal bounds of the text
+    ///
+    /// Returns `None` while the caret is scrolled out of view.
+    fn caret_position(&self) -> Option<(u32, u32, (u32, u32))> {
+        let row = self.row_of(self.caret)?;
+        let visible = row.checked_sub(self.first_visible)?;
+        if visible >= self.visible {
+            return None;
+        }
+        let inner = self.inner_width();
+        let left = self.area.x() + PADDING;
+        Some((
+            left + self.rows[row].x(self.caret).min(inner),
+            self.area.y() + PADDING + visible as u32 * self.line_height,
+            (left, left + inner),
+        ))
+    }
+
+    /// Breaks a line into the rows it is shown as
+    fn wrap_line(&self, line: usize, metrics: &dyn TextMetrics) -> Vec<Row> {
+        let width = self.inner_width();
+        let start = self.buffer.line_start(line);
+        let end = self.buffer.line_end(line);
+
+        let mut rows = vec![];
+        let mut row_start = start;
+        let mut offsets = vec![0];
+        let mut x = 0;
+        // Position after the last space of the row, where the row is preferably broken
+        let mut space = None;
+        for position in start..end {
+            let c = self.buffer.char_at(position);
+            let w = metrics.text_width(c.encode_utf8(&mut [0; 4]));
+            while self.wrap && x + w > width && position > row_start {
+                let split = space.filter(|s| *s > row_start).unwrap_or(position);
+                let tail = offsets.split_off(split - row_start);
+                offsets.push(tail[0]);
+                rows.push(Row {
+                    line,
+                    start: row_start,
+                    end: split,
+                    offsets,
+                });
+                offsets = tail.iter().map(|o| o - tail[0]).collect();
+                x = *offsets.last().unwrap();
+                row_start = split;
+                space = None;
+            }
+            x += w;
+            offsets.push(x);
+            if c.is_whitespace() {
+                space = Some(position + 1);
+            }
+        }
+        rows.push(Row {
+            line,
+            start: row_start,
+            end,
+            offsets,
+        });
+        rows
+    }
+
+    /// Index of the row showing `position` among the rows of its line
+    fn row_in_line(&self, position: usize, metrics: &dyn TextMetrics) -> usize {
+        let rows = self.wrap_line(self.buffer.line_of(position), metrics);
+        rows.iter()
+            .rposition(|row| row.start <= position)
+            .unwrap_or(0)
+    }
+
+    /// Moves the first visible row up or down by `rows` rows
+    fn scroll_rows(&mut self, rows: i32, metrics: &dyn TextMetrics) {
+        let (mut line, mut row) = self.top;
+        for _ in 0..rows.unsigned_abs() {
+            if rows > 0 {
+                if row + 1 < self.wrap_line(line, metrics).len() {
+                    row += 1;
+                } else if line + 1 < self.buffer.line_count() {
+                    line += 1;
+                    row = 0;
+                }
+            } else if row > 0 {
+                row -= 1;
+            } else if line > 0 {
+                line -= 1;
+                row = self.wrap_line(line, metrics).len() - 1;
+            }
+        }
+        self.top = (line, row);
+    }
+}
+
+impl Widget for TextArea {
+    /// A new text replaces the undo history, the caret is kept where it is if the text allows
+    fn reconcile(&mut self, new: &mut dyn Widget) -> bool {
+        let Some(new) = new.as_any_mut().downcast_mut::<Self>() else {
+            return false;
+        };
+        self.wrap = new.wrap;
+        self.tab_width = new.tab_width;
+        self.tab_index = new.tab_index;
+        if self.text() != new.text() {
+            let caret = self.caret;
+            self.set_text(new.text());
+            self.move_to(caret, false);
+        }
+        true
+    }
+}
+
+impl Layout for TextArea {
+    fn area(&self) -> Vec<Rect> {
+        vec![self.area]
+    }
+
+    fn measure(&self, metrics: &dyn TextMetrics) -> Size {
+        Size::new(
+            metrics.text_width(&"0".repeat(WIDTH_CHARS)) + PADDING * 2,
+            metrics.line_height() * HEIGHT_LINES + PADDING * 2,
+        )
+    }
+
+    fn arrange(&mut self, area: Rect, metrics: &dyn TextMetrics) {
+        self.area = area;
+        self.line_height = metrics.line_height().max(1);
+        self.preedit.arrange(metrics);
+        self.visible =
+            ((area.height().saturating_sub(PADDING * 2) / self.line_height) as usize).max(1);
+
+        // The text may have changed since the last layout.
+        let line = self.top.0.min(self.buffer.line_count() - 1);
+        let row = self.top.1.min(self.wrap_line(line, metrics).len() - 1);
+        self.top = (line, row);
+        let scroll = std::mem::take(&mut self.scroll);
+        self.scroll_rows(scroll, metrics);
+
+        if std::mem::take(&mut self.reveal) {
+            let caret = (
+                self.buffer.line_of(self.caret),
+                self.row_in_line(self.caret, metrics),
+            );
+            if caret < self.top {
+                self.top = caret;
+            } else if caret.0 - self.top.0 <= self.visible {
+                let mut distance = 0;
+                for line in self.top.0..caret.0 {
+                    distance += self.wrap_line(line, metrics).len();
+                }
+                let distance = distance + caret.1 - self.top.1;
+                if distance >= self.visible {
+                    self.top = caret;
+                    self.scroll_rows(1 - self.visible as i32, metrics);
+                }
+            } else {
+                self.top = caret;
+                self.scroll_rows(1 - self.visible as i32, metrics);
+            }
+        }
+
+        let mut rows = vec![];
+        if self.top.0 > 0 {
+            rows.extend(self.wrap_line(self.top.0 - 1, metrics));
+        }
+        self.first_visible = rows.len() + self.top.1;
+        let mut line = self.top.0;
+        while line < self.buffer.line_count() {
+            rows.extend(self.wrap_line(line, metrics));
+            line += 1;
+            // One more line is kept below the visible rows to move the caret into.
+            if rows.len() > self.first_visible + self.visible {
+                if line < self.buffer.line_count() {
+                    rows.extend(self.wrap_line(line, metrics));
+                }
+                break;
+            }
+        }
+        self.rows = rows;
+    }
+}
+
+impl Drawable for TextArea {
+    fn theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    fn render(&mut self) -> DisplayList {
+        let area = self.area;
+        let shadow = match (self.focused, self.state) {
+            (true, _) | (_, WidgetState::Hover) => self.theme.hover.shadow,
+            _ => self.theme.normal.shadow,
+        };
+        let mut list = DisplayList::from(vec![
+            Item::RoundedRectangle(
+                Rect::from_coordinate(area.x(), area.y(), area.width(), area.height()),
+                2.0,
+                shadow,
+            ),
+            Item::RoundedRectangle(
+                Rect::from_coordinate(
+                    area.x() + 1,
+                    area.y() + 1,
+                    area.width().saturating_sub(2),
+                    area.height().saturating_sub(2),
+                ),
+                2.0,
+                self.theme.normal.bgr,
+            ),
+        ]);
+
+        let inner = self.inner_width();
+        let x = area.x() + PADDING;
+        let selection = self.selection();
+        let [_, r, g, b] = self.theme.hover.shadow.to_argb();
+        let last = (self.first_visible + self.visible).min(self.rows.len());
+        for (i, row) in self.rows[self.first_visible.min(last)..last]
+            .iter()
+            .enumerate()
+        {
+            let y = area.y() + PADDING + i as u32 * self.line_height;
+
+            if let Some(selection) = &selection {
+                let start = selection.start.max(row.start);
+                let end = selection.end.min(row.end);
+                if start < end {
+                    let left = row.x(start).min(inner);
+                    let right = row.x(end).min(inner);
+                    list.push(Item::Rectangle(
+                        Rect::from_coordinate(x + left, y, right - left, self.line_height),
+                        Color::ARGB(96, r, g, b),
+                    ));
+                }
+            }
+
+            // Rows that are not wrapped are cut off after the last character that fits.
+            let end = (row.start..=row.end)
+                .take_while(|position| row.x(*position) <= inner)
+                .last()
+                .unwrap_or(row.start);
+            if end > row.start {
+                list.push(Item::Text(
+                    Rect::from_coordinate(x, y, row.x(end), self.line_height),
+                    self.theme.normal.color,
+                    self.buffer.slice(row.start..end),
+                ));
+            }
+
+            let composing = !self.preedit.is_empty();
+            if self.focused && !composing && self.row_of(self.caret) == Some(self.first_visible + i)
+            {
+                list.push(Item::Rectangle(
+                    Rect::from_coordinate(x + row.x(self.caret).min(inner), y, 1, self.line_height),
+                    self.theme.normal.color,
+                ));
+            }
+        }
+        if let Some((caret, y, bounds)) = self.caret_position() {
+            list.extend(
+                self.preedit
+                    .render(caret, y, self.line_height, bounds, &self.theme),
+            );
+        }
+        list
+    }
+}
+
+impl EventListener for TextArea {
+    fn on_hover(&mut self) {
+        self.state = WidgetState::Hover;
+    }
+
+    fn unfocus(&mut self) {
+        self.state = WidgetState::Unfocus;
+    }
+
+    fn on_press(&mut self, x: i32, y: i32) {
+        let row = (y - (self.area.y() + PADDING) as i32).max(0) as u32 / self.line_height.max(1);
+        let index = (self.first_visible + row as usize).min(self.rows.len().saturating_sub(1));
+        if let Some(row) = self.rows.get(index) {
+            let position = row.position_at(x - (self.area.x() + PADDING) as i32);
+            self.move_to(position, false);
+        }
+    }
+
+    fn on_drag(&mut self, x: i32, y: i32) {
+        let anchor = self.anchor;
+        self.on_press(x, y);
+        self.anchor = anchor;
+    }
+
+    fn on_scroll(&mut self, _dx: f32, dy: f32) {
+        self.scroll -= (dy * WHEEL_ROWS).round() as i32;
+    }
+
+    fn focusable(&self) -> bool {
+        true
+    }
+
+    fn tab_index(&self) -> Option<u32> {
+        self.tab_index
+    }
+
+    fn captures_tab(&self) -> bool {
+        true
+    }
+
+    fn on_focus(&mut self) {
+        self.focused = true;
+    }
+
+    fn on_blur(&mut self) {
+        self.focused = false;
+        self.preedit.clear();
+    }
+
+    fn on_key_down(&mut self, event: &KeyEvent) {
+        let select = event.modifiers.shift_key();
+        let word = event.modifiers.control_key();
+        match &event.key {
+            Key::Named(NamedKey::ArrowLeft) => {
+                let position = match (word, self.selection()) {
+                    (true, _) => self.previous_word(),
+                    (false, Some(range)) if !select => range.start,
+                    (false, _) => self.caret.saturating_sub(1),
+                };
+                self.move_to(position, select);
+            }
+            Key::Named(NamedKey::ArrowRight) => {
+                let position = match (word, self.selection()) {
+                    (true, _) => self.next_word(),
+                    (false, Some(range)) if !select => range.end,
+                    (false, _) => self.caret + 1,
+                };
+                self.move_to(position, select);
+            }
+            Key::Named(NamedKey::ArrowUp) => self.move_rows(-1, select),
+            Key::Named(NamedKey::ArrowDown) => self.move_rows(1, select),
+            Key::Named(NamedKey::PageUp) => self.move_lines(-(self.visible as isize), select),
+            Key::Named(NamedKey::PageDown) => self.move_lines(self.visible as isize, select),
+            Key::Named(NamedKey::Home) if word => self.move_to(0, select),
+            Key::Named(NamedKey::End) if word => self.move_to(self.buffer.len(), select),
+            Key::Named(NamedKey::Home) => {
+                if let Some(row) = self.row_of(self.caret) {
+                    self.move_to(self.rows[row].start, select);
+                }
+            }
+            Key::Named(NamedKey::End) => {
+                if let Some(row) = self.row_of(self.caret).map(|i| &self.rows[i]) {
+                    // The end of a wrapped row is the start of the next one.
+                    let end = match self.buffer.line_end(row.line) == row.end {
+                        true => row.end,
+                        false => row.end - 1,
+                    };
+                    self.move_to(end, select);
+                }
+            }
+            Key::Named(NamedKey::Backspace) => {
+                let range = self.selection().unwrap_or_else(|| match word {
+                    true => self.previous_word()..self.caret,
+                    false => self.caret.saturating_sub(1)..self.caret,
+                });
+                self.replace(range, "", false);
+            }
+            Key::Named(NamedKey::Delete) => {
+                let range = self.selection().unwrap_or_else(|| match word {
+                    true => self.caret..self.next_word(),
+                    false => self.caret..(self.caret + 1).min(self.buffer.len()),
+                });
+                self.replace(range, "", false);
+            }
+            Key::Named(NamedKey::Enter) => self.insert("\n", false),
+            Key::Named(NamedKey::Tab) => {
+                let start = self.selection().map_or(self.caret, |range| range.start);
+                let column = start - self.buffer.line_start(self.buffer.line_of(start));
+                let spaces = self.tab_width - column % self.tab_width;
+                self.insert(&" ".repeat(spaces), false);
+            }
+            Key::Character(c) if word && c.eq_ignore_ascii_case("a") => self.select_all(),
+            Key::Character(c) if word && c.eq_ignore_ascii_case("z") && select => self.redo(),
+            Key::Character(c) if word && c.eq_ignore_ascii_case("z") => self.undo(),
+            Key::Character(c) if word && c.eq_ignore_ascii_case("y") => self.redo(),
+            _ => {}
+        }
+    }
+
+    fn on_char(&mut self, text: &str) {
+        self.insert(text, true);
+    }
+
+    fn on_preedit(&mut self, text: &str, cursor: Option<(usize, usize)>) {
+        self.preedit.set(text, cursor);
+        self.reveal = true;
+    }
+
+    fn take_change(&mut self) -> bool {
+        std::mem::take(&mut self.changed)
+    }
+
+    fn text_input_area(&self) -> Option<Rect> {
+        let left = self.area.x() + PADDING;
+        let (x, y, bounds) = self.caret_position().unwrap_or((
+            left,
+            self.area.y() + PADDING,
+            (left, left + self.inner_width()),
+        ));
+        Some(self.preedit.area(x, y, self.line_height, bounds))
+    }
+}
+
+impl Object for TextArea {
+    fn id(&self) -> ID {
+        self.id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{layout::Monospace, testing::Driver, Modifiers};
+
+    const METRICS: Monospace = Monospace::new(6, 10);
+
+    fn press(area: &mut TextArea, key: NamedKey, modifiers: Modifiers) {
+        area.on_key_down(&KeyEvent::new(Key::Named(key), modifiers));
+    }
+
+    fn type_text(area: &mut TextArea, text: &str) {
+        for c in text.chars() {
+            area.on_char(&c.to_string());
+        }
+    }
+
+    #[test]
+    fn undo_groups_words() {
+        let mut area = TextArea::new("area");
+        type_text(&mut area, "hello world");
+        press(&mut area, NamedKey::Enter, Modifiers::empty());
+        press(&mut area, NamedKey::Tab, Modifiers::empty());
+        assert_eq!(area.text(), "hello world\n    ");
+
+        let undo = KeyEvent::new(Key::Character("z".into()), Modifiers::CONTROL);
+        area.on_key_down(&undo);
+        area.on_key_down(&undo);
+        assert_eq!(area.text(), "hello world");
+        area.on_key_down(&undo);
+        assert_eq!(area.text(), "hello ");
+        assert_eq!(area.caret(), 6);
+
+        area.on_key_down(&KeyEvent::new(
+            Key::Character("y".into()),
+            Modifiers::CONTROL,
+        ));
+        assert_eq!(area.text(), "hello world");
+        type_text(&mut area, "!");
+        assert!(!area.can_redo());
+    }
+
+    #[test]
+    fn wrapped_rows() {
+        let mut area = TextArea::new("area");
+        area.set_text("the quick brown fox\njumps");
+        // Ten characters fit in a row.
+        area.arrange(Rect::from_coordinate(0, 0, 72, 52), &METRICS);
+        let rows: Vec<(usize, usize)> = area.rows.iter().map(|r| (r.start, r.end)).collect();
+        assert_eq!(rows, vec![(0, 10), (10, 19), (20, 25)]);
+
+        area.move_to(3, false);
+        press(&mut area, NamedKey::ArrowDown, Modifiers::empty());
+        assert_eq!(area.caret(), 13);
+        press(&mut area, NamedKey::ArrowDown, Modifiers::SHIFT);
+        assert_eq!(area.selected_text(), "wn fox\njum");
+        press(&mut area, NamedKey::End, Modifiers::empty());
+        assert_eq!(area.caret(), 25);
+    }
+
+    #[test]
+    fn only_visible_lines_are_drawn() {
+        let text: Vec<String> = (0..10_000).map(|i| format!("line {}", i)).collect();
+        let mut area = TextArea::new("area");
+        area.set_text(text.join("\n"));
+
+        let mut driver = Driver::new(area, 300, 112);
+        let written = |driver: &Driver| {
+            let mut registry = driver.registry();
+            let area = registry.get_mut::<TextArea>(ID::from("area")).unwrap();
+            area.render()
+                .into_iter()
+                .filter_map(|command| match command {
+                    Item::Text(_, _, text) => Some(text),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        driver.step();
+        assert_eq!(written(&driver).len(), 10);
+        assert_eq!(written(&driver)[0], "line 0");
+
+        driver.click(20, 20);
+        driver
+            .key_press(Key::Named(NamedKey::End))
+            .modifiers(Modifiers::CONTROL);
+        driver.key_press(Key::Named(NamedKey::End)).step();
+        assert_eq!(written(&driver).last().unwrap(), "line 9999");
+
+        driver.modifiers(Modifiers::empty()).scroll(0.0, 1.0).step();
+        assert_eq!(written(&driver).last().unwrap(), "line 9996");
+    }
+}
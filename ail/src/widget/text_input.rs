@@ -0,0 +1,626 @@
+use std::ops::Range;
+
+use aom::{Object, ID};
+use winit::keyboard::NamedKey;
+
+use crate::{
+    event::KeyEvent,
+    layout::{Size, TextMetrics},
+    render::{DisplayList, Item},
+    Color, Key, Rect, Theme,
+};
+
+use super::{preedit::Preedit, Drawable, EventListener, Layout, Widget, WidgetState};
+
+/// Space between the text and the left and right edges
+const PADDING_X: u32 = 6;
+/// Space between the text and the top and bottom edges
+const PADDING_Y: u32 = 8;
+/// Number of characters that fit in the preferred width
+const WIDTH_CHARS: usize = 20;
+
+/// A single line of editable text
+///
+/// Clicking the input gives it keyboard focus. The caret is moved with the arrow keys,
+/// Home and End, or by clicking, and holding Shift or dragging with the mouse selects text.
+/// Holding Control moves and deletes whole words, and Control+A selects everything.
+/// Text composed with an input method is shown at the caret until it is committed.
+///
+/// Positions such as [`TextInput::caret`] count characters, not bytes.
+#[derive(Debug)]
+pub struct TextInput {
+    id: ID,
+    text: String,
+    placeholder: String,
+    max_length: Option<usize>,
+    caret: usize,
+    anchor: usize,
+    focused: bool,
+    theme: Theme,
+    state: WidgetState,
+    area: Rect,
+    tab_index: Option<u32>,
+    line_height: u32,
+    /// Width of the text up to each caret position
+    offsets: Vec<u32>,
+    placeholder_width: u32,
+    scroll: u32,
+    preedit: Preedit,
+    /// Whether the user edited the text since the last [`EventListener::take_change`]
+    changed: bool,
+}
+
+impl TextInput {
+    pub fn new(id: &'static str) -> Self {
+        Self {
+            id: ID::from(id),
+            text: String::new(),
+            placeholder: String::new(),
+            max_length: None,
+            caret: 0,
+            anchor: 0,
+            focused: false,
+            theme: Theme::LIGHT,
+            state: WidgetState::Unfocus,
+            area: Rect::from_coordinate(0, 0, 0, 0),
+            tab_index: None,
+            line_height: 0,
+            offsets: vec![0],
+            placeholder_width: 0,
+            scroll: 0,
+            preedit: Preedit::default(),
+            changed: false,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replaces the text and moves the caret to its end
+    pub fn set_text<T>(&mut self, text: T)
+    where
+        T: Into<String>,
+    {
+        let text: String = text.into();
+        self.text = match self.max_length {
+            Some(max) => text.chars().take(max).collect(),
+            None => text,
+        };
+        self.caret = self.len();
+        self.anchor = self.caret;
+    }
+
+    /// Sets the text shown in grey while the input is empty
+    pub fn set_placeholder<T>(&mut self, placeholder: T)
+    where
+        T: Into<String>,
+    {
+        self.placeholder = placeholder.into();
+    }
+
+    /// Limits the number of characters that can be entered
+    pub fn set_max_length(&mut self, max_length: Option<usize>) {
+        self.max_length = max_length;
+        if let Some(max) = max_length {
+            if self.len() > max {
+                self.set_text(self.text.chars().take(max).collect::<String>());
+            }
+        }
+    }
+
+    /// Sets the position of the input in the Tab order
+    pub fn set_tab_index(&mut self, tab_index: Option<u32>) {
+        self.tab_index = tab_index;
+    }
+
+    /// Position of the caret
+    pub fn caret(&self) -> usize {
+        self.caret
+    }
+
+    /// Range of the selected characters, if any
+    pub fn selection(&self) -> Option<Range<usize>> {
+        match self.caret == self.anchor {
+            true => None,
+            false => Some(self.caret.min(self.anchor)..self.caret.max(self.anchor)),
+        }
+    }
+
+    pub fn selected_text(&self) -> &str {
+        match self.selection() {
+            Some(range) => &self.text[self.byte(range.start)..self.byte(range.end)],
+            None => "",
+        }
+    }
+
+    /// Selects the characters in `range`, leaving the caret at its end
+    pub fn select(&mut self, range: Range<usize>) {
+        let len = self.len();
+        self.anchor = range.start.min(len);
+        self.caret = range.end.min(len);
+    }
+
+    pub fn select_all(&mut self) {
+        self.select(0..self.len());
+    }
+
+    fn len(&self) -> usize {
+        self.text.chars().count()
+    }
+
+    /// Converts a character position to a byte index into the text
+    fn byte(&self, position: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(position)
+            .map(|(i, _)| i)
+            .unwrap_or(self.text.len())
+    }
+
+    fn move_to(&mut self, position: usize, select: bool) {
+        self.caret = position.min(self.len());
+        if !select {
+            self.anchor = self.caret;
+        }
+    }
+
+    /// Start of the word before the caret
+    fn previous_word(&self) -> usize {
+        let chars: Vec<char> = self.text.chars().collect();
+        let mut i = self.caret;
+        while i > 0 && chars[i - 1].is_whitespace() {
+            i -= 1;
+        }
+        while i > 0 && !chars[i - 1].is_whitespace() {
+            i -= 1;
+        }
+        i
+    }
+
+    /// End of the word after the caret
+    fn next_word(&self) -> usize {
+        let chars: Vec<char> = self.text.chars().collect();
+        let mut i = self.caret;
+        while i < chars.len() && chars[i].is_whitespace() {
+            i += 1;
+        }
+        while i < chars.len() && !chars[i].is_whitespace() {
+            i += 1;
+        }
+        i
+    }
+
+    fn delete(&mut self, range: Range<usize>) {
+        let (start, end) = (self.byte(range.start), self.byte(range.end));
+        self.text.replace_range(start..end, "");
+        self.caret = range.start;
+        self.anchor = range.start;
+        self.changed |= start < end;
+    }
+
+    /// Deletes the selected text, returning whether there was any
+    fn delete_selection(&mut self) -> bool {
+        match self.selection() {
+            Some(range) => {
+                self.delete(range);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Replaces the selection with `text`, as far as the maximum length allows
+    fn insert(&mut self, text: &str) {
+        self.delete_selection();
+        let room = self
+            .max_length
+            .map(|max| max.saturating_sub(self.len()))
+            .unwrap_or(usize::MAX);
+        let text: String = text
+            .chars()
+            .filter(|c| !c.is_control())
+            .take(room)
+            .collect();
+        let at = self.byte(self.caret);
+        self.text.insert_str(at, &text);
+        self.move_to(self.caret + text.chars().count(), false);
+        self.changed |= !text.is_empty();
+    }
+
+    /// Width of the text up to the caret position
+    fn offset(&self, position: usize) -> u32 {
+        self.offsets
+            .get(position)
+            .or(self.offsets.last())
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Finds the caret position closest to the window coordinate `x`
+    fn position_at(&self, x: i32) -> usize {
+        let x = x - (self.area.x() + PADDING_X) as i32 + self.scroll as i32;
+        (0..self.offsets.len().min(self.len() + 1))
+            .min_by_key(|i| (self.offsets[*i] as i32 - x).abs())
+            .unwrap_or(0)
+    }
+
+    fn inner_width(&self) -> u32 {
+        self.area.width().saturating_sub(PADDING_X * 2)
+    }
+
+    /// Window coordinates of the caret and the horizontal bounds of the text
+    fn caret_position(&self) -> (u32, u32, (u32, u32)) {
+        let inner = self.inner_width();
+        let left = self.area.x() + PADDING_X;
+        let offset = self
+            .offset(self.caret)
+            .clamp(self.scroll, self.scroll + inner);
+        let y = self.area.y() + self.area.height().saturating_sub(self.line_height) / 2;
+        (left + offset - self.scroll, y, (left, left + inner))
+    }
+}
+
+impl Widget for TextInput {
+    /// The caret and selection are kept where they are as long as the text allows
+    fn reconcile(&mut self, new: &mut dyn Widget) -> bool {
+        let Some(new) = new.as_any_mut().downcast_mut::<Self>() else {
+            return false;
+        };
+        self.placeholder = std::mem::take(&mut new.placeholder);
+        self.tab_index = new.tab_index;
+        self.max_length = new.max_length;
+        if self.text != new.text {
+            let (caret, anchor) = (self.caret, self.anchor);
+            self.text = std::mem::take(&mut new.text);
+            self.move_to(anchor, false);
+            self.move_to(caret, true);
+        }
+        true
+    }
+}
+
+impl Layout for TextInput {
+    fn area(&self) -> Vec<Rect> {
+        vec![self.area]
+    }
+
+    fn measure(&self, metrics: &dyn TextMetrics) -> Size {
+        let width = metrics
+            .text_width(&"0".repeat(WIDTH_CHARS))
+            .max(metrics.text_width(&self.placeholder));
+        Size::new(width + PADDING_X * 2, metrics.line_height() + PADDING_Y * 2)
+    }
+
+    fn arrange(&mut self, area: Rect, metrics: &dyn TextMetrics) {
+        self.area = area;
+        self.line_height = metrics.line_height();
+        self.placeholder_width = metrics.text_width(&self.placeholder);
+        self.preedit.arrange(metrics);
+        self.offsets = std::iter::once(0)
+            .chain(self.text.chars().scan(0, |x, c| {
+                *x += metrics.text_width(c.encode_utf8(&mut [0; 4]));
+                Some(*x)
+            }))
+            .collect();
+
+        // Keep the caret in view, without leaving empty space after the end of the text.
+        let inner = self.inner_width();
+        let caret = self.offset(self.caret);
+        let end = self.offset(self.len());
+        self.scroll = self.scroll.min(end.saturating_sub(inner));
+        if caret < self.scroll {
+            self.scroll = caret;
+        } else if caret > self.scroll + inner {
+            self.scroll = caret - inner;
+        }
+    }
+}
+
+impl Drawable for TextInput {
+    fn theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
+
+    fn render(&mut self) -> DisplayList {
+        let area = self.area;
+        let shadow = match (self.focused, self.state) {
+            (true, _) | (_, WidgetState::Hover) => self.theme.hover.shadow,
+            _ => self.theme.normal.shadow,
+        };
+        let mut list = DisplayList::from(vec![
+            Item::RoundedRectangle(
+                Rect::from_coordinate(area.x(), area.y(), area.width(), area.height()),
+                2.0,
+                shadow,
+            ),
+            Item::RoundedRectangle(
+                Rect::from_coordinate(
+                    area.x() + 1,
+                    area.y() + 1,
+                    area.width().saturating_sub(2),
+                    area.height().saturating_sub(2),
+                ),
+                2.0,
+                self.theme.normal.bgr,
+            ),
+        ]);
+
+        let inner = self.inner_width();
+        let x = area.x() + PADDING_X;
+        let y = area.y() + area.height().saturating_sub(self.line_height) / 2;
+        // Clamps a text offset to the visible part of the input.
+        let visible = |offset: u32| offset.clamp(self.scroll, self.scroll + inner) - self.scroll;
+
+        if let Some(range) = self.selection() {
+            let start = visible(self.offset(range.start));
+            let end = visible(self.offset(range.end));
+            let [_, r, g, b] = self.theme.hover.shadow.to_argb();
+            list.push(Item::Rectangle(
+                Rect::from_coordinate(x + start, y, end - start, self.line_height),
+                Color::ARGB(96, r, g, b),
+            ));
+        }
+
+        if self.text.is_empty() {
+            list.push(Item::Text(
+                Rect::from_coordinate(x, y, self.placeholder_width.min(inner), self.line_height),
+                self.theme.normal.shadow,
+                self.placeholder.clone(),
+            ));
+        } else {
+            // Only characters that are completely visible are drawn.
+            let first = (0..=self.len())
+                .find(|i| self.offset(*i) >= self.scroll)
+                .unwrap_or(0);
+            let last = (first..=self.len())
+                .take_while(|i| self.offset(*i) <= self.scroll + inner)
+                .last()
+                .unwrap_or(first);
+            if last > first {
+                list.push(Item::Text(
+                    Rect::from_coordinate(
+                        x + self.offset(first) - self.scroll,
+                        y,
+                        self.offset(last) - self.offset(first),
+                        self.line_height,
+                    ),
+                    self.theme.normal.color,
+                    self.text[self.byte(first)..self.byte(last)].to_string(),
+                ));
+            }
+        }
+
+        let (caret, y, bounds) = self.caret_position();
+        if self.focused && self.preedit.is_empty() {
+            list.push(Item::Rectangle(
+                Rect::from_coordinate(caret, y, 1, self.line_height),
+                self.theme.normal.color,
+            ));
+        }
+        list.extend(
+            self.preedit
+                .render(caret, y, self.line_height, bounds, &self.theme),
+        );
+        list
+    }
+}
+
+impl EventListener for TextInput {
+    fn on_hover(&mut self) {
+        self.state = WidgetState::Hover;
+    }
+
+    fn unfocus(&mut self) {
+        self.state = WidgetState::Unfocus;
+    }
+
+    fn on_press(&mut self, x: i32, _y: i32) {
+        self.move_to(self.position_at(x), false);
+    }
+
+    fn on_drag(&mut self, x: i32, _y: i32) {
+        self.move_to(self.position_at(x), true);
+    }
+
+    fn focusable(&self) -> bool {
+        true
+    }
+
+    fn tab_index(&self) -> Option<u32> {
+        self.tab_index
+    }
+
+    fn on_focus(&mut self) {
+        self.focused = true;
+    }
+
+    fn on_blur(&mut self) {
+        self.focused = false;
+        self.anchor = self.caret;
+        self.preedit.clear();
+    }
+
+    fn on_key_down(&mut self, event: &KeyEvent) {
+        let select = event.modifiers.shift_key();
+        let word = event.modifiers.control_key();
+        match &event.key {
+            Key::Named(NamedKey::ArrowLeft) => {
+                let position = match (word, self.selection()) {
+                    (true, _) => self.previous_word(),
+                    (false, Some(range)) if !select => range.start,
+                    (false, _) => self.caret.saturating_sub(1),
+                };
+                self.move_to(position, select);
+            }
+            Key::Named(NamedKey::ArrowRight) => {
+                let position = match (word, self.selection()) {
+                    (true, _) => self.next_word(),
+                    (false, Some(range)) if !select => range.end,
+                    (false, _) => self.caret + 1,
+                };
+                self.move_to(position, select);
+            }
+            Key::Named(NamedKey::Home) => self.move_to(0, select),
+            Key::Named(NamedKey::End) => self.move_to(self.len(), select),
+            Key::Named(NamedKey::Backspace) => {
+                let range = self.selection().unwrap_or_else(|| match word {
+                    true => self.previous_word()..self.caret,
+                    false => self.caret.saturating_sub(1)..self.caret,
+                });
+                self.delete(range);
+            }
+            Key::Named(NamedKey::Delete) => {
+                let range = self.selection().unwrap_or_else(|| match word {
+                    true => self.caret..self.next_word(),
+                    false => self.caret..(self.caret + 1).min(self.len()),
+                });
+                self.delete(range);
+            }
+            Key::Character(c) if word && c.eq_ignore_ascii_case("a") => self.select_all(),
+            _ => {}
+        }
+    }
+
+    fn on_char(&mut self, text: &str) {
+        self.insert(text);
+    }
+
+    fn on_preedit(&mut self, text: &str, cursor: Option<(usize, usize)>) {
+        self.preedit.set(text, cursor);
+    }
+
+    fn take_change(&mut self) -> bool {
+        std::mem::take(&mut self.changed)
+    }
+
+    fn text_input_area(&self) -> Option<Rect> {
+        let (x, y, bounds) = self.caret_position();
+        Some(self.preedit.area(x, y, self.line_height, bounds))
+    }
+}
+
+impl Object for TextInput {
+    fn id(&self) -> ID {
+        self.id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        layout::{Flex, Padding},
+        testing::Driver,
+        widget::Panel,
+        Modifiers,
+    };
+
+    fn press(input: &mut TextInput, key: NamedKey, modifiers: Modifiers) {
+        input.on_key_down(&KeyEvent::new(Key::Named(key), modifiers));
+    }
+
+    #[test]
+    fn editing() {
+        let mut input = TextInput::new("input");
+        input.set_text("hello brave world");
+
+        press(&mut input, NamedKey::ArrowLeft, Modifiers::CONTROL);
+        assert_eq!(input.caret(), 12);
+        press(
+            &mut input,
+            NamedKey::ArrowLeft,
+            Modifiers::CONTROL | Modifiers::SHIFT,
+        );
+        assert_eq!(input.selected_text(), "brave ");
+        press(&mut input, NamedKey::Backspace, Modifiers::empty());
+        assert_eq!(input.text(), "hello world");
+
+        press(&mut input, NamedKey::Home, Modifiers::empty());
+        press(&mut input, NamedKey::Delete, Modifiers::CONTROL);
+        assert_eq!(input.text(), " world");
+        press(&mut input, NamedKey::End, Modifiers::SHIFT);
+        assert_eq!(input.selection(), Some(0..6));
+        press(&mut input, NamedKey::ArrowLeft, Modifiers::empty());
+        assert_eq!((input.caret(), input.selection()), (0, None));
+    }
+
+    #[test]
+    fn max_length() {
+        let mut input = TextInput::new("input");
+        input.set_max_length(Some(5));
+        input.on_char("abcd");
+        input.on_char("éfg");
+        assert_eq!(input.text(), "abcdé");
+
+        input.select(1..3);
+        input.on_char("xyz");
+        assert_eq!(input.text(), "axydé");
+    }
+
+    #[test]
+    fn typing_and_mouse_selection() {
+        let mut panel = Panel::new("panel");
+        panel.set_layout(Flex::column().padding(Padding::all(10)));
+        panel.push(TextInput::new("input"));
+        let mut driver = Driver::new(panel, 300, 80);
+
+        // Keys are ignored until the input has focus.
+        driver.type_text("ignored");
+        driver.click(20, 20).type_text("hello world");
+        let text = |driver: &Driver| {
+            let registry = driver.registry();
+            let input = registry.get::<TextInput>(ID::from("input")).unwrap();
+            (input.text().to_string(), input.selected_text().to_string())
+        };
+        assert_eq!(text(&driver), ("hello world".into(), "".into()));
+
+        // The text starts at x = 10 + 6 and every character is 6 pixels wide.
+        driver
+            .pointer_move(16 + 6 * 6, 20)
+            .press(crate::MouseButton::Left)
+            .pointer_move(16 + 6 * 11, 20)
+            .release(crate::MouseButton::Left);
+        assert_eq!(text(&driver), ("hello world".into(), "world".into()));
+
+        driver.type_text("there");
+        assert_eq!(text(&driver), ("hello there".into(), "".into()));
+    }
+
+    #[test]
+    fn composition() {
+        let mut panel = Panel::new("panel");
+        panel.set_layout(Flex::column().padding(Padding::all(10)));
+        panel.push(TextInput::new("input"));
+        let mut driver = Driver::new(panel, 300, 80);
+        assert_eq!(driver.text_input_area(), None);
+
+        driver.click(20, 20).type_text("ab");
+        let written = |driver: &Driver| {
+            let mut registry = driver.registry();
+            let input = registry.get_mut::<TextInput>(ID::from("input")).unwrap();
+            let text = input.text().to_string();
+            let strings: Vec<String> = input
+                .render()
+                .into_iter()
+                .filter_map(|command| match command {
+                    Item::Text(_, _, text) => Some(text),
+                    _ => None,
+                })
+                .collect();
+            (text, strings)
+        };
+
+        driver.ime_preedit("にほ", Some((3, 3))).step();
+        assert_eq!(
+            written(&driver),
+            ("ab".into(), vec!["ab".into(), "にほ".into()])
+        );
+        // The candidate window goes where the composition starts, after 10 + 6 + 2 * 6 pixels.
+        let area = driver.text_input_area().unwrap();
+        assert_eq!((area.x(), area.width()), (28, 12));
+
+        driver.ime_commit("日本").step();
+        assert_eq!(written(&driver), ("ab日本".into(), vec!["ab日本".into()]));
+    }
+}
@@ -0,0 +1,401 @@
+use std::{cell::RefCell, fmt, rc::Rc};
+
+use aom::ID;
+use winit::{
+    dpi::{PhysicalPosition, PhysicalSize},
+    event_loop::{EventLoop, EventLoopWindowTarget},
+};
+
+use crate::{
+    binding::Bindings,
+    render::{DisplayList, Renderer},
+    widget::Widget,
+    WindowLevel, WindowTheme,
+};
+
+pub struct Window {
+    pub(crate) event_loop: Option<EventLoop<()>>,
+    pub(crate) inner: winit::window::Window,
+    /// Taken by the application the window is passed to
+    pub(crate) renderer: Option<Renderer>,
+}
+
+impl Window {
+    /// Opens a window with the default settings
+    ///
+    /// # Panics
+    /// Panics if the window cannot be created, use [`Window::builder`] to handle the error.
+    pub fn new() -> Self {
+        Self::builder().build().unwrap()
+    }
+
+    pub fn builder() -> WindowBuilder {
+        WindowBuilder::new()
+    }
+}
+
+pub type Icon = winit::window::Icon;
+
+/// Settings of a window before it is opened
+///
+/// Sizes and positions are in physical pixels.
+///
+/// ```no_run
+/// use ail::{Window, WindowTheme};
+///
+/// let window = Window::builder()
+///     .title("Counter")
+///     .size(400, 300)
+///     .min_size(200, 150)
+///     .theme(WindowTheme::Dark)
+///     .build();
+/// ```
+#[derive(Clone, Debug)]
+pub struct WindowBuilder {
+    title: String,
+    size: Option<(u32, u32)>,
+    min_size: Option<(u32, u32)>,
+    max_size: Option<(u32, u32)>,
+    position: Option<(i32, i32)>,
+    resizable: bool,
+    decorations: bool,
+    icon: Option<Icon>,
+    level: WindowLevel,
+    theme: Option<WindowTheme>,
+}
+
+impl WindowBuilder {
+    pub fn new() -> Self {
+        Self {
+            title: "ail".to_string(),
+            size: None,
+            min_size: None,
+            max_size: None,
+            position: None,
+            resizable: true,
+            decorations: true,
+            icon: None,
+            level: WindowLevel::Normal,
+            theme: None,
+        }
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Initial size of the client area
+    pub fn size(mut self, width: u32, height: u32) -> Self {
+        self.size = Some((width, height));
+        self
+    }
+
+    pub fn min_size(mut self, width: u32, height: u32) -> Self {
+        self.min_size = Some((width, height));
+        self
+    }
+
+    pub fn max_size(mut self, width: u32, height: u32) -> Self {
+        self.max_size = Some((width, height));
+        self
+    }
+
+    /// Initial position of the window on the desktop, chosen by the system if not set
+    pub fn position(mut self, x: i32, y: i32) -> Self {
+        self.position = Some((x, y));
+        self
+    }
+
+    pub fn resizable(mut self, resizable: bool) -> Self {
+        self.resizable = resizable;
+        self
+    }
+
+    /// Whether the title bar and borders are drawn
+    pub fn decorations(mut self, decorations: bool) -> Self {
+        self.decorations = decorations;
+        self
+    }
+
+    /// Icon shown in the title bar and task bar, created with [`Icon::from_rgba`]
+    pub fn icon(mut self, icon: Icon) -> Self {
+        self.icon = Some(icon);
+        self
+    }
+
+    pub fn level(mut self, level: WindowLevel) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Theme of the title bar and borders, the system theme if not set
+    pub fn theme(mut self, theme: WindowTheme) -> Self {
+        self.theme = Some(theme);
+        self
+    }
+
+    pub fn build(self) -> Result<Window, WindowError> {
+        let event_loop = event_loop()?;
+        let (inner, renderer) = self.open(&event_loop)?;
+        Ok(Window {
+            event_loop: Some(event_loop),
+            inner,
+            renderer: Some(renderer),
+        })
+    }
+
+    /// Opens the window on an event loop that may already be running, together with the
+    /// renderer drawing to it
+    pub(crate) fn open(
+        self,
+        target: &EventLoopWindowTarget<()>,
+    ) -> Result<(winit::window::Window, Renderer), WindowError> {
+        let mut builder = winit::window::WindowBuilder::new()
+            .with_title(self.title)
+            .with_resizable(self.resizable)
+            .with_decorations(self.decorations)
+            .with_window_icon(self.icon)
+            .with_window_level(self.level)
+            .with_theme(self.theme);
+        if let Some((width, height)) = self.size {
+            builder = builder.with_inner_size(PhysicalSize::new(width, height));
+        }
+        if let Some((width, height)) = self.min_size {
+            builder = builder.with_min_inner_size(PhysicalSize::new(width, height));
+        }
+        if let Some((width, height)) = self.max_size {
+            builder = builder.with_max_inner_size(PhysicalSize::new(width, height));
+        }
+        if let Some((x, y)) = self.position {
+            builder = builder.with_position(PhysicalPosition::new(x, y));
+        }
+        let window = builder.build(target)?;
+        let renderer = Renderer::new(&window)?;
+        Ok((window, renderer))
+    }
+}
+
+impl Default for WindowBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug)]
+pub enum WindowError {
+    /// The event loop could not be created, for example because no display is available
+    EventLoop(winit::error::EventLoopError),
+    /// The system refused to open the window
+    Os(winit::error::OsError),
+    /// Nothing can draw to windows of this window system
+    Unsupported(&'static str),
+    /// The drawing surface of the window could not be created, for the given reason
+    Surface(&'static str),
+}
+
+impl fmt::Display for WindowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WindowError::EventLoop(error) => {
+                write!(f, "failed to create the event loop: {}", error)
+            }
+            WindowError::Os(error) => write!(f, "failed to open the window: {}", error),
+            WindowError::Unsupported(system) => {
+                write!(f, "drawing to {} windows is not supported", system)
+            }
+            WindowError::Surface(reason) => {
+                write!(f, "failed to create the drawing surface: {}", reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for WindowError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WindowError::EventLoop(error) => Some(error),
+            WindowError::Os(error) => Some(error),
+            WindowError::Unsupported(_) | WindowError::Surface(_) => None,
+        }
+    }
+}
+
+impl From<winit::error::EventLoopError> for WindowError {
+    fn from(error: winit::error::EventLoopError) -> Self {
+        WindowError::EventLoop(error)
+    }
+}
+
+impl From<winit::error::OsError> for WindowError {
+    fn from(error: winit::error::OsError) -> Self {
+        WindowError::Os(error)
+    }
+}
+
+/// Creates the event loop for the current platform
+///
+/// On Linux the X11 backend is requested explicitly, since rendering is done through Xlib.
+/// Wayland sessions are served by XWayland, without it [`WindowBuilder::build`] fails with
+/// [`WindowError::EventLoop`] since there is no Wayland renderer.
+#[cfg(target_os = "linux")]
+fn event_loop() -> Result<EventLoop<()>, winit::error::EventLoopError> {
+    use winit::platform::x11::EventLoopBuilderExtX11;
+
+    winit::event_loop::EventLoopBuilder::new()
+        .with_x11()
+        .build()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn event_loop() -> Result<EventLoop<()>, winit::error::EventLoopError> {
+    EventLoop::new()
+}
+
+pub(crate) enum Request {
+    Open(WindowBuilder, Box<dyn Widget>, Bindings),
+    Dialog(ID, Box<dyn Widget>),
+    Close(ID),
+    Frame(ID, Box<dyn FnOnce(DisplayList)>),
+}
+
+/// Opens and closes the windows of a running [`crate::Application`]
+///
+/// A window is known by the ID of its root widget. Requests are carried out once the
+/// callback handling the current event returns.
+///
+/// ```no_run
+/// use ail::{
+///     management::Handle,
+///     widget::{Button, Label, Panel},
+///     Application, ApplicationEvent, Window, WindowBuilder,
+/// };
+///
+/// let button = Button::new("inspect");
+/// let inspect = Handle::of(&button).id();
+/// let mut panel = Panel::new("panel");
+/// panel.push(button);
+/// let app = Application::new(Window::new(), panel);
+/// let windows = app.windows();
+/// app.run(move |event, _| {
+///     if let ApplicationEvent::OnEvent(_, id) = event {
+///         if id == inspect {
+///             windows.open(WindowBuilder::new().title("Inspector"), Label::new("inspector"));
+///         }
+///     }
+/// });
+/// ```
+#[derive(Clone, Default)]
+pub struct Windows {
+    requests: Rc<RefCell<Vec<Request>>>,
+}
+
+impl Windows {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Opens a window showing `widget`
+    ///
+    /// Nothing happens if a window with the same root widget is already open.
+    /// [`crate::ApplicationEvent::WindowFailed`] is reported if the window cannot be opened,
+    /// or if two of its widgets share an ID.
+    pub fn open<W>(&self, builder: WindowBuilder, widget: W)
+    where
+        W: Widget + 'static,
+    {
+        self.open_with_bindings(builder, widget, Bindings::new());
+    }
+
+    /// Opens a window showing `widget`, whose widgets follow `bindings`
+    ///
+    /// Bindings belong to a single window, the ones of [`crate::Application::bindings`]
+    /// only reach the widgets of the window the application was created with.
+    pub fn open_with_bindings<W>(&self, builder: WindowBuilder, widget: W, bindings: Bindings)
+    where
+        W: Widget + 'static,
+    {
+        self.requests
+            .borrow_mut()
+            .push(Request::Open(builder, Box::new(widget), bindings));
+    }
+
+    /// Shows `dialog` over the window whose root widget is `window`
+    ///
+    /// The dialog takes all input of the window until it is answered, which is reported
+    /// as [`crate::ApplicationEvent::DialogClosed`]. Dialogs shown while another one is
+    /// open are stacked on top of it.
+    /// The dialog is not shown if one of its IDs is taken by a widget of the window.
+    pub fn show_dialog<W>(&self, window: ID, dialog: W)
+    where
+        W: Widget + 'static,
+    {
+        self.requests
+            .borrow_mut()
+            .push(Request::Dialog(window, Box::new(dialog)));
+    }
+
+    /// Closes the window whose root widget is `id`
+    ///
+    /// Closing the window the application was started with ends the application.
+    pub fn close(&self, id: ID) {
+        self.requests.borrow_mut().push(Request::Close(id));
+    }
+
+    /// Passes what the window whose root widget is `window` shows to `callback`
+    ///
+    /// The list describes the last frame drawn, and is empty before the first one. Its text
+    /// form is a dump to attach to a bug report, see [`DisplayList`]. Nothing happens if
+    /// there is no such window.
+    ///
+    /// ```no_run
+    /// use ail::{
+    ///     shortcut::Scope,
+    ///     widget::{Button, Panel},
+    ///     Application, ApplicationEvent, Window,
+    /// };
+    /// use aom::ID;
+    ///
+    /// let mut panel = Panel::new("panel");
+    /// panel.push(Button::new("button"));
+    /// let mut app = Application::new(Window::new(), panel);
+    /// app.shortcuts()
+    ///     .bind("Ctrl+Shift+D".parse().unwrap(), Scope::Window, ID::from("dump"))
+    ///     .unwrap();
+    /// let windows = app.windows();
+    /// app.run(move |event, _| {
+    ///     if let ApplicationEvent::Shortcut(id) = event {
+    ///         if id == ID::from("dump") {
+    ///             windows.frame(ID::from("panel"), |frame| {
+    ///                 std::fs::write("frame.txt", frame.to_string()).unwrap();
+    ///             });
+    ///         }
+    ///     }
+    /// });
+    /// ```
+    pub fn frame<F>(&self, window: ID, callback: F)
+    where
+        F: FnOnce(DisplayList) + 'static,
+    {
+        self.requests
+            .borrow_mut()
+            .push(Request::Frame(window, Box::new(callback)));
+    }
+
+    /// Removes the oldest pending request
+    pub(crate) fn pop(&self) -> Option<Request> {
+        let mut requests = self.requests.borrow_mut();
+        match requests.is_empty() {
+            true => None,
+            false => Some(requests.remove(0)),
+        }
+    }
+}
+
+impl fmt::Debug for Windows {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Windows")
+            .field("pending", &self.requests.borrow().len())
+            .finish()
+    }
+}
@@ -9,6 +9,12 @@ impl From<&'static str> for ID {
     }
 }
 
+impl std::fmt::Display for ID {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
 impl Into<String> for ID {
     fn into(self) -> String {
         self.0.to_owned()